@@ -0,0 +1,60 @@
+use super::{appdata_dir, xdg_config_home, App};
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+
+/// Audacity preferences (`audacity.cfg`) and plugin settings. Projects and
+/// the autosave/temp data are not part of the backup.
+pub struct Audacity;
+
+impl App for Audacity {
+    fn id(&self) -> &'static str {
+        "audacity"
+    }
+
+    fn name(&self) -> &'static str {
+        "Audacity"
+    }
+
+    fn category(&self) -> &'static str {
+        "Media"
+    }
+
+    fn is_installed(&self) -> bool {
+        self.app_path().map(|p| p.exists()).unwrap_or(false)
+    }
+
+    fn target_hint(&self) -> &'static str {
+        "app:audacity"
+    }
+
+    fn package_id(&self) -> Option<&'static str> {
+        if cfg!(target_os = "windows") {
+            Some("Audacity.Audacity")
+        } else {
+            Some("audacity")
+        }
+    }
+
+    fn app_path(&self) -> Result<PathBuf> {
+        let config_dir = if cfg!(target_os = "windows") {
+            appdata_dir()?
+        } else if cfg!(target_os = "macos") {
+            dirs::home_dir()
+                .ok_or_else(|| anyhow!("Could not get home directory"))?
+                .join("Library/Application Support")
+        } else {
+            xdg_config_home()?
+        };
+        Ok(config_dir.join("audacity"))
+    }
+
+    fn config_path(&self) -> Result<Vec<PathBuf>> {
+        let audacity_dir = self.app_path()?;
+
+        Ok(["audacity.cfg", "pluginsettings.cfg"]
+            .iter()
+            .map(|name| audacity_dir.join(name))
+            .filter(|path| path.is_file())
+            .collect())
+    }
+}
@@ -0,0 +1,196 @@
+use super::{collect_files_recursive, xdg_config_home, App, AppCapabilities};
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+
+/// Cache and crash-report directories of the Chromium profile layout, skipped
+/// for every Chromium-based browser. They are rebuilt on first launch and
+/// often outweigh the settings by orders of magnitude.
+const CHROMIUM_SKIP_DIRS: &[&str] = &[
+    "Cache",
+    "Code Cache",
+    "GPUCache",
+    "DawnCache",
+    "DawnGraphiteCache",
+    "DawnWebGPUCache",
+    "GrShaderCache",
+    "GraphiteDawnCache",
+    "ShaderCache",
+    "Media Cache",
+    "Service Worker",
+    "blob_storage",
+    "Crashpad",
+    "Crash Reports",
+    "component_crx_cache",
+    "extensions_crx_cache",
+    "optimization_guide_model_store",
+    "Safe Browsing",
+];
+
+/// Profile databases encrypted with a key from the OS keyring. A copy can't
+/// be decrypted on another machine, so they are never backed up.
+const CHROMIUM_SKIP_FILES: &[&str] = &[
+    "Cookies",
+    "Cookies-journal",
+    "Login Data",
+    "Login Data-journal",
+    "Login Data For Account",
+    "Login Data For Account-journal",
+    "Web Data",
+    "Web Data-journal",
+];
+
+/// A browser sharing Chromium's `User Data` layout: `Local State` at the
+/// root and one directory per profile (`Default`, `Profile 1`...). Only the
+/// product name and where the root lives differ between variants. Bookmarks,
+/// history and open sessions stay in the backup, so every variant is
+/// treated as sensitive.
+pub struct ChromiumBased {
+    id: &'static str,
+    name: &'static str,
+    target_hint: &'static str,
+    /// winget id on Windows, distribution package elsewhere
+    package_ids: (&'static str, &'static str),
+    /// Package name on Arch, official or in the AUR
+    aur_package_id: &'static str,
+    /// Profile root under `%LOCALAPPDATA%`
+    windows_root: &'static str,
+    /// Profile root under `~/Library/Application Support`
+    macos_root: &'static str,
+    /// Profile root under `$XDG_CONFIG_HOME`
+    linux_root: &'static str,
+}
+
+pub const VIVALDI: ChromiumBased = ChromiumBased {
+    id: "vivaldi",
+    name: "Vivaldi",
+    target_hint: "app:vivaldi",
+    package_ids: ("Vivaldi.Vivaldi", "vivaldi-stable"),
+    aur_package_id: "vivaldi",
+    windows_root: "Vivaldi/User Data",
+    macos_root: "Vivaldi",
+    linux_root: "vivaldi",
+};
+
+pub const EDGE: ChromiumBased = ChromiumBased {
+    id: "edge",
+    name: "Microsoft Edge",
+    target_hint: "app:edge",
+    package_ids: ("Microsoft.Edge", "microsoft-edge-stable"),
+    aur_package_id: "microsoft-edge-stable-bin",
+    windows_root: "Microsoft/Edge/User Data",
+    macos_root: "Microsoft Edge",
+    linux_root: "microsoft-edge",
+};
+
+/// Files of a Chromium profile root worth restoring elsewhere
+fn profile_files(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    collect_files_recursive(root, CHROMIUM_SKIP_DIRS, &mut files)?;
+    files.retain(|path| {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        !CHROMIUM_SKIP_FILES.contains(&name)
+    });
+    Ok(files)
+}
+
+impl App for ChromiumBased {
+    fn id(&self) -> &'static str {
+        self.id
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn category(&self) -> &'static str {
+        "Browser"
+    }
+
+    fn capabilities(&self) -> AppCapabilities {
+        AppCapabilities {
+            sensitive: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_installed(&self) -> bool {
+        self.app_path().map(|p| p.exists()).unwrap_or(false)
+    }
+
+    fn target_hint(&self) -> &'static str {
+        self.target_hint
+    }
+
+    fn package_id(&self) -> Option<&'static str> {
+        if cfg!(target_os = "windows") {
+            Some(self.package_ids.0)
+        } else {
+            Some(self.package_ids.1)
+        }
+    }
+
+    fn aur_package_id(&self) -> Option<&'static str> {
+        Some(self.aur_package_id)
+    }
+
+    fn app_path(&self) -> Result<PathBuf> {
+        if cfg!(target_os = "windows") {
+            Ok(dirs::data_local_dir()
+                .ok_or_else(|| anyhow!("Could not get local data directory"))?
+                .join(self.windows_root))
+        } else if cfg!(target_os = "macos") {
+            Ok(dirs::home_dir()
+                .ok_or_else(|| anyhow!("Could not get home directory"))?
+                .join("Library/Application Support")
+                .join(self.macos_root))
+        } else {
+            Ok(xdg_config_home()?.join(self.linux_root))
+        }
+    }
+
+    fn config_path(&self) -> Result<Vec<PathBuf>> {
+        let root = self.app_path()?;
+        if !root.is_dir() {
+            return Ok(Vec::new());
+        }
+        profile_files(&root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_profile_files_skip_caches_and_keyring_databases() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        let profile = root.join("Default");
+        std::fs::create_dir_all(profile.join("Cache").join("Cache_Data"))?;
+        std::fs::create_dir_all(profile.join("Extensions"))?;
+        std::fs::write(root.join("Local State"), b"{}")?;
+        std::fs::write(profile.join("Preferences"), b"{}")?;
+        std::fs::write(profile.join("Bookmarks"), b"{}")?;
+        std::fs::write(profile.join("Extensions").join("manifest.json"), b"{}")?;
+        std::fs::write(profile.join("Cache").join("Cache_Data").join("data_0"), b"cache")?;
+        std::fs::write(profile.join("Login Data"), b"sqlite")?;
+        std::fs::write(profile.join("Cookies"), b"sqlite")?;
+
+        let mut files: Vec<_> = profile_files(root)?
+            .iter()
+            .map(|path| path.strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect();
+        files.sort();
+        assert_eq!(
+            files,
+            [
+                "Default/Bookmarks",
+                "Default/Extensions/manifest.json",
+                "Default/Preferences",
+                "Local State"
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+use super::notes_dir::{NotesDir, NotesDirConfig};
+use super::App;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::storage::manifest::Manifest;
+
+/// User-defined apps, read once at startup from the storage directory
+pub const CUSTOM_APPS_FILE: &str = "custom_apps.toml";
+
+/// Layout of `custom_apps.toml`, e.g.
+///
+/// ```toml
+/// [[notes]]
+/// id = "org"
+/// root = "~/org"
+/// exclude = ["**/*.org_archive"]
+/// max_attachment_mb = 2
+/// ```
+#[derive(Deserialize, Debug, Default)]
+struct CustomApps {
+    #[serde(default)]
+    notes: Vec<NotesDirConfig>,
+}
+
+fn parse(content: &str) -> Result<Vec<Arc<dyn App>>> {
+    let custom: CustomApps = toml::from_str(content)?;
+    let mut apps: Vec<Arc<dyn App>> = Vec::new();
+    for config in &custom.notes {
+        let app = NotesDir::from_config(config)
+            .with_context(|| format!("Invalid notes directory '{}'", config.id))?;
+        apps.push(Arc::new(app));
+    }
+    Ok(apps)
+}
+
+pub(crate) fn load_from(path: &Path) -> Result<Vec<Arc<dyn App>>> {
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    parse(&std::fs::read_to_string(path)?)
+        .with_context(|| format!("Invalid {}", path.display()))
+}
+
+/// Custom apps for the registry; a broken file is logged and ignored so the
+/// built-in apps keep working
+pub(crate) fn load_custom_apps() -> Vec<Arc<dyn App>> {
+    let path = match Manifest::base_storage_dir() {
+        Ok(dir) => dir.join(CUSTOM_APPS_FILE),
+        Err(_) => return Vec::new(),
+    };
+    load_from(&path).unwrap_or_else(|e| {
+        println!("Ignoring custom apps: {:#}", e);
+        Vec::new()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_notes_dir_from_custom_apps_toml() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path().join("notes");
+        std::fs::create_dir_all(root.join("journals"))?;
+        std::fs::create_dir_all(root.join("assets"))?;
+        std::fs::create_dir_all(root.join(".trash"))?;
+        std::fs::write(root.join("journals").join("2026_01_01.md"), vec![b'#'; 2 * 1024 * 1024])?;
+        std::fs::write(root.join("index.org"), b"* Inbox")?;
+        std::fs::write(root.join("assets").join("small.png"), b"png")?;
+        std::fs::write(root.join("assets").join("video.mp4"), vec![0u8; 2 * 1024 * 1024])?;
+        std::fs::write(root.join("draft.tmp"), b"tmp")?;
+        std::fs::write(root.join(".trash").join("old.md"), b"old")?;
+
+        let config_path = temp_dir.path().join(CUSTOM_APPS_FILE);
+        std::fs::write(
+            &config_path,
+            format!(
+                "[[notes]]\nid = \"logseq\"\nname = \"Logseq graph\"\nroot = {:?}\n\
+                 exclude = [\"*.tmp\"]\nmax_attachment_mb = 1\n",
+                root.to_string_lossy()
+            ),
+        )?;
+
+        let apps = load_from(&config_path)?;
+        assert_eq!(apps.len(), 1);
+        let app = &apps[0];
+        assert_eq!(app.target_hint(), "notes:logseq");
+        assert_eq!(app.name(), "Logseq graph");
+        assert!(app.is_installed());
+
+        let mut files: Vec<String> = app
+            .config_path()?
+            .iter()
+            .map(|p| p.strip_prefix(&root).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect();
+        files.sort();
+        // Large notes are kept, large attachments and excluded files are not
+        assert_eq!(files, vec!["assets/small.png", "index.org", "journals/2026_01_01.md"]);
+
+        std::fs::write(&config_path, "[[notes]]\nid = \"bad\"\nroot = \"x\"\nexclude = [\"[\"]\n")?;
+        assert!(load_from(&config_path).is_err());
+        Ok(())
+    }
+}
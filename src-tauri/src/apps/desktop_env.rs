@@ -0,0 +1,87 @@
+use super::{xdg_config_home, App, UnsupportedOs};
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+
+/// Anything bigger than this is not a hand-written session tweak
+const MAX_FILE_SIZE: u64 = 64 * 1024;
+
+/// X11/Wayland session environment: `~/.xprofile`, `~/.Xresources`,
+/// `environment.d/*.conf` and `autostart/*.desktop`.
+///
+/// Autostart entries often hold absolute `Exec=` paths (`/home/alice/bin/...`),
+/// which are restored verbatim and may need adjusting on a different machine.
+pub struct DesktopEnv;
+
+impl DesktopEnv {
+    fn is_small_file(path: &Path) -> bool {
+        path.metadata()
+            .map(|m| m.is_file() && m.len() <= MAX_FILE_SIZE)
+            .unwrap_or(false)
+    }
+
+    /// Files directly in `dir` with the given extension
+    fn files_with_extension(dir: &Path, extension: &str, files: &mut Vec<PathBuf>) -> Result<()> {
+        if !dir.is_dir() {
+            return Ok(());
+        }
+        for entry in std::fs::read_dir(dir)
+            .map_err(|e| anyhow!("Failed to read directory {}: {}", dir.display(), e))?
+        {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) == Some(extension)
+                && Self::is_small_file(&path)
+            {
+                files.push(path);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl App for DesktopEnv {
+    fn id(&self) -> &'static str {
+        "desktop-env"
+    }
+
+    fn name(&self) -> &'static str {
+        "Desktop Environment"
+    }
+
+    fn category(&self) -> &'static str {
+        "System"
+    }
+
+    fn is_installed(&self) -> bool {
+        cfg!(target_os = "linux")
+    }
+
+    fn target_hint(&self) -> &'static str {
+        "sys:desktop-env"
+    }
+
+    fn package_id(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn app_path(&self) -> Result<PathBuf> {
+        if !cfg!(target_os = "linux") {
+            return Err(UnsupportedOs { app: "X11/Wayland session files", os: "Linux" }.into());
+        }
+        dirs::home_dir().ok_or_else(|| anyhow!("Could not get home directory"))
+    }
+
+    fn config_path(&self) -> Result<Vec<PathBuf>> {
+        let home = self.app_path()?;
+        let config_dir = xdg_config_home()?;
+        let mut files: Vec<PathBuf> = [".xprofile", ".Xresources"]
+            .iter()
+            .map(|name| home.join(name))
+            .filter(|path| Self::is_small_file(path))
+            .collect();
+
+        Self::files_with_extension(&config_dir.join("environment.d"), "conf", &mut files)?;
+        Self::files_with_extension(&config_dir.join("autostart"), "desktop", &mut files)?;
+
+        Ok(files)
+    }
+}
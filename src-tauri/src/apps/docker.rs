@@ -0,0 +1,123 @@
+use super::{command_exists, validate_config_syntax, App, AppCapabilities};
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+
+fn docker_home() -> Result<PathBuf> {
+    Ok(dirs::home_dir()
+        .ok_or_else(|| anyhow!("Could not get home directory"))?
+        .join(".docker"))
+}
+
+/// The Docker CLI config, `~/.docker/config.json`: credential helpers,
+/// contexts, proxies and output formats. Registry logins without a
+/// credential helper are stored in it as base64 `auths`, so it counts as
+/// holding credentials.
+pub struct Docker;
+
+impl App for Docker {
+    fn id(&self) -> &'static str {
+        "docker"
+    }
+
+    fn name(&self) -> &'static str {
+        "Docker CLI"
+    }
+
+    fn category(&self) -> &'static str {
+        "DevTools"
+    }
+
+    fn capabilities(&self) -> AppCapabilities {
+        AppCapabilities {
+            credentials: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_installed(&self) -> bool {
+        command_exists("docker") || self.app_path().map(|p| p.exists()).unwrap_or(false)
+    }
+
+    fn target_hint(&self) -> &'static str {
+        "app:docker"
+    }
+
+    fn package_id(&self) -> Option<&'static str> {
+        if cfg!(target_os = "windows") {
+            Some("Docker.DockerDesktop")
+        } else {
+            Some("docker")
+        }
+    }
+
+    fn app_path(&self) -> Result<PathBuf> {
+        docker_home()
+    }
+
+    fn config_path(&self) -> Result<Vec<PathBuf>> {
+        let config = self.app_path()?.join("config.json");
+        Ok(if config.is_file() { vec![config] } else { Vec::new() })
+    }
+
+    fn validate(&self, path: &Path, data: &[u8]) -> Result<(), String> {
+        validate_config_syntax(path, data)
+    }
+}
+
+/// The Docker daemon's `daemon.json` (registry mirrors, log drivers,
+/// storage driver, address pools), kept apart from the user CLI config.
+///
+/// On Linux the daemon runs system-wide and reads `/etc/docker/daemon.json`,
+/// which only root can write: restoring may require running SaveMe elevated.
+/// Windows reads it from `%ProgramData%\docker\config`, while Docker Desktop
+/// on macOS keeps it per user in `~/.docker`.
+pub struct DockerDaemon;
+
+impl App for DockerDaemon {
+    fn id(&self) -> &'static str {
+        "docker-daemon"
+    }
+
+    fn name(&self) -> &'static str {
+        "Docker daemon"
+    }
+
+    fn category(&self) -> &'static str {
+        "DevTools"
+    }
+
+    fn is_installed(&self) -> bool {
+        command_exists("dockerd") || self.config_path().map(|p| !p.is_empty()).unwrap_or(false)
+    }
+
+    fn target_hint(&self) -> &'static str {
+        "sys:docker:daemon"
+    }
+
+    fn package_id(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn app_path(&self) -> Result<PathBuf> {
+        if cfg!(target_os = "windows") {
+            Ok(std::env::var("ProgramData")
+                .map(PathBuf::from)
+                .map_err(|e| anyhow!("Failed to get ProgramData: {}", e))?
+                .join("docker")
+                .join("config"))
+        } else if cfg!(target_os = "macos") {
+            docker_home()
+        } else {
+            Ok(PathBuf::from("/etc/docker"))
+        }
+    }
+
+    fn config_path(&self) -> Result<Vec<PathBuf>> {
+        let daemon = self.app_path()?.join("daemon.json");
+        Ok(if daemon.is_file() { vec![daemon] } else { Vec::new() })
+    }
+
+    fn validate(&self, path: &Path, data: &[u8]) -> Result<(), String> {
+        validate_config_syntax(path, data)
+    }
+}
@@ -0,0 +1,67 @@
+use super::{appdata_dir, xdg_config_home, App, AppCapabilities};
+use anyhow::Result;
+use std::path::PathBuf;
+
+/// FileZilla site manager (`sitemanager.xml`), bookmarks and settings.
+///
+/// Saved site passwords are stored base64-encoded inside `sitemanager.xml`
+/// unless a master password is set, so the files are treated as holding
+/// credentials and need the explicit opt-in plus blob encryption.
+pub struct FileZilla;
+
+impl App for FileZilla {
+    fn id(&self) -> &'static str {
+        "filezilla"
+    }
+
+    fn name(&self) -> &'static str {
+        "FileZilla"
+    }
+
+    fn category(&self) -> &'static str {
+        "Network"
+    }
+
+    fn capabilities(&self) -> AppCapabilities {
+        AppCapabilities {
+            sensitive: true,
+            credentials: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_installed(&self) -> bool {
+        self.app_path().map(|p| p.exists()).unwrap_or(false)
+    }
+
+    fn target_hint(&self) -> &'static str {
+        "app:filezilla"
+    }
+
+    fn package_id(&self) -> Option<&'static str> {
+        if cfg!(target_os = "windows") {
+            Some("TimKosse.FileZilla.Client")
+        } else {
+            Some("filezilla")
+        }
+    }
+
+    fn app_path(&self) -> Result<PathBuf> {
+        // FileZilla uses ~/.config/filezilla on macOS as well
+        if cfg!(target_os = "windows") {
+            Ok(appdata_dir()?.join("FileZilla"))
+        } else {
+            Ok(xdg_config_home()?.join("filezilla"))
+        }
+    }
+
+    fn config_path(&self) -> Result<Vec<PathBuf>> {
+        let filezilla_dir = self.app_path()?;
+
+        Ok(["sitemanager.xml", "bookmarks.xml", "filezilla.xml"]
+            .iter()
+            .map(|name| filezilla_dir.join(name))
+            .filter(|path| path.is_file())
+            .collect())
+    }
+}
@@ -0,0 +1,92 @@
+use super::{collect_files_recursive, xdg_config_home, App};
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Fontconfig configuration (`fonts.conf` and `conf.d/`). User-installed font
+/// files under `~/.local/share/fonts` can be large, so they are only included
+/// when the `include_font_files` setting is on.
+pub struct Fontconfig;
+
+impl Fontconfig {
+    fn user_fonts_dir(&self) -> Result<PathBuf> {
+        let data_home = std::env::var("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|_| std::env::var("HOME").map(|h| PathBuf::from(h).join(".local/share")))
+            .map_err(|e| anyhow!("Failed to get data dir: {}", e))?;
+        Ok(data_home.join("fonts"))
+    }
+}
+
+impl App for Fontconfig {
+    fn id(&self) -> &'static str {
+        "fontconfig"
+    }
+
+    fn name(&self) -> &'static str {
+        "Fontconfig"
+    }
+
+    fn category(&self) -> &'static str {
+        "System"
+    }
+
+    fn is_installed(&self) -> bool {
+        self.app_path().map(|p| p.exists()).unwrap_or(false)
+    }
+
+    fn target_hint(&self) -> &'static str {
+        "sys:fontconfig"
+    }
+
+    fn package_id(&self) -> Option<&'static str> {
+        if cfg!(target_os = "linux") {
+            Some("fontconfig")
+        } else {
+            None
+        }
+    }
+
+    fn app_path(&self) -> Result<PathBuf> {
+        if cfg!(target_os = "windows") {
+            return Err(anyhow!("Fontconfig is not used on Windows."));
+        }
+        Ok(xdg_config_home()?.join("fontconfig"))
+    }
+
+    fn config_path(&self) -> Result<Vec<PathBuf>> {
+        let fontconfig_dir = self.app_path()?;
+        let mut files = Vec::new();
+
+        let fonts_conf = fontconfig_dir.join("fonts.conf");
+        if fonts_conf.is_file() {
+            files.push(fonts_conf);
+        }
+
+        let conf_d = fontconfig_dir.join("conf.d");
+        if conf_d.is_dir() {
+            collect_files_recursive(&conf_d, &[], &mut files)?;
+        }
+
+        if crate::settings::current().include_font_files {
+            let fonts_dir = self.user_fonts_dir()?;
+            if fonts_dir.is_dir() {
+                collect_files_recursive(&fonts_dir, &[], &mut files)?;
+            }
+        }
+
+        Ok(files)
+    }
+
+    fn post_restore(&self) -> Result<()> {
+        // Rebuild the font cache so restored fonts and rules apply immediately
+        let status = Command::new("fc-cache")
+            .arg("-f")
+            .status()
+            .map_err(|e| anyhow!("Failed to run fc-cache: {}", e))?;
+        if !status.success() {
+            return Err(anyhow!("fc-cache exited with {:?}", status.code()));
+        }
+        Ok(())
+    }
+}
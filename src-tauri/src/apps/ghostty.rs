@@ -0,0 +1,115 @@
+use super::{collect_files_recursive, command_exists, xdg_config_home, App};
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+
+/// Config file names, `config.ghostty` being the one newer releases write
+const GHOSTTY_FILES: &[&str] = &["config", "config.ghostty"];
+
+/// The Ghostty terminal: its config file and user themes. On macOS the
+/// config can live under Application Support or the XDG directory, and
+/// Ghostty reads both.
+pub struct Ghostty;
+
+/// Config files and `themes/` of the Ghostty config directory `root`
+fn ghostty_files(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = GHOSTTY_FILES
+        .iter()
+        .map(|name| root.join(name))
+        .filter(|path| path.is_file())
+        .collect();
+    let themes = root.join("themes");
+    if themes.is_dir() {
+        collect_files_recursive(&themes, &[], &mut files)?;
+    }
+    Ok(files)
+}
+
+impl Ghostty {
+    /// Config directories Ghostty reads, in the order it loads them
+    fn config_dirs(&self) -> Result<Vec<PathBuf>> {
+        let mut dirs = vec![xdg_config_home()?.join("ghostty")];
+        if cfg!(target_os = "macos") {
+            dirs.push(
+                dirs::home_dir()
+                    .ok_or_else(|| anyhow!("Could not get home directory"))?
+                    .join("Library/Application Support/com.mitchellh.ghostty"),
+            );
+        }
+        Ok(dirs)
+    }
+}
+
+impl App for Ghostty {
+    fn id(&self) -> &'static str {
+        "ghostty"
+    }
+
+    fn name(&self) -> &'static str {
+        "Ghostty"
+    }
+
+    fn category(&self) -> &'static str {
+        "Terminal"
+    }
+
+    fn is_installed(&self) -> bool {
+        command_exists("ghostty")
+            || (cfg!(target_os = "macos") && Path::new("/Applications/Ghostty.app").is_dir())
+    }
+
+    fn target_hint(&self) -> &'static str {
+        "app:ghostty"
+    }
+
+    fn package_id(&self) -> Option<&'static str> {
+        if cfg!(target_os = "windows") {
+            None
+        } else {
+            Some("ghostty")
+        }
+    }
+
+    /// The Application Support directory on macOS when it holds a config,
+    /// else `ghostty` under the XDG config directory
+    fn app_path(&self) -> Result<PathBuf> {
+        let dirs = self.config_dirs()?;
+        Ok(dirs
+            .iter()
+            .rev()
+            .find(|dir| GHOSTTY_FILES.iter().any(|name| dir.join(name).is_file()))
+            .unwrap_or(&dirs[0])
+            .clone())
+    }
+
+    fn config_path(&self) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        for dir in self.config_dirs()? {
+            files.extend(ghostty_files(&dir)?);
+        }
+        Ok(files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_ghostty_files_include_config_and_themes() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("themes"))?;
+        for file in ["config", "themes/mine", "crash.log"] {
+            std::fs::write(root.join(file), b"x")?;
+        }
+
+        let mut files: Vec<_> = ghostty_files(root)?
+            .iter()
+            .map(|path| path.strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect();
+        files.sort();
+        assert_eq!(files, ["config", "themes/mine"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+use super::{appdata_dir, collect_small_files, xdg_config_home, App};
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+
+/// Profile versions to look for, newest first
+const GIMP_VERSIONS: &[&str] = &["3.0", "2.10"];
+
+/// Scratch and autosave data inside the GIMP profile
+const GIMP_SKIP_DIRS: &[&str] = &["tmp", "backups", "swap"];
+
+/// GIMP profile: `gimprc`, `shortcutsrc`, `sessionrc` plus brushes,
+/// palettes, gradients and other small resources. Files above the
+/// `resource_size_limit_mb` setting are left out.
+pub struct Gimp;
+
+impl Gimp {
+    fn profiles_root(&self) -> Result<PathBuf> {
+        let base = if cfg!(target_os = "windows") {
+            appdata_dir()?
+        } else if cfg!(target_os = "macos") {
+            dirs::home_dir()
+                .ok_or_else(|| anyhow!("Could not get home directory"))?
+                .join("Library/Application Support")
+        } else {
+            xdg_config_home()?
+        };
+        Ok(base.join("GIMP"))
+    }
+}
+
+impl App for Gimp {
+    fn id(&self) -> &'static str {
+        "gimp"
+    }
+
+    fn name(&self) -> &'static str {
+        "GIMP"
+    }
+
+    fn category(&self) -> &'static str {
+        "Graphics"
+    }
+
+    fn is_installed(&self) -> bool {
+        self.app_path().map(|p| p.exists()).unwrap_or(false)
+    }
+
+    fn target_hint(&self) -> &'static str {
+        "app:gimp"
+    }
+
+    fn package_id(&self) -> Option<&'static str> {
+        if cfg!(target_os = "windows") {
+            Some("GIMP.GIMP")
+        } else {
+            Some("gimp")
+        }
+    }
+
+    fn app_path(&self) -> Result<PathBuf> {
+        // Newest existing profile, or the 2.10 location a fresh install uses
+        let root = self.profiles_root()?;
+        Ok(GIMP_VERSIONS
+            .iter()
+            .map(|version| root.join(version))
+            .find(|path| path.is_dir())
+            .unwrap_or_else(|| root.join("2.10")))
+    }
+
+    fn config_path(&self) -> Result<Vec<PathBuf>> {
+        let gimp_dir = self.app_path()?;
+        let mut files = Vec::new();
+        collect_small_files(&gimp_dir, GIMP_SKIP_DIRS, &mut files)
+            .map_err(|e| anyhow!("Failed to read GIMP profile: {}", e))?;
+        Ok(files)
+    }
+}
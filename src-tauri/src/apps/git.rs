@@ -0,0 +1,90 @@
+use super::{command_exists, xdg_config_home, App};
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+
+/// Global Git configuration: `~/.gitconfig`, the XDG `~/.config/git/config`
+/// (git reads both) and `~/.gitignore_global`. Repository configs are
+/// left alone.
+pub struct Git;
+
+/// The global git config files under `home` and `config_home` that exist
+fn git_files(home: &Path, config_home: &Path) -> Vec<PathBuf> {
+    [
+        home.join(".gitconfig"),
+        config_home.join("git").join("config"),
+        home.join(".gitignore_global"),
+    ]
+    .into_iter()
+    .filter(|path| path.is_file())
+    .collect()
+}
+
+impl App for Git {
+    fn id(&self) -> &'static str {
+        "git"
+    }
+
+    fn name(&self) -> &'static str {
+        "Git"
+    }
+
+    fn category(&self) -> &'static str {
+        "DevTools"
+    }
+
+    /// The binary, as git works without any global config
+    fn is_installed(&self) -> bool {
+        command_exists("git")
+    }
+
+    fn target_hint(&self) -> &'static str {
+        "cfg:git"
+    }
+
+    fn package_id(&self) -> Option<&'static str> {
+        if cfg!(target_os = "windows") {
+            Some("Git.Git")
+        } else {
+            Some("git")
+        }
+    }
+
+    /// The home directory, since `.gitconfig` sits directly in it
+    fn app_path(&self) -> Result<PathBuf> {
+        dirs::home_dir().ok_or_else(|| anyhow!("Could not get home directory"))
+    }
+
+    fn config_path(&self) -> Result<Vec<PathBuf>> {
+        Ok(git_files(&self.app_path()?, &xdg_config_home()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_git_files_are_only_the_existing_ones() -> Result<()> {
+        let home = TempDir::new()?;
+        let home = home.path();
+        let config_home = home.join(".config");
+        assert!(git_files(home, &config_home).is_empty());
+
+        std::fs::write(home.join(".gitconfig"), b"[user]\n\tname = me\n")?;
+        std::fs::write(home.join(".gitignore_global"), b"*.swp\n")?;
+        assert_eq!(
+            git_files(home, &config_home),
+            vec![home.join(".gitconfig"), home.join(".gitignore_global")]
+        );
+
+        std::fs::create_dir_all(config_home.join("git"))?;
+        std::fs::write(config_home.join("git").join("config"), b"[core]\n")?;
+        std::fs::remove_file(home.join(".gitconfig"))?;
+        assert_eq!(
+            git_files(home, &config_home),
+            vec![config_home.join("git").join("config"), home.join(".gitignore_global")]
+        );
+        Ok(())
+    }
+}
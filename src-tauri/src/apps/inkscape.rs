@@ -0,0 +1,58 @@
+use super::{appdata_dir, collect_small_files, xdg_config_home, App};
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+
+/// Inkscape preferences, keyboard shortcuts (`keys/`), palettes, templates,
+/// symbols and extensions. Files above the `resource_size_limit_mb` setting
+/// are left out.
+pub struct Inkscape;
+
+impl App for Inkscape {
+    fn id(&self) -> &'static str {
+        "inkscape"
+    }
+
+    fn name(&self) -> &'static str {
+        "Inkscape"
+    }
+
+    fn category(&self) -> &'static str {
+        "Graphics"
+    }
+
+    fn is_installed(&self) -> bool {
+        self.app_path().map(|p| p.exists()).unwrap_or(false)
+    }
+
+    fn target_hint(&self) -> &'static str {
+        "app:inkscape"
+    }
+
+    fn package_id(&self) -> Option<&'static str> {
+        if cfg!(target_os = "windows") {
+            Some("Inkscape.Inkscape")
+        } else {
+            Some("inkscape")
+        }
+    }
+
+    fn app_path(&self) -> Result<PathBuf> {
+        if cfg!(target_os = "windows") {
+            Ok(appdata_dir()?.join("inkscape"))
+        } else if cfg!(target_os = "macos") {
+            Ok(dirs::home_dir()
+                .ok_or_else(|| anyhow!("Could not get home directory"))?
+                .join("Library/Application Support/org.inkscape.Inkscape/config/inkscape"))
+        } else {
+            Ok(xdg_config_home()?.join("inkscape"))
+        }
+    }
+
+    fn config_path(&self) -> Result<Vec<PathBuf>> {
+        let inkscape_dir = self.app_path()?;
+        let mut files = Vec::new();
+        collect_small_files(&inkscape_dir, &[], &mut files)
+            .map_err(|e| anyhow!("Failed to read Inkscape config directory: {}", e))?;
+        Ok(files)
+    }
+}
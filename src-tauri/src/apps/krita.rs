@@ -0,0 +1,95 @@
+use super::{collect_small_files, App};
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+
+/// Imported bundles are large and can be re-imported; the resource cache is
+/// rebuilt by Krita on startup
+const KRITA_SKIP_DIRS: &[&str] = &["bundles", "backups"];
+
+/// Krita settings (`kritarc`, `kritashortcutsrc`) and the resource folder
+/// (brush presets, palettes, workspaces). Paths are kept relative to the home
+/// directory since the two live in different base directories.
+pub struct Krita;
+
+impl Krita {
+    fn config_files(&self) -> Result<Vec<PathBuf>> {
+        let home = self.app_path()?;
+        let config_dir = if cfg!(target_os = "windows") {
+            home.join("AppData/Local")
+        } else if cfg!(target_os = "macos") {
+            home.join("Library/Preferences")
+        } else {
+            home.join(".config")
+        };
+        Ok(["kritarc", "kritashortcutsrc", "kritadisplayrc"]
+            .iter()
+            .map(|name| config_dir.join(name))
+            .collect())
+    }
+
+    fn resource_dir(&self) -> Result<PathBuf> {
+        let home = self.app_path()?;
+        Ok(if cfg!(target_os = "windows") {
+            home.join("AppData/Roaming/krita")
+        } else if cfg!(target_os = "macos") {
+            home.join("Library/Application Support/krita")
+        } else {
+            home.join(".local/share/krita")
+        })
+    }
+}
+
+impl App for Krita {
+    fn id(&self) -> &'static str {
+        "krita"
+    }
+
+    fn name(&self) -> &'static str {
+        "Krita"
+    }
+
+    fn category(&self) -> &'static str {
+        "Graphics"
+    }
+
+    fn is_installed(&self) -> bool {
+        self.resource_dir().map(|p| p.exists()).unwrap_or(false)
+            || self
+                .config_files()
+                .map(|files| files.iter().any(|f| f.exists()))
+                .unwrap_or(false)
+    }
+
+    fn target_hint(&self) -> &'static str {
+        "app:krita"
+    }
+
+    fn package_id(&self) -> Option<&'static str> {
+        if cfg!(target_os = "windows") {
+            Some("KDE.Krita")
+        } else {
+            Some("krita")
+        }
+    }
+
+    fn app_path(&self) -> Result<PathBuf> {
+        dirs::home_dir().ok_or_else(|| anyhow!("Could not get home directory"))
+    }
+
+    fn config_path(&self) -> Result<Vec<PathBuf>> {
+        let mut files: Vec<PathBuf> = self
+            .config_files()?
+            .into_iter()
+            .filter(|path| path.is_file())
+            .collect();
+
+        let resource_dir = self.resource_dir()?;
+        if resource_dir.is_dir() {
+            collect_small_files(&resource_dir, KRITA_SKIP_DIRS, &mut files)
+                .map_err(|e| anyhow!("Failed to read Krita resources: {}", e))?;
+            files.retain(|path| path.extension().and_then(|e| e.to_str()) != Some("sqlite"));
+        }
+
+        Ok(files)
+    }
+}
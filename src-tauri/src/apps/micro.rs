@@ -0,0 +1,160 @@
+use super::{
+    collect_files_recursive, command_exists, validate_config_syntax, xdg_config_home, App,
+    AppCapabilities,
+};
+use anyhow::{anyhow, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::path::{Path, PathBuf};
+
+/// Top-level settings files of the micro config directory
+const MICRO_FILES: &[&str] = &["settings.json", "bindings.json", "init.lua"];
+
+/// Directories of user colorschemes, syntax files and plugins
+const MICRO_DIRS: &[&str] = &["colorschemes", "syntax", "plug"];
+
+/// Compiled helpers some plugins ship next to their Lua sources; they are
+/// platform-specific and come back with a plugin reinstall
+const MICRO_EXCLUDE_GLOBS: &[&str] = &[
+    "plug/**/*.so",
+    "plug/**/*.dll",
+    "plug/**/*.dylib",
+    "plug/**/*.exe",
+    "plug/**/bin/**",
+];
+
+/// The micro editor: settings, key bindings, `init.lua`, colorschemes,
+/// syntax files and plugin sources. `buffers/` (cursor and undo history)
+/// and `backups/` are left out.
+pub struct Micro;
+
+fn exclude_set() -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in MICRO_EXCLUDE_GLOBS {
+        builder.add(Glob::new(pattern)?);
+    }
+    Ok(builder.build()?)
+}
+
+/// Files of the micro config directory `root` worth backing up
+fn micro_files(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = MICRO_FILES
+        .iter()
+        .map(|name| root.join(name))
+        .filter(|path| path.is_file())
+        .collect();
+    for dir in MICRO_DIRS {
+        let dir = root.join(dir);
+        if dir.is_dir() {
+            collect_files_recursive(&dir, &[], &mut files)?;
+        }
+    }
+
+    let exclude = exclude_set()?;
+    files.retain(|path| {
+        path.strip_prefix(root)
+            .map(|relative| !exclude.is_match(relative))
+            .unwrap_or(true)
+    });
+    Ok(files)
+}
+
+impl App for Micro {
+    fn id(&self) -> &'static str {
+        "micro"
+    }
+
+    fn name(&self) -> &'static str {
+        "micro"
+    }
+
+    fn category(&self) -> &'static str {
+        "Editor"
+    }
+
+    fn capabilities(&self) -> AppCapabilities {
+        AppCapabilities {
+            snap: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_installed(&self) -> bool {
+        command_exists("micro")
+    }
+
+    fn target_hint(&self) -> &'static str {
+        "app:micro"
+    }
+
+    fn package_id(&self) -> Option<&'static str> {
+        if cfg!(target_os = "windows") {
+            Some("zyedidia.micro")
+        } else {
+            Some("micro")
+        }
+    }
+
+    /// `$MICRO_CONFIG_HOME`, else `micro` under the XDG config directory,
+    /// which micro also uses on Windows (`%USERPROFILE%\.config\micro`)
+    fn app_path(&self) -> Result<PathBuf> {
+        if let Ok(dir) = std::env::var("MICRO_CONFIG_HOME") {
+            return Ok(PathBuf::from(dir));
+        }
+        if cfg!(target_os = "windows") {
+            return Ok(dirs::home_dir()
+                .ok_or_else(|| anyhow!("Could not get home directory"))?
+                .join(".config")
+                .join("micro"));
+        }
+        Ok(xdg_config_home()?.join("micro"))
+    }
+
+    fn config_path(&self) -> Result<Vec<PathBuf>> {
+        let root = self.app_path()?;
+        if !root.is_dir() {
+            return Ok(Vec::new());
+        }
+        micro_files(&root)
+    }
+
+    fn validate(&self, path: &Path, data: &[u8]) -> Result<(), String> {
+        validate_config_syntax(path, data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_micro_files_skip_history_and_plugin_binaries() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        for dir in ["colorschemes", "buffers", "plug/fzf/bin"] {
+            std::fs::create_dir_all(root.join(dir))?;
+        }
+        for file in [
+            "settings.json",
+            "bindings.json",
+            "colorschemes/mine.micro",
+            "buffers/history",
+            "plug/fzf/fzf.lua",
+            "plug/fzf/helper.so",
+            "plug/fzf/bin/fzf",
+        ] {
+            std::fs::write(root.join(file), b"x")?;
+        }
+
+        let mut files: Vec<_> = micro_files(root)?
+            .iter()
+            .map(|path| path.strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect();
+        files.sort();
+        assert_eq!(
+            files,
+            ["bindings.json", "colorschemes/mine.micro", "plug/fzf/fzf.lua", "settings.json"]
+        );
+        Ok(())
+    }
+}
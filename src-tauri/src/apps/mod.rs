@@ -1,17 +1,104 @@
-use std::path::PathBuf;
-use anyhow::Result;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt;
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use anyhow::{anyhow, Result};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
 use serde::Serialize;
 use once_cell::sync::Lazy;
 
+use crate::settings::Settings;
+
 pub mod zed;
 pub mod windows_terminal;
 pub mod vscode;
+pub mod obs;
+pub mod audacity;
+pub mod fonts;
+pub mod slack;
+pub mod telegram;
+pub mod desktop_env;
+pub mod network;
+pub mod filezilla;
+pub mod winscp;
+pub mod gimp;
+pub mod inkscape;
+pub mod krita;
+pub mod chromium;
+pub mod docker;
+pub mod micro;
+pub mod nano;
+pub mod status_bar;
+pub mod sensitive;
+pub mod notes_dir;
+pub mod ghostty;
+pub mod tmux;
+pub mod git;
+pub mod custom;
 
 #[derive(Serialize, Clone, Debug)]
 pub struct AppInfo {
     pub id: String,
     pub name: String,
+    /// `App::category`, for grouping the app list
+    pub category: String,
     pub is_installed: bool,
+    /// Why the app is or isn't available; `is_installed` is `Installed` here
+    pub status: AppStatus,
+    /// Whether `App::config_path` finds anything to back up
+    pub has_config: bool,
+    /// Total size of the config files. A lower bound when the walk hit
+    /// `CONFIG_SIZE_MAX_FILES`; 0 when it didn't finish in time.
+    pub config_size_bytes: u64,
+    /// Names accepted by `save_config_subset` for this app
+    pub config_subsets: Vec<String>,
+}
+
+/// Availability of an app on this machine, so the UI can tell a missing
+/// app from one that can't exist here or couldn't be looked for
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub enum AppStatus {
+    Installed,
+    NotInstalled,
+    /// The app doesn't exist on this OS
+    UnsupportedOS,
+    /// Detection failed; the message says why
+    DetectionError(String),
+}
+
+/// Error `App::app_path` returns on an OS the app doesn't exist on, told
+/// apart from other failures by `app_status`
+#[derive(Debug)]
+pub struct UnsupportedOs {
+    pub app: &'static str,
+    pub os: &'static str,
+}
+
+impl fmt::Display for UnsupportedOs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} is only available on {}.", self.app, self.os)
+    }
+}
+
+impl std::error::Error for UnsupportedOs {}
+
+/// Behaviors an app opts into. Everything is off by default, so a new app
+/// only names what applies to it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AppCapabilities {
+    /// Installed as a snap on Linux
+    pub snap: bool,
+    /// The config can expose credentials or session data. Sensitive apps
+    /// are only backed up once blob encryption is enabled.
+    pub sensitive: bool,
+    /// The files hold plain-text credentials. On top of blob encryption,
+    /// these also need the `include_credentials` opt-in.
+    pub credentials: bool,
 }
 
 pub trait App: Send + Sync {
@@ -22,25 +109,867 @@ pub trait App: Send + Sync {
     fn app_path(&self) -> Result<PathBuf>;
     fn target_hint(&self) -> &'static str;
     fn package_id(&self) -> Option<&'static str>;
-    fn snap_support(&self) -> bool;
+
+    /// Group the app is listed under, e.g. "Editor", "Terminal", "Browser"
+    fn category(&self) -> &'static str {
+        "Other"
+    }
+
+    /// Package name in the Arch User Repository, tried with an AUR helper
+    /// when `package_id` isn't in the official Arch repositories
+    fn aur_package_id(&self) -> Option<&'static str> {
+        self.package_id()
+    }
+
+    fn capabilities(&self) -> AppCapabilities {
+        AppCapabilities::default()
+    }
+
+    /// Target hints this app's entries had in backups made before it
+    /// changed its own, so those backups still restore
+    fn legacy_target_hints(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Whether entries with `target_hint` belong to this app
+    fn owns_target_hint(&self, target_hint: &str) -> bool {
+        target_hint == self.target_hint() || self.legacy_target_hints().contains(&target_hint)
+    }
+
+    /// Named parts of the config `config_subset` accepts, e.g. `keymap`
+    fn config_subsets(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    /// The existing files of config subset `subset` (one of
+    /// `config_subsets`), for backing up only part of an app's config
+    fn config_subset(&self, subset: &str) -> Result<Vec<PathBuf>> {
+        Err(anyhow!("{} has no '{}' config subset", self.name(), subset))
+    }
+
+    /// Runs before this app's files are collected for a backup, e.g. to
+    /// export settings that live outside the filesystem into a file
+    fn pre_backup(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Runs after this app's files have been restored (e.g. rebuilding a cache)
+    fn post_restore(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Check the syntax of config file `path` (content `data`) before it is
+    /// backed up. An error is only reported as a warning; the file is saved
+    /// either way.
+    fn validate(&self, _path: &Path, _data: &[u8]) -> Result<(), String> {
+        Ok(())
+    }
 }
 
-pub static REGISTRY: Lazy<Vec<Box<dyn App>>> = Lazy::new(|| {
+/// The built-in apps, constructed once and shared by every registry lookup
+static BUILTIN_APPS: Lazy<Vec<Arc<dyn App>>> = Lazy::new(|| {
     vec![
-        Box::new(zed::Zed),
-        Box::new(windows_terminal::WindowsTerminal),
-        Box::new(vscode::VSCode),
+        Arc::new(zed::Zed),
+        Arc::new(windows_terminal::WindowsTerminal),
+        Arc::new(vscode::VSCode),
+        Arc::new(obs::OBS),
+        Arc::new(audacity::Audacity),
+        Arc::new(fonts::Fontconfig),
+        Arc::new(slack::Slack),
+        Arc::new(telegram::Telegram),
+        Arc::new(desktop_env::DesktopEnv),
+        Arc::new(network::NetworkManager),
+        Arc::new(filezilla::FileZilla),
+        Arc::new(winscp::WinSCP),
+        Arc::new(gimp::Gimp),
+        Arc::new(inkscape::Inkscape),
+        Arc::new(krita::Krita),
+        Arc::new(chromium::VIVALDI),
+        Arc::new(chromium::EDGE),
+        Arc::new(docker::Docker),
+        Arc::new(docker::DockerDaemon),
+        Arc::new(micro::Micro),
+        Arc::new(nano::Nano),
+        Arc::new(status_bar::WAYBAR),
+        Arc::new(status_bar::POLYBAR),
+        Arc::new(status_bar::EWW),
+        Arc::new(ghostty::Ghostty),
+        Arc::new(tmux::Tmux),
+        Arc::new(git::Git),
     ]
 });
 
-pub fn get_app(id: &str) -> Option<&'static dyn App> {
-    REGISTRY.iter().find(|app| app.id() == id).map(|app| app.as_ref())
+/// Built-in apps followed by the custom ones from `custom_apps.toml` and any
+/// registered at runtime with `register_app`
+pub static REGISTRY: Lazy<RwLock<Vec<Arc<dyn App>>>> = Lazy::new(|| {
+    let mut apps = BUILTIN_APPS.clone();
+    for app in custom::load_custom_apps() {
+        if apps.iter().any(|existing| existing.id() == app.id()) {
+            println!("Ignoring custom app '{}': id already in use", app.id());
+            continue;
+        }
+        apps.push(app);
+    }
+    RwLock::new(apps)
+});
+
+/// Snapshot of the registered apps, in registry order
+pub fn all_apps() -> Vec<Arc<dyn App>> {
+    REGISTRY.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Add an app to the registry at runtime; fails if its id is taken
+pub fn register_app(app: Arc<dyn App>) -> Result<()> {
+    register_in(&REGISTRY, app)
+}
+
+fn register_in(registry: &RwLock<Vec<Arc<dyn App>>>, app: Arc<dyn App>) -> Result<()> {
+    let mut apps = registry.write().unwrap_or_else(|e| e.into_inner());
+    if apps.iter().any(|existing| existing.id() == app.id()) {
+        return Err(anyhow!("App id '{}' is already in use", app.id()));
+    }
+    apps.push(app);
+    Ok(())
+}
+
+/// First app in `registry` matching `predicate`
+fn find_in(
+    registry: &RwLock<Vec<Arc<dyn App>>>,
+    predicate: impl Fn(&dyn App) -> bool,
+) -> Option<Arc<dyn App>> {
+    let apps = registry.read().unwrap_or_else(|e| e.into_inner());
+    apps.iter().find(|app| predicate(app.as_ref())).cloned()
+}
+
+/// Register the custom apps added to `custom_apps.toml` since startup;
+/// returns how many were new. Apps already registered are left as they are.
+pub fn reload_custom_apps() -> usize {
+    custom::load_custom_apps()
+        .into_iter()
+        .filter(|app| register_app(Arc::clone(app)).is_ok())
+        .count()
 }
 
+/// How long a cached `config_path()` result stays valid
+const CONFIG_PATH_CACHE_TTL: Duration = Duration::from_secs(10);
+
+/// Short-lived cache of `config_path()` results keyed by app id, so a single
+/// UI refresh doesn't walk the same config tree several times
+static CONFIG_PATH_CACHE: Lazy<Mutex<HashMap<&'static str, (Instant, Vec<PathBuf>)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// `app.config_path()`, served from the cache when a fresh result exists.
+/// Errors are never cached.
+pub fn cached_config_path(app: &dyn App) -> Result<Vec<PathBuf>> {
+    if let Some(paths) = fresh_cached_paths(app) {
+        return Ok(paths);
+    }
+    let paths = app.config_path()?;
+    cache_paths(app, &paths);
+    Ok(paths)
+}
+
+fn fresh_cached_paths(app: &dyn App) -> Option<Vec<PathBuf>> {
+    let cache = CONFIG_PATH_CACHE.lock().ok()?;
+    let (cached_at, paths) = cache.get(app.id())?;
+    (cached_at.elapsed() < CONFIG_PATH_CACHE_TTL).then(|| paths.clone())
+}
+
+fn cache_paths(app: &dyn App, paths: &[PathBuf]) {
+    if let Ok(mut cache) = CONFIG_PATH_CACHE.lock() {
+        cache.insert(app.id(), (Instant::now(), paths.to_vec()));
+    }
+}
+
+/// Drop all cached `config_path()` results, called after save and restore
+/// since both can change what's on disk
+pub fn invalidate_config_path_cache() {
+    if let Ok(mut cache) = CONFIG_PATH_CACHE.lock() {
+        cache.clear();
+    }
+}
+
+pub fn get_app(id: &str) -> Option<Arc<dyn App>> {
+    find_in(&REGISTRY, |app| app.id() == id)
+}
+
+/// The registered app whose entries carry `target_hint`
+pub fn get_app_by_hint(target_hint: &str) -> Option<Arc<dyn App>> {
+    find_in(&REGISTRY, |app| app.owns_target_hint(target_hint))
+}
+
+/// Longest `get_all_apps_info` waits for config sizes
+const CONFIG_SIZE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Files summed per app at most
+const CONFIG_SIZE_MAX_FILES: usize = 20_000;
+
+/// Most threads `get_all_apps_info` summarizes apps on
+const CONFIG_SIZE_THREADS: usize = 8;
+
+thread_local! {
+    /// Files `collect_files_recursive` may still gather on this thread, set
+    /// by `bounded_config_path` so a huge tree stops early; None is no limit
+    static WALK_BUDGET: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// `cached_config_path`, except that a walk stops after gathering about
+/// `max_files` files. The bool tells whether it did; such a partial list is
+/// not cached, so a save never sees it.
+fn bounded_config_path(app: &dyn App, max_files: usize) -> Result<(Vec<PathBuf>, bool)> {
+    if let Some(paths) = fresh_cached_paths(app) {
+        return Ok((paths, false));
+    }
+    WALK_BUDGET.with(|budget| budget.set(Some(max_files)));
+    let paths = app.config_path();
+    let cut_short = WALK_BUDGET.with(|budget| budget.replace(None)) == Some(0);
+    let paths = paths?;
+    if !cut_short {
+        cache_paths(app, &paths);
+    }
+    Ok((paths, cut_short))
+}
+
+/// Whether an app has config files and their summed size, from a single
+/// walk (shared with later `cached_config_path` calls) of at most
+/// `CONFIG_SIZE_MAX_FILES` files
+fn config_summary(app: &dyn App) -> (bool, u64) {
+    let (paths, _) = bounded_config_path(app, CONFIG_SIZE_MAX_FILES).unwrap_or_default();
+    let size = paths
+        .iter()
+        .take(CONFIG_SIZE_MAX_FILES)
+        .filter_map(|path| path.metadata().ok())
+        .map(|metadata| metadata.len())
+        .sum();
+    (!paths.is_empty(), size)
+}
+
+/// Install state of `app`. An `app_path()` error other than `UnsupportedOs`
+/// only counts when the app isn't found, and a panicking `is_installed()`
+/// is reported instead of taking the whole listing down.
+pub fn app_status(app: &dyn App) -> AppStatus {
+    let path_error = match app.app_path() {
+        Err(e) if e.is::<UnsupportedOs>() => return AppStatus::UnsupportedOS,
+        Err(e) => Some(e.to_string()),
+        Ok(_) => None,
+    };
+    match std::panic::catch_unwind(AssertUnwindSafe(|| app.is_installed())) {
+        Ok(true) => AppStatus::Installed,
+        Ok(false) => match path_error {
+            Some(error) => AppStatus::DetectionError(error),
+            None => AppStatus::NotInstalled,
+        },
+        Err(_) => AppStatus::DetectionError(format!("Detecting {} panicked", app.name())),
+    }
+}
+
+/// Every registered app with its install state and config summary. Apps are
+/// summarized in parallel on at most `CONFIG_SIZE_THREADS` threads; one
+/// whose config walk outlives `CONFIG_SIZE_TIMEOUT` is reported with size 0
+/// and `has_config` guessed from `is_installed`, and finishes warming the
+/// path cache in the background.
 pub fn get_all_apps_info() -> Vec<AppInfo> {
-    REGISTRY.iter().map(|app| AppInfo {
-        id: app.id().to_string(),
-        name: app.name().to_string(),
-        is_installed: app.is_installed(),
-    }).collect()
+    let apps = Arc::new(all_apps());
+    let next = Arc::new(AtomicUsize::new(0));
+    let (sender, receiver) = std::sync::mpsc::channel();
+    for _ in 0..apps.len().min(CONFIG_SIZE_THREADS) {
+        let (apps, next, sender) = (Arc::clone(&apps), Arc::clone(&next), sender.clone());
+        std::thread::spawn(move || loop {
+            let index = next.fetch_add(1, Ordering::SeqCst);
+            let Some(app) = apps.get(index) else {
+                break;
+            };
+            let _ = sender.send((index, config_summary(app.as_ref())));
+        });
+    }
+    drop(sender);
+
+    let mut summaries = vec![None; apps.len()];
+    let deadline = Instant::now() + CONFIG_SIZE_TIMEOUT;
+    while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+        match receiver.recv_timeout(remaining) {
+            Ok((index, summary)) => summaries[index] = Some(summary),
+            Err(_) => break,
+        }
+    }
+
+    apps.iter()
+        .zip(summaries)
+        .map(|(app, summary)| {
+            let status = app_status(app.as_ref());
+            let is_installed = status == AppStatus::Installed;
+            let (has_config, config_size_bytes) = summary.unwrap_or_else(|| {
+                println!("Config size of '{}' timed out", app.name());
+                (is_installed, 0)
+            });
+            AppInfo {
+                id: app.id().to_string(),
+                name: app.name().to_string(),
+                category: app.category().to_string(),
+                is_installed,
+                status,
+                has_config,
+                config_size_bytes,
+                config_subsets: app.config_subsets().iter().map(|s| s.to_string()).collect(),
+            }
+        })
+        .collect()
+}
+
+/// Refuse to back up a sensitive app unless blob encryption is on, and an
+/// app holding credentials unless the user opted in to that as well
+pub fn ensure_backup_allowed(app: &dyn App, settings: &Settings) -> Result<()> {
+    let capabilities = app.capabilities();
+    if capabilities.credentials && !settings.include_credentials {
+        return Err(anyhow!(
+            "'{}' stores credentials in plain text; enable 'include credentials' in settings to back it up",
+            app.name()
+        ));
+    }
+    if (capabilities.sensitive || capabilities.credentials) && !settings.encrypt_blobs {
+        return Err(anyhow!(
+            "'{}' may contain account data; enable blob encryption in settings to back it up",
+            app.name()
+        ));
+    }
+    Ok(())
+}
+
+/// Whether an executable called `name` is on `PATH`
+pub(crate) fn command_exists(name: &str) -> bool {
+    let Some(path) = std::env::var_os("PATH") else {
+        return false;
+    };
+    std::env::split_paths(&path).any(|dir| {
+        dir.join(name).is_file()
+            || (cfg!(target_os = "windows") && dir.join(format!("{}.exe", name)).is_file())
+    })
+}
+
+/// `$XDG_CONFIG_HOME`, falling back to `~/.config`
+pub(crate) fn xdg_config_home() -> Result<PathBuf> {
+    std::env::var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|_| std::env::var("HOME").map(|h| PathBuf::from(h).join(".config")))
+        .map_err(|e| anyhow!("Failed to get config dir: {}", e))
+}
+
+/// Parse `data` as JSON or TOML according to `path`'s extension, for
+/// `App::validate`. JSON is read the way editors write it: `//` and `/* */`
+/// comments and trailing commas are accepted. Other files always pass.
+pub(crate) fn validate_config_syntax(path: &Path, data: &[u8]) -> Result<(), String> {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+    match extension.to_ascii_lowercase().as_str() {
+        "json" | "jsonc" => {
+            let text = std::str::from_utf8(data).map_err(|e| e.to_string())?;
+            serde_json::from_str::<serde_json::Value>(&strip_json_extensions(text))
+                .map(|_| ())
+                .map_err(|e| format!("invalid JSON: {}", e))
+        }
+        "toml" => {
+            let text = std::str::from_utf8(data).map_err(|e| e.to_string())?;
+            toml::from_str::<toml::Value>(text)
+                .map(|_| ())
+                .map_err(|e| format!("invalid TOML: {}", e.message()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// `text` with comments blanked out and trailing commas removed, keeping
+/// line and column positions so parse errors point at the right place
+fn strip_json_extensions(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    let mut in_string = false;
+    while i < chars.len() {
+        let c = chars[i];
+        if in_string {
+            out.push(c);
+            if c == '\\' && i + 1 < chars.len() {
+                out.push(chars[i + 1]);
+                i += 1;
+            } else if c == '"' {
+                in_string = false;
+            }
+            i += 1;
+            continue;
+        }
+        match (c, chars.get(i + 1)) {
+            ('"', _) => {
+                in_string = true;
+                out.push(c);
+            }
+            ('/', Some('/')) => {
+                while i < chars.len() && chars[i] != '\n' {
+                    out.push(' ');
+                    i += 1;
+                }
+                continue;
+            }
+            ('/', Some('*')) => {
+                let end = (i + 2..chars.len().saturating_sub(1))
+                    .find(|&j| chars[j] == '*' && chars[j + 1] == '/')
+                    .map_or(chars.len(), |j| j + 2);
+                for &skipped in &chars[i..end] {
+                    out.push(if skipped == '\n' { '\n' } else { ' ' });
+                }
+                i = end;
+                continue;
+            }
+            (',', _) => {
+                let next = chars[i + 1..].iter().find(|c| !c.is_whitespace());
+                out.push(if matches!(next, Some('}') | Some(']')) { ' ' } else { ',' });
+            }
+            _ => out.push(c),
+        }
+        i += 1;
+    }
+    out
+}
+
+/// `%APPDATA%` on Windows
+pub(crate) fn appdata_dir() -> Result<PathBuf> {
+    std::env::var("APPDATA")
+        .map(PathBuf::from)
+        .map_err(|e| anyhow!("Failed to get APPDATA: {}", e))
+}
+
+/// Per-directory exclude file with gitignore-style patterns, honored by
+/// every collector on top of the app's own skip list
+pub(crate) const IGNORE_FILE_NAME: &str = ".savemeignore";
+
+/// `App::config_subset` for apps whose subsets are single files: look
+/// `subset` up in `files` (subset name, path relative to `root`) and return
+/// the file if it exists
+pub(crate) fn subset_file(
+    app: &dyn App,
+    root: &Path,
+    files: &[(&str, &str)],
+    subset: &str,
+) -> Result<Vec<PathBuf>> {
+    let (_, relative) = files
+        .iter()
+        .find(|(name, _)| *name == subset)
+        .ok_or_else(|| anyhow!("{} has no '{}' config subset", app.name(), subset))?;
+    let path = root.join(relative);
+    Ok(if path.is_file() { vec![path] } else { Vec::new() })
+}
+
+/// Linux config directory `name` (as under `~/.config`) of an app that can
+/// also be installed as a snap or flatpak, which keep their config inside
+/// the sandbox: `~/snap/<snap>/current/.config/<name>` and
+/// `~/.var/app/<flatpak_id>/config/<name>`. `native` wins when it exists or
+/// when neither sandboxed copy does, so restores still target it.
+pub(crate) fn sandboxed_config_dir(
+    native: PathBuf,
+    home: &Path,
+    name: &str,
+    snap: Option<&str>,
+    flatpak_id: Option<&str>,
+) -> PathBuf {
+    if native.exists() {
+        return native;
+    }
+    let snap_dir = snap.map(|snap| home.join("snap").join(snap).join("current/.config").join(name));
+    let flatpak_dir = flatpak_id.map(|id| home.join(".var/app").join(id).join("config").join(name));
+    snap_dir.into_iter().chain(flatpak_dir).find(|dir| dir.exists()).unwrap_or(native)
+}
+
+/// Recursively collect every file under `dir`, skipping directories whose
+/// name is in `skip_dirs` (logs, caches and other non-config data), anything
+/// the collector settings ignore (VCS metadata, OS cruft, and dotfiles when
+/// `include_hidden` is off) and anything matched by a `.savemeignore` in the
+/// directory or one of its collected parents. Stops early during a bounded
+/// walk (see `bounded_config_path`).
+pub(crate) fn collect_files_recursive(
+    dir: &Path,
+    skip_dirs: &[&str],
+    files: &mut Vec<PathBuf>,
+) -> Result<()> {
+    let settings = crate::settings::current();
+    collect_filtered(dir, skip_dirs, &settings, &mut Vec::new(), files)
+}
+
+/// `collect_files_recursive`, dropping files above the
+/// `resource_size_limit_mb` setting, for apps whose directories mix small
+/// settings with large resources
+pub(crate) fn collect_small_files(
+    dir: &Path,
+    skip_dirs: &[&str],
+    files: &mut Vec<PathBuf>,
+) -> Result<()> {
+    let limit = crate::settings::current().resource_size_limit_mb * 1024 * 1024;
+    let mut collected = Vec::new();
+    collect_files_recursive(dir, skip_dirs, &mut collected)?;
+    files.extend(collected.into_iter().filter(|path| {
+        path.metadata().map(|m| m.len() <= limit).unwrap_or(false)
+    }));
+    Ok(())
+}
+
+/// Parse `dir/.savemeignore`, with patterns anchored at `dir`
+fn load_ignore_file(dir: &Path) -> Result<Option<Gitignore>> {
+    let ignore_file = dir.join(IGNORE_FILE_NAME);
+    if !ignore_file.is_file() {
+        return Ok(None);
+    }
+    let mut builder = GitignoreBuilder::new(dir);
+    if let Some(e) = builder.add(&ignore_file) {
+        return Err(anyhow!("Invalid {}: {}", ignore_file.display(), e));
+    }
+    let matcher = builder
+        .build()
+        .map_err(|e| anyhow!("Invalid {}: {}", ignore_file.display(), e))?;
+    Ok(Some(matcher))
+}
+
+/// Whether the innermost `.savemeignore` with an opinion on `path` excludes
+/// it; a `!pattern` in a nested file re-includes what a parent excluded
+fn is_excluded(matchers: &[Gitignore], path: &Path, is_dir: bool) -> bool {
+    for matcher in matchers.iter().rev() {
+        match matcher.matched(path, is_dir) {
+            Match::Ignore(_) => return true,
+            Match::Whitelist(_) => return false,
+            Match::None => {}
+        }
+    }
+    false
+}
+
+fn collect_filtered(
+    dir: &Path,
+    skip_dirs: &[&str],
+    settings: &Settings,
+    matchers: &mut Vec<Gitignore>,
+    files: &mut Vec<PathBuf>,
+) -> Result<()> {
+    let own_matcher = load_ignore_file(dir)?;
+    let pushed = own_matcher.is_some();
+    matchers.extend(own_matcher);
+
+    for entry in std::fs::read_dir(dir)
+        .map_err(|e| anyhow!("Failed to read directory {}: {}", dir.display(), e))?
+    {
+        if WALK_BUDGET.with(|budget| budget.get()) == Some(0) {
+            break;
+        }
+        let entry = entry.map_err(|e| anyhow!("Failed to read directory entry: {}", e))?;
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if settings.is_ignored_name(&name) {
+            continue;
+        }
+        if path.is_file() {
+            if !is_excluded(matchers, &path, false) {
+                files.push(path);
+                WALK_BUDGET.with(|budget| budget.set(budget.get().map(|left| left - 1)));
+            }
+        } else if path.is_dir() {
+            if skip_dirs.iter().any(|skip| name == *skip) || is_excluded(matchers, &path, true) {
+                continue;
+            }
+            collect_filtered(&path, skip_dirs, settings, matchers, files)?;
+        }
+    }
+
+    if pushed {
+        matchers.pop();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct CountingApp {
+        calls: AtomicUsize,
+    }
+
+    impl App for CountingApp {
+        fn id(&self) -> &'static str {
+            "test-counting-app"
+        }
+        fn name(&self) -> &'static str {
+            "Counting App"
+        }
+        fn is_installed(&self) -> bool {
+            true
+        }
+        fn config_path(&self) -> Result<Vec<PathBuf>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(vec![PathBuf::from("/tmp/counting/settings.json")])
+        }
+        fn app_path(&self) -> Result<PathBuf> {
+            Ok(PathBuf::from("/tmp/counting"))
+        }
+        fn target_hint(&self) -> &'static str {
+            "app:test-counting"
+        }
+        fn package_id(&self) -> Option<&'static str> {
+            None
+        }
+    }
+
+    #[test]
+    fn test_config_path_cache_and_invalidation() -> Result<()> {
+        let app = CountingApp { calls: AtomicUsize::new(0) };
+
+        let first = cached_config_path(&app)?;
+        let second = cached_config_path(&app)?;
+        assert_eq!(first, second);
+        assert_eq!(app.calls.load(Ordering::SeqCst), 1);
+
+        invalidate_config_path_cache();
+        cached_config_path(&app)?;
+        assert_eq!(app.calls.load(Ordering::SeqCst), 2);
+        Ok(())
+    }
+
+    /// App whose config is every file under `root`
+    struct WalkingApp {
+        root: PathBuf,
+    }
+
+    impl App for WalkingApp {
+        fn id(&self) -> &'static str {
+            "test-walking-app"
+        }
+        fn name(&self) -> &'static str {
+            "Walking App"
+        }
+        fn is_installed(&self) -> bool {
+            true
+        }
+        fn config_path(&self) -> Result<Vec<PathBuf>> {
+            let mut files = Vec::new();
+            collect_files_recursive(&self.root, &[], &mut files)?;
+            Ok(files)
+        }
+        fn app_path(&self) -> Result<PathBuf> {
+            Ok(self.root.clone())
+        }
+        fn target_hint(&self) -> &'static str {
+            "app:test-walking"
+        }
+        fn package_id(&self) -> Option<&'static str> {
+            None
+        }
+    }
+
+    #[test]
+    fn test_bounded_config_walk_stops_early_and_only_caches_full_lists() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        for dir in ["a", "b"] {
+            std::fs::create_dir_all(temp_dir.path().join(dir))?;
+            for i in 0..5 {
+                std::fs::write(temp_dir.path().join(dir).join(format!("{}.conf", i)), b"x")?;
+            }
+        }
+        let app = WalkingApp { root: temp_dir.path().to_path_buf() };
+
+        let (paths, cut_short) = bounded_config_path(&app, 3)?;
+        assert_eq!((paths.len(), cut_short), (3, true));
+        assert!(fresh_cached_paths(&app).is_none());
+        assert_eq!(WALK_BUDGET.with(|budget| budget.get()), None);
+
+        let (paths, cut_short) = bounded_config_path(&app, 100)?;
+        assert_eq!((paths.len(), cut_short), (10, false));
+        assert_eq!(fresh_cached_paths(&app).map(|paths| paths.len()), Some(10));
+        assert_eq!(config_summary(&app), (true, 10));
+        Ok(())
+    }
+
+    struct BrokenApp {
+        path: fn() -> Result<PathBuf>,
+    }
+
+    impl App for BrokenApp {
+        fn id(&self) -> &'static str {
+            "test-broken-app"
+        }
+        fn name(&self) -> &'static str {
+            "Broken App"
+        }
+        fn is_installed(&self) -> bool {
+            self.app_path().map(|p| p.exists()).unwrap_or(false)
+        }
+        fn config_path(&self) -> Result<Vec<PathBuf>> {
+            Ok(Vec::new())
+        }
+        fn app_path(&self) -> Result<PathBuf> {
+            (self.path)()
+        }
+        fn target_hint(&self) -> &'static str {
+            "app:test-broken"
+        }
+        fn package_id(&self) -> Option<&'static str> {
+            None
+        }
+    }
+
+    #[test]
+    fn test_app_status_explains_unavailable_apps() {
+        let status = |path| app_status(&BrokenApp { path });
+        assert_eq!(
+            status(|| Err(UnsupportedOs { app: "Broken App", os: "Plan 9" }.into())),
+            AppStatus::UnsupportedOS
+        );
+        assert_eq!(
+            status(|| Err(anyhow!("APPDATA is not set"))),
+            AppStatus::DetectionError("APPDATA is not set".to_string())
+        );
+        assert_eq!(status(|| Ok(PathBuf::from("/nonexistent/broken"))), AppStatus::NotInstalled);
+        assert_eq!(status(|| Ok(std::env::temp_dir())), AppStatus::Installed);
+        assert_eq!(app_status(&CountingApp { calls: AtomicUsize::new(0) }), AppStatus::Installed);
+    }
+
+    #[test]
+    fn test_builtin_apps_have_a_category() {
+        for app in BUILTIN_APPS.iter() {
+            assert_ne!(app.category(), "Other", "{} has no category", app.id());
+        }
+        assert_eq!(CountingApp { calls: AtomicUsize::new(0) }.category(), "Other");
+        assert_eq!(get_app("ghostty").unwrap().category(), "Terminal");
+    }
+
+    #[test]
+    fn test_register_app_at_runtime() -> Result<()> {
+        // A local registry, so the global one other tests read is untouched
+        let registry = RwLock::new(BUILTIN_APPS.clone());
+        register_in(&registry, Arc::new(CountingApp { calls: AtomicUsize::new(0) }))?;
+        let app = find_in(&registry, |app| app.id() == "test-counting-app")
+            .expect("registered app is found");
+        assert_eq!(app.name(), "Counting App");
+        assert!(find_in(&registry, |app| app.owns_target_hint("app:test-counting")).is_some());
+        assert!(get_app("test-counting-app").is_none());
+
+        let taken = register_in(&registry, Arc::new(CountingApp { calls: AtomicUsize::new(0) }));
+        assert!(taken.is_err());
+        let builtin = register_in(&registry, Arc::new(git::Git));
+        assert!(builtin.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_subset_file_picks_the_named_file() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("User"))?;
+        std::fs::write(root.join("User").join("keybindings.json"), b"[]")?;
+        let app = CountingApp { calls: AtomicUsize::new(0) };
+        let files = [("keymap", "User/keybindings.json"), ("settings", "User/settings.json")];
+
+        let keymap = subset_file(&app, root, &files, "keymap")?;
+        assert_eq!(keymap, [root.join("User/keybindings.json")]);
+        // Known but not created yet, versus not a subset at all
+        assert!(subset_file(&app, root, &files, "settings")?.is_empty());
+        assert!(subset_file(&app, root, &files, "snippets").is_err());
+        assert!(app.config_subsets().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_config_syntax_accepts_editor_json() {
+        let settings = "// Zed settings\n{\n  \"theme\": \"One Dark\", /* inline */\n  \
+                        \"url\": \"https://example.com\",\n  \"list\": [1, 2,],\n}\n";
+        assert!(validate_config_syntax(Path::new("settings.json"), settings.as_bytes()).is_ok());
+
+        let broken = "{\n  \"theme\": \"One Dark\"\n  \"buffer_font_size\": 15\n";
+        let error = validate_config_syntax(Path::new("settings.json"), broken.as_bytes());
+        assert!(error.unwrap_err().contains("line 3"));
+
+        assert!(validate_config_syntax(Path::new("config.toml"), b"a = [1,").is_err());
+        assert!(validate_config_syntax(Path::new("keymap.bin"), b"{").is_ok());
+    }
+
+    #[test]
+    fn test_savemeignore_applies_relative_to_its_directory() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let root = temp_dir.path();
+        let write = |rel: &str, data: &str| -> Result<()> {
+            let path = root.join(rel);
+            std::fs::create_dir_all(path.parent().unwrap())?;
+            std::fs::write(path, data)?;
+            Ok(())
+        };
+        write("settings.json", "{}")?;
+        write("huge.bin", "x")?;
+        write("cache/blob", "x")?;
+        write("logs/today.log", "x")?;
+        write("brushes/soft.gbr", "x")?;
+        write("brushes/big.gbr", "x")?;
+        write("brushes/keep.bin", "x")?;
+        write(IGNORE_FILE_NAME, "*.bin\ncache/\n")?;
+        // Nested file: anchored at brushes/, and re-includes one .bin
+        write("brushes/.savemeignore", "/big.gbr\n!keep.bin\n")?;
+
+        let settings = Settings {
+            include_hidden: false,
+            ..Default::default()
+        };
+        let mut files = Vec::new();
+        collect_filtered(root, &["logs"], &settings, &mut Vec::new(), &mut files)?;
+        files.sort();
+
+        let expected: Vec<PathBuf> = ["brushes/keep.bin", "brushes/soft.gbr", "settings.json"]
+            .iter()
+            .map(|rel| root.join(rel))
+            .collect();
+        assert_eq!(files, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sandboxed_config_dir_falls_back_to_the_native_one() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let home = temp_dir.path();
+        let native = home.join(".config/Code");
+        let flatpak_id = Some("com.visualstudio.code");
+        let probe = || sandboxed_config_dir(native.clone(), home, "Code", Some("code"), flatpak_id);
+        assert_eq!(probe(), native);
+
+        let flatpak = home.join(".var/app/com.visualstudio.code/config/Code");
+        std::fs::create_dir_all(&flatpak)?;
+        assert_eq!(probe(), flatpak);
+        let snap = home.join("snap/code/current/.config/Code");
+        std::fs::create_dir_all(&snap)?;
+        assert_eq!(probe(), snap);
+
+        std::fs::create_dir_all(&native)?;
+        assert_eq!(probe(), native);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sensitive_apps_require_encryption() {
+        let plain = Settings::default();
+        let encrypted = Settings {
+            encrypt_blobs: true,
+            ..Default::default()
+        };
+
+        assert!(ensure_backup_allowed(&slack::Slack, &plain).is_err());
+        assert!(ensure_backup_allowed(&telegram::Telegram, &plain).is_err());
+        assert!(ensure_backup_allowed(&slack::Slack, &encrypted).is_ok());
+        assert!(ensure_backup_allowed(&zed::Zed, &plain).is_ok());
+
+        // Credentials need the explicit opt-in on top of encryption
+        let opted_in = Settings {
+            include_credentials: true,
+            ..Default::default()
+        };
+        let both = Settings {
+            encrypt_blobs: true,
+            include_credentials: true,
+            ..Default::default()
+        };
+        let nm = network::NetworkManager;
+        assert!(ensure_backup_allowed(&nm, &encrypted).is_err());
+        assert!(ensure_backup_allowed(&nm, &opted_in).is_err());
+        assert!(ensure_backup_allowed(&nm, &both).is_ok());
+    }
 }
@@ -0,0 +1,56 @@
+use super::{collect_files_recursive, command_exists, xdg_config_home, App};
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+
+/// GNU nano: `~/.nanorc` and everything under `~/.config/nano` (the XDG
+/// `nanorc` and any included syntax files). Search and position history
+/// live in `~/.local/share/nano` and are not backed up.
+pub struct Nano;
+
+impl App for Nano {
+    fn id(&self) -> &'static str {
+        "nano"
+    }
+
+    fn name(&self) -> &'static str {
+        "GNU nano"
+    }
+
+    fn category(&self) -> &'static str {
+        "Editor"
+    }
+
+    fn is_installed(&self) -> bool {
+        command_exists("nano")
+    }
+
+    fn target_hint(&self) -> &'static str {
+        "app:nano"
+    }
+
+    fn package_id(&self) -> Option<&'static str> {
+        if cfg!(target_os = "windows") {
+            Some("GNU.Nano")
+        } else {
+            Some("nano")
+        }
+    }
+
+    /// The home directory, since `.nanorc` sits directly in it
+    fn app_path(&self) -> Result<PathBuf> {
+        dirs::home_dir().ok_or_else(|| anyhow!("Could not get home directory"))
+    }
+
+    fn config_path(&self) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        let nanorc = self.app_path()?.join(".nanorc");
+        if nanorc.is_file() {
+            files.push(nanorc);
+        }
+        let config_dir = xdg_config_home()?.join("nano");
+        if config_dir.is_dir() {
+            collect_files_recursive(&config_dir, &[], &mut files)?;
+        }
+        Ok(files)
+    }
+}
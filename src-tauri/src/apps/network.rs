@@ -0,0 +1,73 @@
+use super::{command_exists, App, AppCapabilities, UnsupportedOs};
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+
+/// NetworkManager connection profiles (`*.nmconnection`) for Wi-Fi, VPN and
+/// wired networks.
+///
+/// These files hold PSKs and VPN secrets in plain text, so they are only
+/// backed up with blob encryption and the `include_credentials` opt-in, and
+/// are restored with their original `0600` mode. The directory is root-only:
+/// both backup and restore may require running SaveMe elevated.
+pub struct NetworkManager;
+
+impl App for NetworkManager {
+    fn id(&self) -> &'static str {
+        "networkmanager"
+    }
+
+    fn name(&self) -> &'static str {
+        "NetworkManager"
+    }
+
+    fn category(&self) -> &'static str {
+        "System"
+    }
+
+    fn capabilities(&self) -> AppCapabilities {
+        AppCapabilities {
+            sensitive: true,
+            credentials: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_installed(&self) -> bool {
+        cfg!(target_os = "linux") && command_exists("nmcli")
+    }
+
+    fn target_hint(&self) -> &'static str {
+        "sys:networkmanager"
+    }
+
+    fn package_id(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn app_path(&self) -> Result<PathBuf> {
+        if !cfg!(target_os = "linux") {
+            return Err(UnsupportedOs { app: "NetworkManager", os: "Linux" }.into());
+        }
+        Ok(PathBuf::from("/etc/NetworkManager/system-connections"))
+    }
+
+    fn config_path(&self) -> Result<Vec<PathBuf>> {
+        let connections_dir = self.app_path()?;
+        let mut files = Vec::new();
+
+        for entry in std::fs::read_dir(&connections_dir).map_err(|e| {
+            anyhow!(
+                "Failed to read {} (try running elevated): {}",
+                connections_dir.display(),
+                e
+            )
+        })? {
+            let path = entry?.path();
+            if path.is_file() && path.extension().and_then(|e| e.to_str()) == Some("nmconnection") {
+                files.push(path);
+            }
+        }
+
+        Ok(files)
+    }
+}
@@ -0,0 +1,178 @@
+use super::{collect_files_recursive, App};
+use anyhow::{anyhow, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Trash folders of Logseq, Obsidian and Joplin exports
+const NOTES_SKIP_DIRS: &[&str] = &[".trash", ".recycle"];
+
+/// Note formats backed up whatever their size
+const TEXT_EXTENSIONS: &[&str] = &["md", "markdown", "org", "txt"];
+
+/// A `[[notes]]` table of `custom_apps.toml`
+#[derive(Deserialize, Debug, Clone)]
+pub struct NotesDirConfig {
+    pub id: String,
+    pub name: Option<String>,
+    /// Notes root; a leading `~/` is the home directory
+    pub root: String,
+    /// Globs relative to `root`; when empty every file is considered
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Size limit for attachments (anything but markdown/org/text), falling
+    /// back to the `resource_size_limit_mb` setting
+    pub max_attachment_mb: Option<u64>,
+}
+
+/// A user-configured notes directory (org-roam, Logseq graph, Joplin
+/// export...). Markdown, org and text notes are always kept; other files
+/// count as attachments and are skipped above the size limit. Paths are
+/// relative to the root, so restore recreates the same tree elsewhere.
+pub struct NotesDir {
+    id: &'static str,
+    name: &'static str,
+    hint: &'static str,
+    root: PathBuf,
+    include: GlobSet,
+    exclude: GlobSet,
+    max_attachment_mb: Option<u64>,
+}
+
+/// Strings handed out as `&'static str` by notes directories, so each
+/// distinct one is leaked once however often `custom_apps.toml` is reloaded
+static INTERNED: Lazy<Mutex<HashSet<&'static str>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+fn intern(value: String) -> &'static str {
+    let mut interned = INTERNED.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(existing) = interned.get(value.as_str()) {
+        return existing;
+    }
+    let leaked: &'static str = Box::leak(value.into_boxed_str());
+    interned.insert(leaked);
+    leaked
+}
+
+fn glob_set(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern).map_err(|e| anyhow!("Invalid glob '{}': {}", pattern, e))?);
+    }
+    Ok(builder.build()?)
+}
+
+fn expand_home(path: &str) -> Result<PathBuf> {
+    match path.strip_prefix("~/") {
+        Some(rest) => Ok(dirs::home_dir()
+            .ok_or_else(|| anyhow!("Could not get home directory"))?
+            .join(rest)),
+        None => Ok(PathBuf::from(path)),
+    }
+}
+
+impl NotesDir {
+    /// The strings `App` hands out as `&'static str` are interned
+    pub fn from_config(config: &NotesDirConfig) -> Result<Self> {
+        if config.id.is_empty() {
+            return Err(anyhow!("Notes directory needs an id"));
+        }
+        let name = config.name.clone().unwrap_or_else(|| config.id.clone());
+        Ok(Self {
+            id: intern(config.id.clone()),
+            name: intern(name),
+            hint: intern(format!("notes:{}", config.id)),
+            root: expand_home(&config.root)?,
+            include: glob_set(&config.include)?,
+            exclude: glob_set(&config.exclude)?,
+            max_attachment_mb: config.max_attachment_mb,
+        })
+    }
+
+    fn wanted(&self, relative: &Path, size: u64, limit: u64) -> bool {
+        if self.exclude.is_match(relative)
+            || (!self.include.is_empty() && !self.include.is_match(relative))
+        {
+            return false;
+        }
+        let is_text = relative
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| TEXT_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()));
+        is_text || size <= limit
+    }
+}
+
+impl App for NotesDir {
+    fn id(&self) -> &'static str {
+        self.id
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn category(&self) -> &'static str {
+        "Notes"
+    }
+
+    fn is_installed(&self) -> bool {
+        self.root.is_dir()
+    }
+
+    fn target_hint(&self) -> &'static str {
+        self.hint
+    }
+
+    fn package_id(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn app_path(&self) -> Result<PathBuf> {
+        Ok(self.root.clone())
+    }
+
+    fn config_path(&self) -> Result<Vec<PathBuf>> {
+        let limit_mb = self
+            .max_attachment_mb
+            .unwrap_or_else(|| crate::settings::current().resource_size_limit_mb);
+        let limit = limit_mb * 1024 * 1024;
+
+        let mut files = Vec::new();
+        collect_files_recursive(&self.root, NOTES_SKIP_DIRS, &mut files)
+            .map_err(|e| anyhow!("Failed to read notes in {}: {}", self.root.display(), e))?;
+        files.retain(|path| {
+            let relative = path.strip_prefix(&self.root).unwrap_or(path);
+            let size = path.metadata().map(|m| m.len()).unwrap_or(0);
+            self.wanted(relative, size, limit)
+        });
+        Ok(files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reloaded_notes_dirs_reuse_interned_strings() -> Result<()> {
+        let config = NotesDirConfig {
+            id: "test-interned-notes".to_string(),
+            name: None,
+            root: "/tmp/test-interned-notes".to_string(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            max_attachment_mb: None,
+        };
+        let first = NotesDir::from_config(&config)?;
+        let second = NotesDir::from_config(&config)?;
+        assert!(std::ptr::eq(first.id(), second.id()));
+        assert!(std::ptr::eq(first.target_hint(), second.target_hint()));
+        assert_eq!(second.name(), "test-interned-notes");
+        Ok(())
+    }
+}
@@ -0,0 +1,78 @@
+use super::{appdata_dir, collect_files_recursive, xdg_config_home, App};
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+
+/// Directories under the OBS config root that hold logs, crash dumps and
+/// runtime data rather than configuration
+const OBS_SKIP_DIRS: &[&str] = &["logs", "crashes", "updates", "profiler_data", "plugin_manager"];
+
+/// OBS Studio scene collections (`basic/scenes`), profiles (`basic/profiles`)
+/// and global settings.
+///
+/// Scene collections reference media (images, videos, browser sources) by
+/// absolute path. Only the JSON is captured, linked media is not, and files
+/// are restored relative to the OBS config root.
+pub struct OBS;
+
+impl App for OBS {
+    fn id(&self) -> &'static str {
+        "obs"
+    }
+
+    fn name(&self) -> &'static str {
+        "OBS Studio"
+    }
+
+    fn category(&self) -> &'static str {
+        "Media"
+    }
+
+    fn is_installed(&self) -> bool {
+        self.app_path().map(|p| p.exists()).unwrap_or(false)
+    }
+
+    fn target_hint(&self) -> &'static str {
+        "app:obs"
+    }
+
+    fn package_id(&self) -> Option<&'static str> {
+        if cfg!(target_os = "windows") {
+            Some("OBSProject.OBSStudio")
+        } else if cfg!(target_os = "macos") {
+            Some("obs")
+        } else {
+            Some("obs-studio")
+        }
+    }
+
+    fn app_path(&self) -> Result<PathBuf> {
+        let config_dir = if cfg!(target_os = "windows") {
+            appdata_dir()?
+        } else if cfg!(target_os = "macos") {
+            dirs::home_dir()
+                .ok_or_else(|| anyhow!("Could not get home directory"))?
+                .join("Library/Application Support")
+        } else {
+            xdg_config_home()?
+        };
+        Ok(config_dir.join("obs-studio"))
+    }
+
+    fn config_path(&self) -> Result<Vec<PathBuf>> {
+        let obs_dir = self.app_path()?;
+        let mut files = Vec::new();
+
+        collect_files_recursive(&obs_dir, OBS_SKIP_DIRS, &mut files)
+            .map_err(|e| anyhow!("Failed to read OBS config directory: {}", e))?;
+
+        // Recordings default to the videos folder, but skip any that ended up here
+        files.retain(|path| {
+            !matches!(
+                path.extension().and_then(|e| e.to_str()),
+                Some("mkv" | "mp4" | "flv" | "mov" | "ts" | "log")
+            )
+        });
+
+        Ok(files)
+    }
+}
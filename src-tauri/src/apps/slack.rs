@@ -0,0 +1,88 @@
+use super::{appdata_dir, xdg_config_home, App, AppCapabilities};
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+
+/// Slack desktop preferences: the top-level JSON settings and the `storage`
+/// directory holding window/workspace state. Caches, IndexedDB, Local Storage
+/// and other Chromium data where messages and session cookies live are left
+/// out, but workspace state can still identify the account, so Slack is
+/// treated as sensitive.
+pub struct Slack;
+
+impl App for Slack {
+    fn id(&self) -> &'static str {
+        "slack"
+    }
+
+    fn name(&self) -> &'static str {
+        "Slack"
+    }
+
+    fn category(&self) -> &'static str {
+        "Chat"
+    }
+
+    fn capabilities(&self) -> AppCapabilities {
+        AppCapabilities {
+            sensitive: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_installed(&self) -> bool {
+        self.app_path().map(|p| p.exists()).unwrap_or(false)
+    }
+
+    fn target_hint(&self) -> &'static str {
+        "app:slack"
+    }
+
+    fn package_id(&self) -> Option<&'static str> {
+        if cfg!(target_os = "windows") {
+            Some("SlackTechnologies.Slack")
+        } else {
+            Some("slack")
+        }
+    }
+
+    fn aur_package_id(&self) -> Option<&'static str> {
+        Some("slack-desktop")
+    }
+
+    fn app_path(&self) -> Result<PathBuf> {
+        let config_dir = if cfg!(target_os = "windows") {
+            appdata_dir()?
+        } else if cfg!(target_os = "macos") {
+            dirs::home_dir()
+                .ok_or_else(|| anyhow!("Could not get home directory"))?
+                .join("Library/Application Support")
+        } else {
+            xdg_config_home()?
+        };
+        Ok(config_dir.join("Slack"))
+    }
+
+    fn config_path(&self) -> Result<Vec<PathBuf>> {
+        let slack_dir = self.app_path()?;
+        let mut files = Vec::new();
+
+        for dir in [slack_dir.clone(), slack_dir.join("storage")] {
+            if !dir.is_dir() {
+                continue;
+            }
+            for entry in std::fs::read_dir(&dir)
+                .map_err(|e| anyhow!("Failed to read Slack config directory: {}", e))?
+            {
+                let path = entry?.path();
+                // Top level only keeps JSON settings; `storage` is all state files
+                let is_settings = dir != slack_dir
+                    || path.extension().and_then(|e| e.to_str()) == Some("json");
+                if path.is_file() && is_settings {
+                    files.push(path);
+                }
+            }
+        }
+
+        Ok(files)
+    }
+}
@@ -0,0 +1,154 @@
+use super::{collect_files_recursive, command_exists, xdg_config_home, App};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+/// Build and cache directories found next to widget sources (compiled Rust
+/// or C helpers, Python bytecode, npm installs); they are rebuilt from the
+/// sources and don't belong in a backup
+const STATUS_BAR_SKIP_DIRS: &[&str] = &[
+    ".git",
+    "__pycache__",
+    "node_modules",
+    "target",
+    "build",
+    ".cache",
+];
+
+/// Compiled artifacts left by helper scripts
+const STATUS_BAR_SKIP_EXTENSIONS: &[&str] = &["pyc", "o", "so"];
+
+/// A status bar configured through one directory under `$XDG_CONFIG_HOME`.
+/// Bar configs `exec` helper scripts by paths relative to that directory,
+/// so the whole tree is backed up and restored with its layout intact
+/// rather than just the main config file.
+pub struct StatusBar {
+    id: &'static str,
+    name: &'static str,
+    target_hint: &'static str,
+    package_id: Option<&'static str>,
+    /// Binary looked up on `PATH`
+    command: &'static str,
+    /// Config directory under `$XDG_CONFIG_HOME`
+    config_dir: &'static str,
+}
+
+pub const WAYBAR: StatusBar = StatusBar {
+    id: "waybar",
+    name: "Waybar",
+    target_hint: "app:waybar",
+    package_id: Some("waybar"),
+    command: "waybar",
+    config_dir: "waybar",
+};
+
+pub const POLYBAR: StatusBar = StatusBar {
+    id: "polybar",
+    name: "Polybar",
+    target_hint: "app:polybar",
+    package_id: Some("polybar"),
+    command: "polybar",
+    config_dir: "polybar",
+};
+
+/// eww (ElKowar's wacky widgets) is rarely packaged, so it can't be installed
+pub const EWW: StatusBar = StatusBar {
+    id: "eww",
+    name: "eww",
+    target_hint: "app:eww",
+    package_id: None,
+    command: "eww",
+    config_dir: "eww",
+};
+
+/// Every file of a status bar config directory except build artifacts
+fn bar_files(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    collect_files_recursive(root, STATUS_BAR_SKIP_DIRS, &mut files)?;
+    files.retain(|path| {
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+        !STATUS_BAR_SKIP_EXTENSIONS.contains(&extension)
+    });
+    Ok(files)
+}
+
+impl App for StatusBar {
+    fn id(&self) -> &'static str {
+        self.id
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn category(&self) -> &'static str {
+        "Desktop"
+    }
+
+    fn is_installed(&self) -> bool {
+        command_exists(self.command) || self.app_path().map(|p| p.is_dir()).unwrap_or(false)
+    }
+
+    fn target_hint(&self) -> &'static str {
+        self.target_hint
+    }
+
+    fn package_id(&self) -> Option<&'static str> {
+        self.package_id
+    }
+
+    fn app_path(&self) -> Result<PathBuf> {
+        Ok(xdg_config_home()?.join(self.config_dir))
+    }
+
+    fn config_path(&self) -> Result<Vec<PathBuf>> {
+        let root = self.app_path()?;
+        if !root.is_dir() {
+            return Ok(Vec::new());
+        }
+        bar_files(&root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_bar_files_keep_scripts_and_skip_build_artifacts() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        for dir in ["scripts/__pycache__", "widgets/clock/target/release"] {
+            std::fs::create_dir_all(root.join(dir))?;
+        }
+        for file in [
+            "eww.yuck",
+            "eww.scss",
+            "scripts/battery.sh",
+            "scripts/weather.py",
+            "scripts/__pycache__/weather.cpython-312.pyc",
+            "scripts/helper.o",
+            "widgets/clock/main.rs",
+            "widgets/clock/target/release/clock",
+        ] {
+            std::fs::write(root.join(file), b"x")?;
+        }
+
+        let mut files: Vec<_> = bar_files(root)?
+            .iter()
+            .map(|path| path.strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect();
+        files.sort();
+        assert_eq!(
+            files,
+            [
+                "eww.scss",
+                "eww.yuck",
+                "scripts/battery.sh",
+                "scripts/weather.py",
+                "widgets/clock/main.rs"
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+use super::{appdata_dir, App, AppCapabilities};
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+
+/// Settings files inside `tdata`. Everything else there (`key_datas`, the
+/// per-account `D877F783D5D3EF8C*` folders, media cache) is the session and
+/// message store and must never be copied to another machine.
+const TELEGRAM_SETTINGS_FILES: &[&str] = &["settingss", "shortcuts-custom.json"];
+
+/// Telegram Desktop settings and custom shortcuts only. The settings file
+/// still carries account-adjacent data such as proxy credentials, so
+/// Telegram is treated as sensitive.
+pub struct Telegram;
+
+impl App for Telegram {
+    fn id(&self) -> &'static str {
+        "telegram"
+    }
+
+    fn name(&self) -> &'static str {
+        "Telegram Desktop"
+    }
+
+    fn category(&self) -> &'static str {
+        "Chat"
+    }
+
+    fn capabilities(&self) -> AppCapabilities {
+        AppCapabilities {
+            sensitive: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_installed(&self) -> bool {
+        self.app_path().map(|p| p.exists()).unwrap_or(false)
+    }
+
+    fn target_hint(&self) -> &'static str {
+        "app:telegram"
+    }
+
+    fn package_id(&self) -> Option<&'static str> {
+        if cfg!(target_os = "windows") {
+            Some("Telegram.TelegramDesktop")
+        } else {
+            Some("telegram-desktop")
+        }
+    }
+
+    fn app_path(&self) -> Result<PathBuf> {
+        let data_dir = if cfg!(target_os = "windows") {
+            appdata_dir()?.join("Telegram Desktop")
+        } else if cfg!(target_os = "macos") {
+            dirs::home_dir()
+                .ok_or_else(|| anyhow!("Could not get home directory"))?
+                .join("Library/Application Support/Telegram Desktop")
+        } else {
+            dirs::data_dir()
+                .ok_or_else(|| anyhow!("Could not get data directory"))?
+                .join("TelegramDesktop")
+        };
+        Ok(data_dir.join("tdata"))
+    }
+
+    fn config_path(&self) -> Result<Vec<PathBuf>> {
+        let tdata = self.app_path()?;
+
+        Ok(TELEGRAM_SETTINGS_FILES
+            .iter()
+            .map(|name| tdata.join(name))
+            .filter(|path| path.is_file())
+            .collect())
+    }
+}
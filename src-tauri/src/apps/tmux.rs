@@ -0,0 +1,81 @@
+use super::{command_exists, xdg_config_home, App};
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+
+/// tmux: a single config file, `~/.tmux.conf` or the XDG
+/// `~/.config/tmux/tmux.conf`. Both are backed up when both exist, since
+/// one may `source-file` the other.
+pub struct Tmux;
+
+/// The candidate tmux config files under `home` and `config_home` that exist
+fn tmux_files(home: &Path, config_home: &Path) -> Vec<PathBuf> {
+    [home.join(".tmux.conf"), config_home.join("tmux").join("tmux.conf")]
+        .into_iter()
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+impl App for Tmux {
+    fn id(&self) -> &'static str {
+        "tmux"
+    }
+
+    fn name(&self) -> &'static str {
+        "tmux"
+    }
+
+    fn category(&self) -> &'static str {
+        "Terminal"
+    }
+
+    /// The binary, as tmux runs fine without any config file
+    fn is_installed(&self) -> bool {
+        command_exists("tmux")
+    }
+
+    fn target_hint(&self) -> &'static str {
+        "app:tmux"
+    }
+
+    fn package_id(&self) -> Option<&'static str> {
+        if cfg!(target_os = "windows") {
+            None
+        } else {
+            Some("tmux")
+        }
+    }
+
+    /// The home directory, since `.tmux.conf` sits directly in it
+    fn app_path(&self) -> Result<PathBuf> {
+        dirs::home_dir().ok_or_else(|| anyhow!("Could not get home directory"))
+    }
+
+    fn config_path(&self) -> Result<Vec<PathBuf>> {
+        Ok(tmux_files(&self.app_path()?, &xdg_config_home()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_tmux_files_are_the_existing_candidates() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let home = temp_dir.path();
+        let config_home = home.join(".config");
+        assert!(tmux_files(home, &config_home).is_empty());
+
+        std::fs::create_dir_all(config_home.join("tmux"))?;
+        std::fs::write(config_home.join("tmux").join("tmux.conf"), b"set -g mouse on")?;
+        assert_eq!(
+            tmux_files(home, &config_home),
+            vec![config_home.join("tmux").join("tmux.conf")]
+        );
+
+        std::fs::write(home.join(".tmux.conf"), b"source-file ~/.config/tmux/tmux.conf")?;
+        assert_eq!(tmux_files(home, &config_home).len(), 2);
+        Ok(())
+    }
+}
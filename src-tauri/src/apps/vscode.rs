@@ -1,9 +1,15 @@
-use super::App;
+use super::{sandboxed_config_dir, subset_file, validate_config_syntax, App};
 use anyhow::{anyhow, Result};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub struct VSCode;
 
+/// `config_subset` names and the file each one is, under `app_path`
+const SUBSETS: &[(&str, &str)] = &[
+    ("keymap", "User/keybindings.json"),
+    ("settings", "User/settings.json"),
+];
+
 impl App for VSCode {
     fn id(&self) -> &'static str {
         "vscode"
@@ -13,8 +19,8 @@ impl App for VSCode {
         "Visual Studio Code"
     }
 
-    fn snap_support(&self) -> bool {
-        false
+    fn category(&self) -> &'static str {
+        "Editor"
     }
 
     fn is_installed(&self) -> bool {
@@ -50,12 +56,22 @@ impl App for VSCode {
                 .ok_or_else(|| anyhow!("Could not get home directory"))?
                 .join("Library/Application Support/Code")
         } else {
-            // Linux
-            std::env::var("XDG_CONFIG_HOME")
+            // Linux, where the snap and flatpak keep their own copy
+            let native = std::env::var("XDG_CONFIG_HOME")
                 .map(PathBuf::from)
                 .or_else(|_| std::env::var("HOME").map(|h| PathBuf::from(h).join(".config")))
                 .map_err(|e| anyhow!("Failed to get config dir: {}", e))?
-                .join("Code")
+                .join("Code");
+            match dirs::home_dir() {
+                Some(home) => sandboxed_config_dir(
+                    native,
+                    &home,
+                    "Code",
+                    Some("code"),
+                    Some("com.visualstudio.code"),
+                ),
+                None => native,
+            }
         };
         Ok(app_dir)
     }
@@ -76,4 +92,16 @@ impl App for VSCode {
 
         Ok(files)
     }
+
+    fn config_subsets(&self) -> Vec<&'static str> {
+        SUBSETS.iter().map(|(name, _)| *name).collect()
+    }
+
+    fn config_subset(&self, subset: &str) -> Result<Vec<PathBuf>> {
+        subset_file(self, &self.app_path()?, SUBSETS, subset)
+    }
+
+    fn validate(&self, path: &Path, data: &[u8]) -> Result<(), String> {
+        validate_config_syntax(path, data)
+    }
 }
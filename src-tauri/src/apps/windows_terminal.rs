@@ -1,9 +1,33 @@
-use super::App;
+use super::{validate_config_syntax, App, UnsupportedOs};
 use anyhow::{anyhow, Result};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+/// Window and tab layout Windows Terminal rewrites on every launch. Only
+/// backed up with the `include_terminal_state` setting, since each backup
+/// would otherwise store a fresh copy.
+const VOLATILE_STATE_FILES: &[&str] = &["state.json", "elevated-state.json"];
+
+/// Windows Terminal: `settings.json` and the other files of its
+/// `LocalState` (fragments, backgrounds), without the volatile state files
 pub struct WindowsTerminal;
 
+/// Entries of `LocalState` (`base_path`) to back up
+fn terminal_files(base_path: &Path, include_state: bool) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    if !base_path.exists() {
+        return Ok(paths);
+    }
+    let entries = std::fs::read_dir(base_path)
+        .map_err(|e| anyhow!("Failed to read directory: {}", e))?;
+    for entry in entries.flatten() {
+        let volatile = VOLATILE_STATE_FILES.iter().any(|name| entry.file_name() == *name);
+        if include_state || !volatile {
+            paths.push(entry.path());
+        }
+    }
+    Ok(paths)
+}
+
 impl App for WindowsTerminal {
     fn id(&self) -> &'static str {
         "windows-terminal"
@@ -13,8 +37,8 @@ impl App for WindowsTerminal {
         "Windows Terminal"
     }
 
-    fn snap_support(&self) -> bool {
-        false
+    fn category(&self) -> &'static str {
+        "Terminal"
     }
 
     fn is_installed(&self) -> bool {
@@ -22,7 +46,12 @@ impl App for WindowsTerminal {
     }
 
     fn target_hint(&self) -> &'static str {
-        "sys:windows-terminal"
+        "sys:windows-terminal:settings"
+    }
+
+    /// Used while the whole `LocalState`, state included, was backed up
+    fn legacy_target_hints(&self) -> &'static [&'static str] {
+        &["sys:windows-terminal"]
     }
 
     fn package_id(&self) -> Option<&'static str> {
@@ -35,7 +64,7 @@ impl App for WindowsTerminal {
 
     fn app_path(&self) -> Result<PathBuf> {
         if !cfg!(target_os = "windows") {
-            return Err(anyhow!("Windows Terminal is only available on Windows."));
+            return Err(UnsupportedOs { app: "Windows Terminal", os: "Windows" }.into());
         }
 
         let local_appdata = std::env::var("LOCALAPPDATA")
@@ -51,22 +80,43 @@ impl App for WindowsTerminal {
     }
 
     fn config_path(&self) -> Result<Vec<PathBuf>> {
-        let base_path = self.app_path()?;
-        let mut paths = Vec::new();
-
-        if base_path.exists() {
-            match std::fs::read_dir(&base_path) {
-                Ok(entries) => {
-                    for entry in entries {
-                        if let Ok(entry) = entry {
-                            paths.push(entry.path());
-                        }
-                    }
-                }
-                Err(e) => return Err(anyhow!("Failed to read directory: {}", e)),
-            }
+        terminal_files(&self.app_path()?, crate::settings::current().include_terminal_state)
+    }
+
+    fn validate(&self, path: &Path, data: &[u8]) -> Result<(), String> {
+        validate_config_syntax(path, data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apps::get_app_by_hint;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_terminal_files_leave_out_state_unless_asked() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        for file in ["settings.json", "state.json", "elevated-state.json", "bg.png"] {
+            std::fs::write(root.join(file), b"{}")?;
         }
 
-        Ok(paths)
+        let names = |include_state| -> Result<Vec<String>> {
+            let mut names: Vec<String> = terminal_files(root, include_state)?
+                .iter()
+                .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+                .collect();
+            names.sort();
+            Ok(names)
+        };
+        assert_eq!(names(false)?, ["bg.png", "settings.json"]);
+        assert_eq!(names(true)?.len(), 4);
+
+        // Backups made under the old hint still belong to this app
+        let app = get_app_by_hint("sys:windows-terminal").unwrap();
+        assert_eq!(app.id(), "windows-terminal");
+        assert!(app.owns_target_hint("sys:windows-terminal:settings"));
+        Ok(())
     }
 }
@@ -0,0 +1,146 @@
+use super::{appdata_dir, App, AppCapabilities, UnsupportedOs};
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Registry key WinSCP keeps its configuration and sessions under when not
+/// using an INI file
+const WINSCP_REGISTRY_KEY: &str = r"HKCU\Software\Martin Prikryl\WinSCP 2";
+
+/// File the registry configuration is exported to before a backup
+const REGISTRY_EXPORT_FILE: &str = "winscp-registry.reg";
+
+/// How long the answer of `reg query` is reused, as app listings ask for
+/// every app's install state
+const REGISTRY_PROBE_TTL: Duration = Duration::from_secs(60);
+
+static REGISTRY_PROBE: Mutex<Option<(Instant, bool)>> = Mutex::new(None);
+
+/// WinSCP sessions and preferences (Windows only).
+///
+/// WinSCP stores them either in `%APPDATA%\WinSCP.ini` or in the registry.
+/// For the registry case `pre_backup` exports the key to a `.reg` file in
+/// `%APPDATA%\WinSCP` that is backed up like any other, and `post_restore`
+/// imports it again. Saved session passwords are only obfuscated, so the app
+/// needs the credentials opt-in plus blob encryption.
+pub struct WinSCP;
+
+impl WinSCP {
+    fn ini_path(&self) -> Result<PathBuf> {
+        if !cfg!(target_os = "windows") {
+            return Err(UnsupportedOs { app: "WinSCP", os: "Windows" }.into());
+        }
+        Ok(appdata_dir()?.join("WinSCP.ini"))
+    }
+
+    fn registry_export_path(&self) -> Result<PathBuf> {
+        Ok(self.app_path()?.join(REGISTRY_EXPORT_FILE))
+    }
+
+    /// Whether WinSCP's registry key exists, probed at most once per
+    /// `REGISTRY_PROBE_TTL`
+    fn uses_registry(&self) -> bool {
+        let mut probe = REGISTRY_PROBE.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((probed_at, found)) = *probe {
+            if probed_at.elapsed() < REGISTRY_PROBE_TTL {
+                return found;
+            }
+        }
+        let found = Command::new("reg")
+            .args(["query", WINSCP_REGISTRY_KEY])
+            .output()
+            .map(|output| output.status.success())
+            .unwrap_or(false);
+        *probe = Some((Instant::now(), found));
+        found
+    }
+}
+
+impl App for WinSCP {
+    fn id(&self) -> &'static str {
+        "winscp"
+    }
+
+    fn name(&self) -> &'static str {
+        "WinSCP"
+    }
+
+    fn category(&self) -> &'static str {
+        "Network"
+    }
+
+    fn capabilities(&self) -> AppCapabilities {
+        AppCapabilities {
+            sensitive: true,
+            credentials: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_installed(&self) -> bool {
+        cfg!(target_os = "windows")
+            && (self.ini_path().map(|p| p.exists()).unwrap_or(false) || self.uses_registry())
+    }
+
+    fn target_hint(&self) -> &'static str {
+        "app:winscp"
+    }
+
+    fn package_id(&self) -> Option<&'static str> {
+        if cfg!(target_os = "windows") {
+            Some("WinSCP.WinSCP")
+        } else {
+            None
+        }
+    }
+
+    /// `%APPDATA%\WinSCP`, where the registry export is kept; the INI file
+    /// sits one level up, where WinSCP looks for it
+    fn app_path(&self) -> Result<PathBuf> {
+        Ok(self.ini_path()?.with_file_name("WinSCP"))
+    }
+
+    fn config_path(&self) -> Result<Vec<PathBuf>> {
+        Ok([self.ini_path()?, self.registry_export_path()?]
+            .into_iter()
+            .filter(|path| path.is_file())
+            .collect())
+    }
+
+    fn pre_backup(&self) -> Result<()> {
+        if self.ini_path()?.exists() || !self.uses_registry() {
+            return Ok(());
+        }
+        let export_path = self.registry_export_path()?;
+        std::fs::create_dir_all(self.app_path()?)?;
+        let status = Command::new("reg")
+            .arg("export")
+            .arg(WINSCP_REGISTRY_KEY)
+            .arg(&export_path)
+            .arg("/y")
+            .status()
+            .map_err(|e| anyhow!("Failed to run reg export: {}", e))?;
+        if !status.success() {
+            return Err(anyhow!("reg export exited with {:?}", status.code()));
+        }
+        Ok(())
+    }
+
+    fn post_restore(&self) -> Result<()> {
+        let export_path = self.registry_export_path()?;
+        if !export_path.is_file() {
+            return Ok(());
+        }
+        let status = Command::new("reg")
+            .arg("import")
+            .arg(&export_path)
+            .status()
+            .map_err(|e| anyhow!("Failed to run reg import: {}", e))?;
+        if !status.success() {
+            return Err(anyhow!("reg import exited with {:?}", status.code()));
+        }
+        Ok(())
+    }
+}
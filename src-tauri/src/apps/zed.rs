@@ -1,9 +1,15 @@
-use super::App;
+use super::{
+    collect_files_recursive, sandboxed_config_dir, subset_file, validate_config_syntax, App,
+    AppCapabilities,
+};
 use anyhow::{anyhow, Result};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub struct Zed;
 
+/// `config_subset` names and the file each one is
+const SUBSETS: &[(&str, &str)] = &[("keymap", "keymap.json"), ("settings", "settings.json")];
+
 impl App for Zed {
     fn id(&self) -> &'static str {
         "zed"
@@ -13,8 +19,15 @@ impl App for Zed {
         "Zed"
     }
 
-    fn snap_support(&self) -> bool {
-        true
+    fn category(&self) -> &'static str {
+        "Editor"
+    }
+
+    fn capabilities(&self) -> AppCapabilities {
+        AppCapabilities {
+            snap: true,
+            ..Default::default()
+        }
     }
 
     fn is_installed(&self) -> bool {
@@ -47,7 +60,13 @@ impl App for Zed {
             config_home
         };
 
-        let zed_dir = config_dir.join("zed");
+        let mut zed_dir = config_dir.join("zed");
+        if platform == "linux" {
+            if let Some(home) = dirs::home_dir() {
+                let flatpak_id = Some("dev.zed.Zed");
+                zed_dir = sandboxed_config_dir(zed_dir, &home, "zed", Some("zed"), flatpak_id);
+            }
+        }
         if !zed_dir.exists() {
             return Err(anyhow!("Zed is not installed"));
         }
@@ -58,22 +77,7 @@ impl App for Zed {
         let zed_dir = self.app_path()?;
         let mut files = Vec::new();
 
-        fn collect_files_recursive(dir: &PathBuf, files: &mut Vec<PathBuf>) -> Result<()> {
-            for entry in std::fs::read_dir(dir)
-                .map_err(|e| anyhow!("Failed to read directory: {}", e))?
-            {
-                let entry = entry.map_err(|e| anyhow!("Failed to read directory entry: {}", e))?;
-                let path = entry.path();
-                if path.is_file() {
-                    files.push(path);
-                } else if path.is_dir() {
-                    collect_files_recursive(&path, files)?;
-                }
-            }
-            Ok(())
-        }
-
-        collect_files_recursive(&zed_dir, &mut files)
+        collect_files_recursive(&zed_dir, &[], &mut files)
             .map_err(|e| anyhow!("Failed to read zed config directory recursively: {}", e))?;
 
         // On Linux, also collect files from .local/share/zed/extensions/installed
@@ -103,4 +107,16 @@ impl App for Zed {
 
         Ok(files)
     }
+
+    fn config_subsets(&self) -> Vec<&'static str> {
+        SUBSETS.iter().map(|(name, _)| *name).collect()
+    }
+
+    fn config_subset(&self, subset: &str) -> Result<Vec<PathBuf>> {
+        subset_file(self, &self.app_path()?, SUBSETS, subset)
+    }
+
+    fn validate(&self, path: &Path, data: &[u8]) -> Result<(), String> {
+        validate_config_syntax(path, data)
+    }
 }
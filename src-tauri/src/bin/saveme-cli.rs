@@ -0,0 +1,9 @@
+//! Headless companion to the desktop app, for CI, SSH sessions and pipelines:
+//! `saveme-cli export daily | gpg -c | ssh host 'cat > daily.bundle'`
+
+fn main() {
+    if let Err(e) = saveme_config_lib::cli::run(std::env::args_os()) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
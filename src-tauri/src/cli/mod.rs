@@ -0,0 +1,382 @@
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::{Parser, Subcommand, ValueEnum};
+
+use crate::ops::{
+    self, AppStatus, ConflictInfo, ConflictStrategy, PerformanceProfile, SaveOptions,
+};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "saveme-cli",
+    version,
+    about = "Back up and restore app configs",
+    after_help = "With blob encryption on, blobs are sealed with the passphrase in SAVEME_PASSPHRASE"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// List supported apps and whether they are installed, or why not
+    Apps,
+    /// Back up app configs into a backup, creating it if needed
+    Save {
+        name: String,
+        /// App IDs to back up (see `apps`)
+        #[arg(long, short, value_delimiter = ',', required = true)]
+        apps: Vec<String>,
+        /// zstd level (1-22) for this backup
+        #[arg(long)]
+        compression: Option<i32>,
+        /// Train a zstd dictionary over the backed-up files and compress with it
+        #[arg(long)]
+        dictionary: bool,
+        /// Tag the backup with a machine profile
+        #[arg(long)]
+        profile: Option<String>,
+        /// Performance preset; picked from the backup's size when omitted
+        #[arg(long, value_enum)]
+        performance: Option<PerformanceArg>,
+    },
+    /// Restore app configs from a backup
+    Restore {
+        name: String,
+        /// App IDs to restore; defaults to every app in the backup. Entries
+        /// of apps unknown here come along only when restoring every app or
+        /// when their target hint (e.g. `dotfiles:home`) is listed
+        #[arg(long, short, value_delimiter = ',')]
+        apps: Vec<String>,
+        #[arg(long, value_enum, default_value_t = StrategyArg::Backup)]
+        on_conflict: StrategyArg,
+        /// Restore everything or nothing; existing files are backed up
+        #[arg(long, conflicts_with = "on_conflict")]
+        atomic: bool,
+        /// Read the restored files back and compare them with the backup
+        #[arg(long, conflicts_with_all = ["atomic", "only"])]
+        verify: bool,
+        /// Only restore files whose path matches this glob; repeatable
+        #[arg(long, conflicts_with = "atomic")]
+        only: Vec<String>,
+        /// Keep existing files modified since they were backed up
+        #[arg(long, conflicts_with = "atomic")]
+        skip_if_newer: bool,
+        /// Restore entries of apps unknown here to their original paths
+        #[arg(long, conflicts_with = "atomic")]
+        force_generic: bool,
+        /// Restore files saved under another user's home into yours
+        #[arg(long, conflicts_with = "atomic")]
+        into_new_user: bool,
+        /// Ask what to do about each existing file; --on-conflict is the
+        /// answer to an empty reply
+        #[arg(long, conflicts_with_all = ["atomic", "verify", "only"])]
+        interactive: bool,
+        /// Refuse to restore a backup tagged with another profile
+        #[arg(long)]
+        profile: Option<String>,
+    },
+    /// List backups, optionally only those of one machine profile
+    List {
+        #[arg(long)]
+        profile: Option<String>,
+    },
+    /// Check a backup's blob chain and blob files
+    Verify {
+        name: String,
+        /// Also decompress every blob and read each file in it
+        #[arg(long)]
+        deep: bool,
+        /// Also check each blob file matches the copy in the manifest
+        #[arg(long)]
+        copies: bool,
+    },
+    /// Write a backup bundle to stdout or a file
+    Export {
+        name: String,
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Read a backup bundle from stdin or a file
+    Import {
+        /// Import under a different name
+        #[arg(long)]
+        name: Option<String>,
+        #[arg(long, short)]
+        input: Option<PathBuf>,
+    },
+}
+
+/// `ConflictStrategy` as a command-line value
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum StrategyArg {
+    Overwrite,
+    Skip,
+    KeepBoth,
+    Backup,
+}
+
+/// Prompt on stderr for what to do about `conflict` until a valid reply
+/// is read from `input`; an empty reply or end of input picks `default`
+fn ask_conflict(
+    conflict: &ConflictInfo,
+    default: ConflictStrategy,
+    input: &mut impl io::BufRead,
+) -> ConflictStrategy {
+    loop {
+        eprint!(
+            "{} exists ({} bytes{}). [o]verwrite, [s]kip, [k]eep both, [b]ackup first? ",
+            conflict.path,
+            conflict.existing_size,
+            conflict
+                .existing_modified
+                .as_deref()
+                .map(|modified| format!(", modified {}", modified))
+                .unwrap_or_default()
+        );
+        let mut reply = String::new();
+        if input.read_line(&mut reply).unwrap_or(0) == 0 {
+            return default;
+        }
+        match reply.trim().to_lowercase().as_str() {
+            "" => return default,
+            "o" | "overwrite" => return ConflictStrategy::Overwrite,
+            "s" | "skip" => return ConflictStrategy::Skip,
+            "k" | "keep-both" => return ConflictStrategy::KeepBoth,
+            "b" | "backup" => return ConflictStrategy::BackupThenOverwrite,
+            _ => eprintln!("Please answer o, s, k or b"),
+        }
+    }
+}
+
+/// `PerformanceProfile` as a command-line value
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum PerformanceArg {
+    Auto,
+    Fast,
+    Balanced,
+    Max,
+}
+
+impl From<PerformanceArg> for PerformanceProfile {
+    fn from(arg: PerformanceArg) -> Self {
+        match arg {
+            PerformanceArg::Auto => PerformanceProfile::Auto,
+            PerformanceArg::Fast => PerformanceProfile::Fast,
+            PerformanceArg::Balanced => PerformanceProfile::Balanced,
+            PerformanceArg::Max => PerformanceProfile::MaxCompression,
+        }
+    }
+}
+
+impl From<StrategyArg> for ConflictStrategy {
+    fn from(arg: StrategyArg) -> Self {
+        match arg {
+            StrategyArg::Overwrite => ConflictStrategy::Overwrite,
+            StrategyArg::Skip => ConflictStrategy::Skip,
+            StrategyArg::KeepBoth => ConflictStrategy::KeepBoth,
+            StrategyArg::Backup => ConflictStrategy::BackupThenOverwrite,
+        }
+    }
+}
+
+/// Entry point for `saveme-cli`; `args` includes the program name
+pub fn run<I, T>(args: I) -> Result<()>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let cli = Cli::parse_from(args);
+
+    // Reports go to stderr so stdout stays clean for `export`
+    match cli.command {
+        Command::Apps => {
+            for app in ops::list_applications() {
+                let status = match app.status {
+                    AppStatus::Installed => "installed".to_string(),
+                    AppStatus::NotInstalled => "not installed".to_string(),
+                    AppStatus::UnsupportedOS => "not available on this OS".to_string(),
+                    AppStatus::DetectionError(e) => format!("detection failed: {}", e),
+                };
+                println!("{}\t{}\t{}", app.id, app.name, status);
+            }
+        }
+        Command::Save {
+            name,
+            apps,
+            compression,
+            dictionary,
+            profile,
+            performance,
+        } => {
+            let options = SaveOptions {
+                compression_level: compression,
+                compression_dictionary: dictionary,
+                machine_profile: profile,
+                performance_profile: performance.map(Into::into),
+            };
+            let warnings = ops::save_backup(&name, &apps, &options, |update| {
+                let eta = update
+                    .eta_seconds
+                    .map(|s| format!(", ~{}s left", s))
+                    .unwrap_or_default();
+                eprintln!("[{}{}]", update.describe(), eta);
+            })?;
+            for warning in warnings {
+                eprintln!("warning: {}", warning);
+            }
+            eprintln!("Backup '{}' saved", name);
+        }
+        Command::Restore {
+            name,
+            apps,
+            on_conflict,
+            atomic,
+            verify,
+            only,
+            skip_if_newer,
+            force_generic,
+            into_new_user,
+            interactive,
+            profile,
+        } => {
+            let apps = if apps.is_empty() {
+                ops::apps_in_backup(&name)?
+            } else {
+                apps
+            };
+            let options = ops::RestoreOptions {
+                strategy: on_conflict.into(),
+                machine_profile: profile,
+                skip_if_newer,
+                force_generic,
+                into_new_user,
+            };
+            let restored = if atomic {
+                ops::restore_atomic(&name, &apps, options.machine_profile.as_deref())?
+            } else if !only.is_empty() {
+                let (matched, restored) = ops::restore_matching(&name, &apps, &only, &options)?;
+                eprintln!("{} entries matched, {} restored", matched, restored.len());
+                restored
+            } else if verify {
+                let (restored, summary) = ops::restore_backup_verified(&name, &apps, &options)?;
+                eprintln!("{}", ops::describe_summary(&summary));
+                restored
+            } else if interactive {
+                let stdin = io::stdin();
+                ops::restore_backup_interactive(&name, &apps, &options, |conflict| {
+                    ask_conflict(conflict, options.strategy, &mut stdin.lock())
+                })?
+            } else {
+                ops::restore_backup(&name, &apps, &options)?
+            };
+            println!("{}", ops::describe_restored(&restored));
+        }
+        Command::List { profile } => {
+            let backups = match profile {
+                Some(profile) => ops::list_backups_for_profile(&profile)?,
+                None => ops::list_backups()?,
+            };
+            for backup in backups {
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    backup.name,
+                    backup.created_at,
+                    backup.machine_profile.unwrap_or_default(),
+                    ops::describe_app_entry_counts(&backup.app_entry_counts)
+                );
+            }
+        }
+        Command::Verify { name, deep, copies } => {
+            if deep {
+                ops::verify_backup_deep(&name)?;
+            } else {
+                ops::verify_backup(&name)?;
+            }
+            if copies {
+                let mismatches = ops::verify_blob_copies(&name)?;
+                if !mismatches.is_empty() {
+                    anyhow::bail!(
+                        "Backup '{}' has blob copies that disagree:\n{}",
+                        name,
+                        mismatches.join("\n")
+                    );
+                }
+            }
+            eprintln!("Backup '{}' verified", name);
+        }
+        Command::Export { name, output } => {
+            let writer: Box<dyn Write> = match output {
+                Some(path) => Box::new(File::create(path)?),
+                None => Box::new(io::stdout().lock()),
+            };
+            ops::export_backup(&name, BufWriter::new(writer))?.flush()?;
+        }
+        Command::Import { name, input } => {
+            let reader: Box<dyn Read> = match input {
+                Some(path) => Box::new(File::open(path)?),
+                None => Box::new(io::stdin().lock()),
+            };
+            let imported = ops::import_backup(BufReader::new(reader), name.as_deref())?;
+            eprintln!("Imported backup '{}'", imported);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_definition_and_parsing() {
+        Cli::command().debug_assert();
+
+        let cli = Cli::parse_from(["saveme-cli", "save", "daily", "--apps", "zed,vscode"]);
+        match cli.command {
+            Command::Save { name, apps, .. } => {
+                assert_eq!(name, "daily");
+                assert_eq!(apps, vec!["zed".to_string(), "vscode".to_string()]);
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+
+        let cli = Cli::parse_from(["saveme-cli", "restore", "daily", "--on-conflict", "keep-both"]);
+        assert!(matches!(
+            cli.command,
+            Command::Restore {
+                on_conflict: StrategyArg::KeepBoth,
+                ..
+            }
+        ));
+
+        let cli = Cli::parse_from(["saveme-cli", "restore", "daily", "--only", "**/{a,b}.json"]);
+        match cli.command {
+            Command::Restore { only, .. } => assert_eq!(only, vec!["**/{a,b}.json".to_string()]),
+            other => panic!("unexpected command: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_ask_conflict_reads_replies() {
+        let conflict = ConflictInfo {
+            path: "/home/me/.nanorc".to_string(),
+            logical_path: "/home/me/.nanorc".to_string(),
+            target_hint: "app:nano".to_string(),
+            existing_size: 10,
+            existing_modified: None,
+            backup_size: None,
+        };
+        let ask = |input: &[u8]| ask_conflict(&conflict, ConflictStrategy::Skip, &mut &input[..]);
+        assert_eq!(ask(b"k\n"), ConflictStrategy::KeepBoth);
+        assert_eq!(ask(b"what\nOverwrite\n"), ConflictStrategy::Overwrite);
+        assert_eq!(ask(b"\n"), ConflictStrategy::Skip);
+        assert_eq!(ask(b""), ConflictStrategy::Skip);
+    }
+}
@@ -0,0 +1,132 @@
+use anyhow::Result;
+use serde::Serialize;
+use similar::TextDiff;
+
+use crate::storage::{entry::Entry, manifest::Manifest};
+
+/// How much of a file is looked at to decide whether it is text
+const SNIFF_BYTES: usize = 8000;
+
+/// Logical paths that differ between two backups
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct BackupDiff {
+    /// Only in the newer backup
+    pub added: Vec<String>,
+    /// Only in the older backup
+    pub removed: Vec<String>,
+    /// In both, with different content
+    pub changed: Vec<String>,
+}
+
+/// Compare the entries of `a` (older) and `b` (newer) by logical path.
+/// Content is compared by the original file hash when both entries have
+/// one, else by blob ID.
+pub fn diff_manifests(a: &Manifest, b: &Manifest) -> BackupDiff {
+    let mut diff = BackupDiff::default();
+    for entry in &b.entries {
+        match find_entry(a, &entry.logical_path) {
+            None => diff.added.push(entry.logical_path.clone()),
+            Some(old) if !same_content(old, entry) => {
+                diff.changed.push(entry.logical_path.clone())
+            }
+            Some(_) => {}
+        }
+    }
+    for entry in &a.entries {
+        if find_entry(b, &entry.logical_path).is_none() {
+            diff.removed.push(entry.logical_path.clone());
+        }
+    }
+    diff.added.sort();
+    diff.removed.sort();
+    diff.changed.sort();
+    diff
+}
+
+pub fn find_entry<'m>(manifest: &'m Manifest, logical_path: &str) -> Option<&'m Entry> {
+    manifest.entries.iter().find(|entry| entry.logical_path == logical_path)
+}
+
+fn same_content(a: &Entry, b: &Entry) -> bool {
+    match (&a.original_sha256, &b.original_sha256) {
+        (Some(a_hash), Some(b_hash)) => a_hash == b_hash,
+        _ => a.blob_id == b.blob_id,
+    }
+}
+
+/// UTF-8 without NUL bytes in its first `SNIFF_BYTES`, which is how most
+/// tools tell text from binary
+fn is_text(data: &[u8]) -> bool {
+    let head = &data[..data.len().min(SNIFF_BYTES)];
+    !head.contains(&0) && std::str::from_utf8(data).is_ok()
+}
+
+/// Unified diff of `path` from `old` to `new`; a missing side counts as an
+/// empty file. Binary content gets a one-line marker instead, and identical
+/// content an empty string.
+pub fn unified_diff(
+    path: &str,
+    (old_label, old): (&str, Option<&[u8]>),
+    (new_label, new): (&str, Option<&[u8]>),
+) -> Result<String> {
+    let (old, new) = (old.unwrap_or_default(), new.unwrap_or_default());
+    if old == new {
+        return Ok(String::new());
+    }
+    if !is_text(old) || !is_text(new) {
+        return Ok(format!("Binary file {} differs", path));
+    }
+    let (old, new) = (std::str::from_utf8(old)?, std::str::from_utf8(new)?);
+    Ok(TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("{}:{}", old_label, path), &format!("{}:{}", new_label, path))
+        .to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff_of_text_and_binary() -> Result<()> {
+        let old = b"theme = \"dark\"\nfont_size = 14\n";
+        let new = b"theme = \"light\"\nfont_size = 14\n";
+        let diff = unified_diff("settings.toml", ("daily", Some(old)), ("weekly", Some(new)))?;
+        assert!(diff.starts_with("--- daily:settings.toml\n+++ weekly:settings.toml\n"));
+        assert!(diff.contains("-theme = \"dark\"\n+theme = \"light\"\n font_size = 14\n"));
+
+        let added = unified_diff("new.toml", ("daily", None), ("weekly", Some(new)))?;
+        assert!(added.contains("+font_size = 14"));
+        assert_eq!(unified_diff("same", ("a", Some(old)), ("b", Some(old)))?, "");
+        let binary = unified_diff("icon.png", ("a", Some(b"\x89PNG\0")), ("b", Some(b"x")))?;
+        assert_eq!(binary, "Binary file icon.png differs");
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_manifests_by_logical_path() {
+        let entry = |path: &str, blob_id: &str| Entry {
+            logical_path: path.to_string(),
+            blob_id: blob_id.to_string(),
+            ..Default::default()
+        };
+        let mut a = Manifest::empty("a".to_string());
+        a.entries = vec![
+            entry("/cfg/kept", "1"),
+            entry("/cfg/edited", "2"),
+            entry("/cfg/gone", "3"),
+        ];
+        let mut b = Manifest::empty("b".to_string());
+        b.entries = vec![
+            entry("/cfg/kept", "1"),
+            entry("/cfg/edited", "4"),
+            entry("/cfg/new", "5"),
+        ];
+
+        let diff = diff_manifests(&a, &b);
+        assert_eq!(diff.added, ["/cfg/new"]);
+        assert_eq!(diff.removed, ["/cfg/gone"]);
+        assert_eq!(diff.changed, ["/cfg/edited"]);
+    }
+}
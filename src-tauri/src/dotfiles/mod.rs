@@ -0,0 +1,156 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use git2::{ObjectType, Repository, TreeWalkMode, TreeWalkResult};
+
+/// Git file mode of symlinks; their target is not a config file we can restore
+const GIT_SYMLINK_MODE: i32 = 0o120000;
+const GIT_EXECUTABLE_MODE: i32 = 0o100755;
+
+/// One file tracked by a dotfiles repo
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackedFile {
+    /// Path relative to the work tree, always `/`-separated
+    pub path: String,
+    pub data: Vec<u8>,
+    /// Unix permission bits to restore with
+    pub mode: u32,
+}
+
+/// Short name of a dotfiles repo: `~/.dotfiles` and `~/dotfiles.git` both
+/// become `dotfiles`
+pub fn repo_name(repo_path: &Path) -> String {
+    let name = repo_path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let name = name.trim_start_matches('.');
+    let name = name.strip_suffix(".git").unwrap_or(name);
+    if name.is_empty() {
+        "dotfiles".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Every regular file tracked at `HEAD` of the (typically bare) repo at
+/// `repo_path`. Content comes from `work_tree` when the file is checked out
+/// there, so uncommitted edits are picked up, and from the committed blob
+/// otherwise. Symlinks and submodules are skipped.
+pub fn read_tracked_files(repo_path: &Path, work_tree: &Path) -> Result<Vec<TrackedFile>> {
+    let repo = Repository::open(repo_path)
+        .with_context(|| format!("Not a git repository: {}", repo_path.display()))?;
+    let tree = repo
+        .head()
+        .and_then(|head| head.peel_to_tree())
+        .context("Dotfiles repo has no commits")?;
+
+    let mut tracked = Vec::new();
+    let mut walk_error = None;
+    tree.walk(TreeWalkMode::PreOrder, |dir, entry| {
+        if entry.kind() != Some(ObjectType::Blob) {
+            return TreeWalkResult::Ok;
+        }
+        let Some(name) = entry.name() else {
+            return TreeWalkResult::Ok;
+        };
+        let path = format!("{}{}", dir, name);
+        if entry.filemode() == GIT_SYMLINK_MODE {
+            println!("Skipping symlink in dotfiles repo: {}", path);
+            return TreeWalkResult::Ok;
+        }
+
+        let checked_out = work_tree.join(&path);
+        let data = if checked_out.is_file() {
+            fs::read(&checked_out).map_err(anyhow::Error::from)
+        } else {
+            repo.find_blob(entry.id())
+                .map(|blob| blob.content().to_vec())
+                .map_err(anyhow::Error::from)
+        };
+        match data {
+            Ok(data) => {
+                let mode = if entry.filemode() == GIT_EXECUTABLE_MODE {
+                    0o755
+                } else {
+                    0o644
+                };
+                tracked.push(TrackedFile { path, data, mode });
+                TreeWalkResult::Ok
+            }
+            Err(e) => {
+                walk_error = Some(anyhow!("Failed to read {}: {}", path, e));
+                TreeWalkResult::Abort
+            }
+        }
+    })
+    .or_else(|e| if walk_error.is_some() { Ok(()) } else { Err(e) })?;
+
+    match walk_error {
+        Some(e) => Err(e),
+        None => Ok(tracked),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Commit `files` to a fresh bare repo, the way `git --bare` dotfiles setups do
+    fn bare_repo_with(repo_path: &Path, files: &[(&str, &[u8], i32)]) -> Result<()> {
+        let repo = Repository::init_bare(repo_path)?;
+        let mut index = repo.index()?;
+        for (path, data, mode) in files {
+            let entry = git2::IndexEntry {
+                ctime: git2::IndexTime::new(0, 0),
+                mtime: git2::IndexTime::new(0, 0),
+                dev: 0,
+                ino: 0,
+                mode: *mode as u32,
+                uid: 0,
+                gid: 0,
+                file_size: data.len() as u32,
+                id: repo.blob(data)?,
+                flags: 0,
+                flags_extended: 0,
+                path: path.as_bytes().to_vec(),
+            };
+            index.add_frombuffer(&entry, data)?;
+        }
+        let tree = repo.find_tree(index.write_tree_to(&repo)?)?;
+        let signature = git2::Signature::now("test", "test@example.com")?;
+        repo.commit(Some("HEAD"), &signature, &signature, "dotfiles", &tree, &[])?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_tracked_files_prefers_work_tree() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let repo_path = temp_dir.path().join(".dotfiles");
+        let work_tree = temp_dir.path().join("home");
+        bare_repo_with(
+            &repo_path,
+            &[
+                (".bashrc", b"committed", 0o100644),
+                (".config/nvim/init.lua", b"vim.o.number = true", 0o100644),
+                ("bin/sync", b"#!/bin/sh", 0o100755),
+                (".vimrc", b".config/nvim/init.lua", GIT_SYMLINK_MODE),
+            ],
+        )?;
+        fs::create_dir_all(&work_tree)?;
+        fs::write(work_tree.join(".bashrc"), b"edited")?;
+
+        let mut files = read_tracked_files(&repo_path, &work_tree)?;
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec![".bashrc", ".config/nvim/init.lua", "bin/sync"]);
+        assert_eq!(files[0].data, b"edited");
+        assert_eq!(files[1].data, b"vim.o.number = true");
+        assert_eq!(files[2].mode, 0o755);
+        assert_eq!(repo_name(&repo_path), "dotfiles");
+        Ok(())
+    }
+}
@@ -1,6 +1,77 @@
-use crate::apps::App;
+use crate::apps::{command_exists, App};
 use std::process::Command;
 
+/// AUR helpers, in order of preference. They call sudo themselves and
+/// refuse to run as root.
+const AUR_HELPERS: &[&str] = &["paru", "yay"];
+
+/// Whether `/etc/os-release` content describes Arch or a derivative
+/// (Manjaro, EndeavourOS, ...), from its `ID` and `ID_LIKE` fields
+fn is_arch_based(os_release: &str) -> bool {
+    os_release.lines().any(|line| {
+        let Some((key, value)) = line.split_once('=') else {
+            return false;
+        };
+        (key == "ID" || key == "ID_LIKE")
+            && value.trim_matches('"').split_whitespace().any(|id| id == "arch")
+    })
+}
+
+fn is_arch_linux() -> bool {
+    std::fs::read_to_string("/etc/os-release").is_ok_and(|content| is_arch_based(&content))
+}
+
+/// Install with pacman when `package_id` is in the official repositories,
+/// else from the AUR with the first AUR helper found
+fn install_on_arch(app: &dyn App, package_id: &str) -> Result<(), String> {
+    let in_repos = Command::new("pacman")
+        .arg("-Si")
+        .arg(package_id)
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false);
+
+    let (mut cmd, manager, package) = if in_repos {
+        let mut c = Command::new("sudo");
+        c.arg("pacman").arg("-S").arg("--needed").arg("--noconfirm").arg(package_id);
+        (c, "pacman", package_id)
+    } else {
+        let helper = AUR_HELPERS.iter().find(|helper| command_exists(helper)).ok_or_else(|| {
+            format!(
+                "'{}' is not in the official Arch repositories and no AUR helper ({}) is installed.",
+                package_id,
+                AUR_HELPERS.join(" or ")
+            )
+        })?;
+        let aur_id = app.aur_package_id().unwrap_or(package_id);
+        let mut c = Command::new(helper);
+        c.arg("-S").arg("--needed").arg("--noconfirm").arg(aur_id);
+        (c, *helper, aur_id)
+    };
+
+    println!("Installing '{}' ({}) using {}...", app.name(), package, manager);
+    let status = cmd.status().map_err(|e| {
+        format!(
+            "Failed to execute {} installation command for '{}'. Error: {}",
+            manager,
+            app.name(),
+            e
+        )
+    })?;
+
+    if status.success() {
+        println!("Successfully installed '{}' using {}", app.name(), manager);
+        Ok(())
+    } else {
+        Err(format!(
+            "Failed to install '{}' using {}. The command finished with a non-zero exit code: {:?}",
+            app.name(),
+            manager,
+            status.code()
+        ))
+    }
+}
+
 pub fn install_app(app: &dyn App) -> Result<(), String> {
     let package_id = app.package_id().ok_or_else(|| {
         format!(
@@ -13,8 +84,11 @@ pub fn install_app(app: &dyn App) -> Result<(), String> {
 
     println!("Attempting to install '{}' with package ID '{}' on platform '{}'", app.name(), package_id, platform);
 
-    // Check if app supports snap and if we're on Linux
-    if platform != "windows" && platform != "darwin" && app.snap_support() {
+    // Arch gets pacman or the AUR rather than snap or apt
+    if platform == "linux" && is_arch_linux() {
+        install_on_arch(app, package_id)
+    } else if platform != "windows" && platform != "darwin" && app.capabilities().snap {
+        // The app supports snap and we're on Linux
         // First, install snap if not already installed
         println!("Installing snap package manager...");
         let snap_install_status = Command::new("sudo")
@@ -98,3 +172,17 @@ pub fn install_app(app: &dyn App) -> Result<(), String> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_arch_based_reads_id_and_id_like() {
+        assert!(is_arch_based("NAME=\"Arch Linux\"\nID=arch\n"));
+        assert!(is_arch_based("ID=manjaro\nID_LIKE=arch\n"));
+        assert!(is_arch_based("ID=cachyos\nID_LIKE=\"arch endeavouros\"\n"));
+        assert!(!is_arch_based("ID=ubuntu\nID_LIKE=debian\n"));
+        assert!(!is_arch_based("ID=archlike\n"));
+    }
+}
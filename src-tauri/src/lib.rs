@@ -1,22 +1,27 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 
-use chrono::Utc;
 use serde::Serialize;
-use tauri_plugin_os::platform;
+use std::collections::BTreeMap;
+use std::path::Path;
+use tauri::{AppHandle, Emitter};
 
 mod apps;
+pub mod cli;
+mod diff;
+mod dotfiles;
 mod installer;
+mod messages;
+pub mod ops;
+mod progress;
+mod remote;
+mod restore;
+mod settings;
 mod storage;
 
-use apps::AppInfo;
+use ops::{AppInfo, AppNode, BackupInfo, BackupPage, ConflictStrategy, SaveOptions};
+use storage::health::HealthReport;
 use storage::manifest::Manifest;
 
-#[derive(Serialize, Clone)]
-struct BackupInfo {
-    name: String,
-    created_at: String,
-}
-
 #[derive(Serialize, Clone)]
 struct BackupChainInfo {
     name: String,
@@ -28,151 +33,381 @@ struct BackupChainInfo {
 
 #[tauri::command]
 fn list_applications() -> Vec<AppInfo> {
-    apps::get_all_apps_info()
+    ops::list_applications()
 }
 
 #[tauri::command]
-fn save_config(name: &str, app_ids: Vec<String>) -> Result<String, String> {
-    let mut manifest = match Manifest::load_from(name) {
-        Ok(existing_manifest) => {
-            println!("Loading existing manifest for: {}", name);
-            existing_manifest
-        }
-        Err(_) => {
-            println!("Creating new manifest for: {}", name);
-            Manifest::new(
-                name.to_string(),
-                Utc::now().to_rfc3339(),
-                platform().to_string(),
-            )
+fn get_crate_version() -> String {
+    ops::crate_version().to_string()
+}
+
+#[tauri::command]
+fn get_settings() -> settings::Settings {
+    settings::current()
+}
+
+#[tauri::command]
+fn update_settings(new_settings: settings::Settings) -> Result<(), String> {
+    settings::update(new_settings).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn set_encryption_passphrase(passphrase: Option<String>) {
+    ops::set_encryption_passphrase(passphrase)
+}
+
+#[tauri::command]
+fn save_config(
+    app_handle: AppHandle,
+    name: &str,
+    app_ids: Vec<String>,
+    compression_level: Option<i32>,
+    machine_profile: Option<String>,
+    compression_dictionary: Option<bool>,
+    performance_profile: Option<ops::PerformanceProfile>,
+) -> Result<String, String> {
+    let options = SaveOptions {
+        compression_level,
+        machine_profile,
+        compression_dictionary: compression_dictionary.unwrap_or(false),
+        performance_profile,
+    };
+    let warnings = ops::save_backup(name, &app_ids, &options, |update| {
+        if let Err(e) = app_handle.emit(progress::BACKUP_PROGRESS_EVENT, update) {
+            println!("Failed to emit backup progress: {}", e);
         }
+    })
+    .map_err(|e| e.to_string())?;
+
+    let mut message = "Config saved successfully".to_string();
+    for warning in warnings {
+        message.push_str("\nWarning: ");
+        message.push_str(&warning);
+    }
+    Ok(message)
+}
+
+fn emit_remote_progress(app_handle: &AppHandle, attempt: ops::RemoteAttempt) {
+    if let Err(e) = app_handle.emit(progress::REMOTE_PROGRESS_EVENT, attempt) {
+        println!("Failed to emit remote progress: {}", e);
+    }
+}
+
+#[tauri::command]
+fn push_backup(app_handle: AppHandle, name: &str) -> Result<String, String> {
+    ops::push_backup(name, |attempt| emit_remote_progress(&app_handle, attempt))
+        .map_err(|e| e.to_string())?;
+    Ok(format!("Pushed backup '{}' to the remote", name))
+}
+
+#[tauri::command]
+fn pull_backup(
+    app_handle: AppHandle,
+    name: &str,
+    rename: Option<String>,
+) -> Result<String, String> {
+    let imported = ops::pull_backup(name, rename.as_deref(), |attempt| {
+        emit_remote_progress(&app_handle, attempt)
+    })
+    .map_err(|e| e.to_string())?;
+    Ok(format!("Pulled backup '{}' from the remote", imported))
+}
+
+#[tauri::command]
+fn restore_from_remote(
+    app_handle: AppHandle,
+    name: &str,
+    app_ids: Vec<String>,
+    strategy: Option<ConflictStrategy>,
+    machine_profile: Option<String>,
+) -> Result<String, String> {
+    let options = ops::RestoreOptions {
+        strategy: strategy.unwrap_or_default(),
+        machine_profile,
+        ..Default::default()
     };
+    let restored_files = ops::restore_from_remote(name, &app_ids, &options, |attempt| {
+        emit_remote_progress(&app_handle, attempt)
+    })
+    .map_err(|e| e.to_string())?;
 
-    for app_id in app_ids {
-        if let Some(app) = apps::get_app(&app_id) {
-            if app.is_installed() {
-                println!("Processing app: {}", app.name());
-                if let Ok(paths) = app.config_path() {
-                    for path in paths {
-                        println!("Processing config file: {}", path.display());
-                        if path.exists() {
-                            println!("Config file exists");
-                            println!("Creating blob from file");
-                            if !path.is_dir() {
-                                manifest
-                                    .create_blob_from_file(&path, app.target_hint())
-                                    .map_err(|e| e.to_string())?;
-                            }
-                            println!("Blob created successfully");
-                        }
-                    }
-                }
-            }
-        }
+    let mut message = format!("Config restored from remote backup '{}'", name);
+    if !restored_files.is_empty() {
+        message.push('\n');
+        message.push_str(&ops::describe_restored(&restored_files));
     }
+    Ok(message)
+}
+
+#[tauri::command]
+fn list_remote_backups(app_handle: AppHandle) -> Result<Vec<String>, String> {
+    ops::list_remote_backups(|attempt| emit_remote_progress(&app_handle, attempt))
+        .map_err(|e| e.to_string())
+}
 
-    // Blob blockchain is managed automatically during blob creation
-    // No need for manual chain setup for backups anymore
-    println!("Using automatic blob blockchain management");
+#[tauri::command]
+fn scan_sensitive(app_ids: Vec<String>) -> Result<Vec<String>, String> {
+    ops::scan_sensitive(&app_ids).map_err(|e| e.to_string())
+}
 
-    manifest.ingest_blobs_dir().map_err(|e| e.to_string())?;
-    manifest.save().map_err(|e| e.to_string())?;
-    Ok("Config saved successfully".to_string())
+#[tauri::command]
+fn cancel_backup() {
+    progress::request_cancel();
 }
 
 #[tauri::command]
 fn list_backups() -> Result<Vec<BackupInfo>, String> {
-    let storage_dir = Manifest::base_storage_dir().map_err(|e| e.to_string())?;
-    let mut backups = Vec::new();
+    ops::list_backups().map_err(|e| e.to_string())
+}
 
-    if !storage_dir.exists() {
-        return Ok(backups);
-    }
+#[tauri::command]
+fn list_backups_paged(offset: usize, limit: usize) -> Result<BackupPage, String> {
+    ops::list_backups_paged(offset, limit).map_err(|e| e.to_string())
+}
 
-    for entry in std::fs::read_dir(storage_dir).map_err(|e| e.to_string())? {
-        let entry = entry.map_err(|e| e.to_string())?;
-        if entry.file_type().map_err(|e| e.to_string())?.is_dir() {
-            let manifest_path = entry.path().join("manifest.json");
-            if manifest_path.exists() {
-                let content = std::fs::read_to_string(manifest_path).map_err(|e| e.to_string())?;
-                let manifest: Manifest =
-                    serde_json::from_str(&content).map_err(|e| e.to_string())?;
-                backups.push(BackupInfo {
-                    name: manifest.name,
-                    created_at: manifest.created_at,
-                });
-            }
+#[tauri::command]
+fn list_backups_for_profile(profile: &str) -> Result<Vec<BackupInfo>, String> {
+    ops::list_backups_for_profile(profile).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn backup_tree(backup_name: &str) -> Result<Vec<AppNode>, String> {
+    ops::backup_tree(backup_name).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn save_config_subset(
+    app_handle: AppHandle,
+    name: &str,
+    app_id: &str,
+    subset: &str,
+) -> Result<String, String> {
+    let options = SaveOptions::default();
+    let warnings = ops::save_config_subset(name, app_id, subset, &options, |update| {
+        if let Err(e) = app_handle.emit(progress::BACKUP_PROGRESS_EVENT, update) {
+            println!("Failed to emit backup progress: {}", e);
         }
+    })
+    .map_err(|e| e.to_string())?;
+
+    let mut message = format!("Saved the '{}' files of {}", subset, app_id);
+    for warning in warnings {
+        message.push_str("\nWarning: ");
+        message.push_str(&warning);
     }
-    Ok(backups)
+    Ok(message)
 }
 
 #[tauri::command]
-fn restore_config(backup_name: &str, app_ids: Vec<String>) -> Result<String, String> {
-    let manifest = Manifest::load_from(backup_name).map_err(|e| e.to_string())?;
+fn restore_config(
+    backup_name: &str,
+    app_ids: Vec<String>,
+    strategy: Option<ConflictStrategy>,
+    machine_profile: Option<String>,
+    verify: Option<bool>,
+    skip_if_newer: Option<bool>,
+    force_generic: Option<bool>,
+) -> Result<String, String> {
+    let options = ops::RestoreOptions {
+        strategy: strategy.unwrap_or_default(),
+        machine_profile,
+        skip_if_newer: skip_if_newer.unwrap_or(false),
+        force_generic: force_generic.unwrap_or(false),
+        ..Default::default()
+    };
+    let mut message = "Config restored successfully".to_string();
+    let restored_files = if verify.unwrap_or(false) {
+        let (restored_files, summary) =
+            ops::restore_backup_verified(backup_name, &app_ids, &options)
+                .map_err(|e| e.to_string())?;
+        message.push('\n');
+        message.push_str(&ops::describe_summary(&summary));
+        restored_files
+    } else {
+        ops::restore_backup(backup_name, &app_ids, &options).map_err(|e| e.to_string())?
+    };
 
-    for app_id in app_ids {
-        if let Some(app) = apps::get_app(&app_id) {
-            // If the app is not installed, try to install it.
-            if !app.is_installed() {
-                if app.package_id().is_some() {
-                    installer::install_app(app)?;
-                } else {
-                    // Optionally, you could choose to skip or warn the user.
-                    // For now, we'll just print a message to the console.
-                    println!("Skipping restore for '{}' because it is not installed and no package_id is available.", app.name());
-                    continue;
-                }
-            }
+    if !restored_files.is_empty() {
+        message.push('\n');
+        message.push_str(&ops::describe_restored(&restored_files));
+    }
+    Ok(message)
+}
 
-            let entries_of_app = manifest
-                .entries
-                .iter()
-                .filter(|e| e.target_hint == app.target_hint())
-                .collect::<Vec<_>>();
-            for entry in entries_of_app {
-                let config_paths = app
-                    .config_path()
-                    .map_err(|e| e.to_string())?;
-                let logical_path = config_paths
-                    .iter()
-                    .find(|f| {
-                        f.ends_with(&entry.tar_member.as_ref().unwrap_or(&String::default()))
-                    });
-                if let Some(dest_path) = logical_path {
-                    if let Some(parent) = dest_path.parent() {
-                        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
-                    }
-                    manifest
-                        .restore_blob_to(entry, &dest_path)
-                        .map_err(|e| e.to_string())?;
+#[tauri::command]
+async fn restore_config_interactive(
+    app_handle: AppHandle,
+    backup_name: String,
+    app_ids: Vec<String>,
+    machine_profile: Option<String>,
+) -> Result<String, String> {
+    // Blocks on the frontend's answers, so it can't hold a command thread
+    tauri::async_runtime::spawn_blocking(move || {
+        let options = ops::RestoreOptions {
+            machine_profile,
+            ..Default::default()
+        };
+        let restored_files =
+            ops::restore_backup_prompting(&backup_name, &app_ids, &options, |prompt| {
+                if let Err(e) = app_handle.emit(progress::RESTORE_CONFLICT_EVENT, prompt) {
+                    println!("Failed to emit restore conflict: {}", e);
+                    ops::answer_conflict(prompt.id, ConflictStrategy::Skip);
                 }
-            }
-        }
+            })
+            .map_err(|e| e.to_string())?;
+        Ok(ops::describe_restored(&restored_files))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn answer_restore_conflict(id: u64, strategy: ConflictStrategy) -> bool {
+    ops::answer_conflict(id, strategy)
+}
+
+#[tauri::command]
+fn restore_config_matching(
+    backup_name: &str,
+    app_ids: Vec<String>,
+    patterns: Vec<String>,
+    strategy: Option<ConflictStrategy>,
+    machine_profile: Option<String>,
+    skip_if_newer: Option<bool>,
+    force_generic: Option<bool>,
+) -> Result<String, String> {
+    let options = ops::RestoreOptions {
+        strategy: strategy.unwrap_or_default(),
+        machine_profile,
+        skip_if_newer: skip_if_newer.unwrap_or(false),
+        force_generic: force_generic.unwrap_or(false),
+        ..Default::default()
+    };
+    let (matched, restored_files) =
+        ops::restore_matching(backup_name, &app_ids, &patterns, &options)
+            .map_err(|e| e.to_string())?;
+
+    let mut message = format!("{} entries matched, {} restored", matched, restored_files.len());
+    if !restored_files.is_empty() {
+        message.push('\n');
+        message.push_str(&ops::describe_restored(&restored_files));
     }
+    Ok(message)
+}
 
-    Ok("Config restored successfully".to_string())
+#[tauri::command]
+fn restore_config_atomic(
+    backup_name: &str,
+    app_ids: Vec<String>,
+    machine_profile: Option<String>,
+) -> Result<String, String> {
+    let restored_files = ops::restore_atomic(backup_name, &app_ids, machine_profile.as_deref())
+        .map_err(|e| e.to_string())?;
+
+    let mut message = "Config restored successfully".to_string();
+    if !restored_files.is_empty() {
+        message.push('\n');
+        message.push_str(&ops::describe_restored(&restored_files));
+    }
+    Ok(message)
 }
 
 #[tauri::command]
-fn verify_backup_integrity(backup_name: &str) -> Result<String, String> {
+fn diff_backups(backup_a: &str, backup_b: &str) -> Result<ops::BackupDiff, String> {
+    ops::diff_backups(backup_a, backup_b).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn diff_entry(backup_a: &str, backup_b: &str, logical_path: &str) -> Result<String, String> {
+    ops::diff_entry(backup_a, backup_b, logical_path).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_restore_log(limit: Option<usize>) -> Result<Vec<ops::RestoreLogEntry>, String> {
+    ops::get_restore_log(limit.unwrap_or(50)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn restore_as(
+    backup_name: &str,
+    src_target_hint: &str,
+    dest_app_id: &str,
+    strategy: Option<ConflictStrategy>,
+) -> Result<String, String> {
+    let options = ops::RestoreOptions {
+        strategy: strategy.unwrap_or_default(),
+        ..Default::default()
+    };
+    let restored_files = ops::restore_as(backup_name, src_target_hint, dest_app_id, &options)
+        .map_err(|e| e.to_string())?;
+
+    let mut message = format!(
+        "Manual migration: copied {} file(s) from '{}' into '{}' without converting them. \
+         Check that '{}' understands them before relying on them.",
+        restored_files.len(),
+        src_target_hint,
+        dest_app_id,
+        dest_app_id
+    );
+    message.push('\n');
+    message.push_str(&ops::describe_restored(&restored_files));
+    Ok(message)
+}
+
+#[tauri::command]
+fn extract_blob(backup_name: &str, blob_id: &str, dest_dir: String) -> Result<Vec<String>, String> {
     let manifest = Manifest::load_from(backup_name).map_err(|e| e.to_string())?;
 
-    let is_valid = manifest
-        .verify_blob_chain_integrity()
+    let extracted = manifest
+        .extract_blob_to_dir(blob_id, std::path::Path::new(&dest_dir))
         .map_err(|e| e.to_string())?;
 
-    if is_valid {
-        Ok(format!(
-            "Backup '{}' blob chain integrity verified successfully",
-            backup_name
-        ))
-    } else {
-        Err(format!(
-            "Backup '{}' failed blob chain integrity verification",
-            backup_name
-        ))
+    Ok(extracted
+        .iter()
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect())
+}
+
+#[tauri::command]
+fn merge_backups(a: &str, b: &str, into: &str) -> Result<String, String> {
+    let report = ops::merge_backups(a, b, into).map_err(|e| e.to_string())?;
+
+    let mut message = format!(
+        "Merged '{}' and '{}' into '{}': {} entries, {} blobs",
+        a, b, report.name, report.entry_count, report.blob_count
+    );
+    for conflict in &report.conflicts {
+        message.push_str(&format!(
+            "\nConflict on {}: kept '{}', dropped '{}'",
+            conflict.logical_path, conflict.kept_from, conflict.dropped_from
+        ));
     }
+    Ok(message)
+}
+
+#[tauri::command]
+fn verify_backup_integrity(backup_name: &str) -> Result<String, String> {
+    ops::verify_backup(backup_name).map_err(|e| e.to_string())?;
+    Ok(format!(
+        "Backup '{}' blob chain and blob files verified successfully",
+        backup_name
+    ))
+}
+
+#[tauri::command]
+fn verify_blob_copies(backup_name: &str) -> Result<Vec<String>, String> {
+    ops::verify_blob_copies(backup_name).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn verify_backup_deep(backup_name: &str) -> Result<String, String> {
+    ops::verify_backup_deep(backup_name).map_err(|e| e.to_string())?;
+    Ok(format!("Backup '{}' passed deep verification", backup_name))
+}
+
+#[tauri::command]
+fn heal_blob(backup: &str, blob_id: &str) -> Result<bool, String> {
+    ops::heal_blob(backup, blob_id).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -196,23 +431,155 @@ fn verify_backup_chain(start_backup_name: &str) -> Result<String, String> {
     }
 }
 
+#[tauri::command]
+fn verify_backup_chain_hash(name: &str) -> Result<bool, String> {
+    let manifest = Manifest::load_from(name).map_err(|e| e.to_string())?;
+    manifest.verify_backup_chain_hash().map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 fn get_backup_chain_info(backup_name: &str) -> Result<BackupChainInfo, String> {
     let manifest = Manifest::load_from(backup_name).map_err(|e| e.to_string())?;
 
     Ok(BackupChainInfo {
         name: manifest.name.clone(),
-        backup_hash: "N/A (using blob-based blockchain)".to_string(),
+        backup_hash: manifest.calculate_backup_hash(),
         chain_hash: manifest.get_blob_chain_info().unwrap_or_default(),
-        previous_backup_hash: None, // No longer used in blob-based blockchain
+        previous_backup_hash: manifest.previous_backup_hash.clone(),
         is_integrity_valid: manifest
             .verify_blob_chain_integrity()
             .map_err(|e| e.to_string())?,
     })
 }
 
+#[tauri::command]
+fn export_chain_graph(backup: &str) -> Result<String, String> {
+    let manifest = Manifest::load_from(backup).map_err(|e| e.to_string())?;
+    let links = manifest.export_chain_links().map_err(|e| e.to_string())?;
+    serde_json::to_string_pretty(&links).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn pack_backup(name: &str) -> Result<String, String> {
+    let mut manifest = Manifest::load_from(name).map_err(|e| e.to_string())?;
+    let stats = manifest.pack_blobs().map_err(|e| e.to_string())?;
+    Ok(format!(
+        "Packed {} loose blobs ({:.2}MB) for '{}'",
+        stats.blobs_packed,
+        stats.bytes_packed as f64 / (1024.0 * 1024.0),
+        name
+    ))
+}
+
+#[tauri::command]
+fn export_backup(backup_name: &str, dest_path: &str) -> Result<String, String> {
+    let file = std::fs::File::create(dest_path).map_err(|e| e.to_string())?;
+    let mut writer = ops::export_backup(backup_name, std::io::BufWriter::new(file))
+        .map_err(|e| e.to_string())?;
+    std::io::Write::flush(&mut writer).map_err(|e| e.to_string())?;
+    Ok(format!("Backup '{}' exported to {}", backup_name, dest_path))
+}
+
+#[tauri::command]
+fn import_backup(bundle_path: &str, new_name: Option<String>) -> Result<String, String> {
+    let file = std::fs::File::open(bundle_path).map_err(|e| e.to_string())?;
+    let name = ops::import_backup(std::io::BufReader::new(file), new_name.as_deref())
+        .map_err(|e| e.to_string())?;
+    Ok(format!("Backup '{}' imported", name))
+}
+
+#[tauri::command]
+fn import_dotfiles_repo(repo_path: String, work_tree: String) -> Result<String, String> {
+    let name = ops::import_dotfiles_repo(Path::new(&repo_path), Path::new(&work_tree))
+        .map_err(|e| e.to_string())?;
+    Ok(format!("Dotfiles from {} imported as backup '{}'", repo_path, name))
+}
+
+#[tauri::command]
+fn prune_backups(max_age_days: u32) -> Result<String, String> {
+    let pruned = ops::prune_backups(max_age_days).map_err(|e| e.to_string())?;
+    if pruned.is_empty() {
+        Ok(format!("No backups older than {} days", max_age_days))
+    } else {
+        Ok(format!("Pruned {} backups: {}", pruned.len(), pruned.join(", ")))
+    }
+}
+
+#[tauri::command]
+fn delete_backup(backup_name: &str, force: Option<bool>) -> Result<String, String> {
+    ops::delete_backup(backup_name, force.unwrap_or(false)).map_err(|e| e.to_string())?;
+    Ok(format!("Backup '{}' deleted", backup_name))
+}
+
+#[tauri::command]
+fn find_orphan_blobs(name: &str) -> Result<Vec<String>, String> {
+    ops::find_orphan_blobs(name).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn gc_orphaned_blobs(name: &str) -> Result<Vec<String>, String> {
+    ops::gc_orphaned_blobs(name).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn preview_delete(name: &str) -> Result<ops::DeletePreview, String> {
+    ops::preview_delete(name).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn rebase_backup(name: &str, new_parent: Option<String>) -> Result<(), String> {
+    ops::rebase_backup(name, new_parent.as_deref()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn rebuild_refcounts() -> Result<String, String> {
+    let blobs = ops::rebuild_refcounts().map_err(|e| e.to_string())?;
+    Ok(format!("Recounted references to {} blobs", blobs))
+}
+
+#[tauri::command]
+fn migrate_to_blob_pool() -> Result<String, String> {
+    let migration = ops::migrate_to_blob_pool().map_err(|e| e.to_string())?;
+    Ok(format!(
+        "Pooled the blobs of {} backups: {} moved, {} duplicates removed ({} bytes freed)",
+        migration.backups_migrated,
+        migration.blobs_pooled,
+        migration.duplicates_removed,
+        migration.bytes_freed
+    ))
+}
+
+#[tauri::command]
+fn reload_custom_apps() -> String {
+    format!("Registered {} new custom apps", ops::reload_custom_apps())
+}
+
+#[tauri::command]
+fn health_check() -> Result<HealthReport, String> {
+    storage::health::health_check().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_scrub_results() -> Result<BTreeMap<String, ops::ScrubResult>, String> {
+    ops::get_scrub_results().map_err(|e| e.to_string())
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // Surface storage corruption at launch rather than at the next restore
+    std::thread::spawn(|| match storage::health::health_check() {
+        Ok(report) if report.is_healthy() => println!("Storage health check passed"),
+        Ok(report) => {
+            if !report.writable {
+                println!("Storage directory is not writable: {}", report.storage_dir);
+            }
+            for backup in report.backups.iter().filter(|b| !b.is_healthy()) {
+                println!("Backup '{}' has problems: {}", backup.name, backup.errors.join("; "));
+            }
+        }
+        Err(e) => println!("Storage health check failed: {}", e),
+    });
+
     tauri::Builder::default()
         .plugin(
             tauri_plugin_log::Builder::new()
@@ -223,14 +590,70 @@ pub fn run() {
         )
         .plugin(tauri_plugin_os::init())
         .plugin(tauri_plugin_shell::init())
+        .setup(|app| {
+            // Re-verify backups in the background when `scrub_interval_hours` is set
+            let app_handle = app.handle().clone();
+            std::thread::spawn(move || {
+                ops::run_scrub_schedule(|failed| {
+                    if let Err(e) = app_handle.emit(progress::SCRUB_FAILED_EVENT, failed) {
+                        println!("Failed to emit scrub failures: {}", e);
+                    }
+                })
+            });
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             list_applications,
+            reload_custom_apps,
             save_config,
+            save_config_subset,
+            scan_sensitive,
+            cancel_backup,
             list_backups,
+            list_backups_paged,
+            list_backups_for_profile,
+            backup_tree,
             restore_config,
+            restore_config_matching,
+            restore_config_interactive,
+            answer_restore_conflict,
+            restore_config_atomic,
+            restore_as,
+            get_restore_log,
+            diff_backups,
+            diff_entry,
             verify_backup_integrity,
+            verify_backup_deep,
+            verify_blob_copies,
+            heal_blob,
             verify_backup_chain,
-            get_backup_chain_info
+            verify_backup_chain_hash,
+            get_backup_chain_info,
+            extract_blob,
+            get_crate_version,
+            get_settings,
+            update_settings,
+            set_encryption_passphrase,
+            export_chain_graph,
+            merge_backups,
+            health_check,
+            get_scrub_results,
+            pack_backup,
+            export_backup,
+            import_backup,
+            import_dotfiles_repo,
+            prune_backups,
+            delete_backup,
+            preview_delete,
+            find_orphan_blobs,
+            gc_orphaned_blobs,
+            rebase_backup,
+            rebuild_refcounts,
+            migrate_to_blob_pool,
+            push_backup,
+            pull_backup,
+            restore_from_remote,
+            list_remote_backups
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,32 @@
+//! User-facing messages of errors that reach the UI or CLI.
+//!
+//! Everything is English for now; keeping the wording here rather than
+//! inline in `anyhow!` calls keeps it consistent and gives a translation
+//! layer a single place to hook into.
+
+use std::fmt::Display;
+use std::path::Path;
+
+pub const BLOB_BASE64_INVALID: &str = "Failed to decode the blob's base64 payload";
+
+pub const ZSTD_DECOMPRESS_FAILED: &str = "Failed to decompress zstd data";
+
+pub fn blob_not_in_manifest(blob_id: &str) -> String {
+    format!("Blob {} is not in the manifest", blob_id)
+}
+
+pub fn decompression_failed(error: impl Display) -> String {
+    format!("Decompression error: {}", error)
+}
+
+pub fn unknown_blob_format(format: &str) -> String {
+    format!("Unknown blob format: {}", format)
+}
+
+pub fn no_parent_dir(path: &Path) -> String {
+    format!("Destination has no parent directory: {}", path.display())
+}
+
+pub fn member_not_in_blob(member: &str, blob_id: &str) -> String {
+    format!("Member '{}' not found in the TAR of blob {}", member, blob_id)
+}
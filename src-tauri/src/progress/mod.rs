@@ -0,0 +1,301 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard, TryLockError};
+use std::time::{Duration, Instant};
+
+use crate::storage::performance::PERFORMANCE_METRICS;
+
+/// Event name the frontend listens on for backup progress
+pub const BACKUP_PROGRESS_EVENT: &str = "backup-progress";
+
+/// Event name for attempts and retries of remote push/pull/list
+pub const REMOTE_PROGRESS_EVENT: &str = "remote-progress";
+
+/// Event name for conflicts an interactive restore is waiting on
+pub const RESTORE_CONFLICT_EVENT: &str = "restore-conflict";
+
+/// Event name for backups that failed a background verification
+pub const SCRUB_FAILED_EVENT: &str = "scrub-failed";
+
+/// Minimum time between two progress events
+const EMIT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Weight of the newest throughput sample in the rolling average
+const THROUGHPUT_SMOOTHING: f64 = 0.3;
+
+/// Set by `cancel_backup`, checked by the running backup between files
+static BACKUP_CANCELLED: AtomicBool = AtomicBool::new(false);
+
+pub fn request_cancel() {
+    BACKUP_CANCELLED.store(true, Ordering::SeqCst);
+}
+
+/// Clear a stale cancel request before a new backup starts
+pub fn reset_cancel() {
+    BACKUP_CANCELLED.store(false, Ordering::SeqCst);
+}
+
+pub fn is_cancelled() -> bool {
+    BACKUP_CANCELLED.load(Ordering::SeqCst)
+}
+
+/// Held shared by saves, restores and other work reading or changing
+/// backups, and exclusively by a background verification, so a backup is
+/// never verified while something else uses the storage
+static OPERATIONS: RwLock<()> = RwLock::new(());
+
+/// Marks a save, restore or other operation on backups as in progress until
+/// dropped. Waits for a background verification underway to finish first.
+pub struct OperationGuard {
+    _lock: RwLockReadGuard<'static, ()>,
+}
+
+impl OperationGuard {
+    pub fn start() -> Self {
+        Self {
+            _lock: OPERATIONS.read().unwrap_or_else(PoisonError::into_inner),
+        }
+    }
+}
+
+/// Keeps every `OperationGuard` from starting until dropped, for the
+/// duration of one background verification
+pub struct ExclusiveGuard {
+    _lock: RwLockWriteGuard<'static, ()>,
+}
+
+impl ExclusiveGuard {
+    /// None while any operation is in progress, so background work never
+    /// makes one wait for long
+    pub fn try_start() -> Option<Self> {
+        match OPERATIONS.try_write() {
+            Ok(lock) => Some(Self { _lock: lock }),
+            Err(TryLockError::Poisoned(poisoned)) => Some(Self {
+                _lock: poisoned.into_inner(),
+            }),
+            Err(TryLockError::WouldBlock) => None,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct BackupProgress {
+    pub files_done: usize,
+    pub files_total: usize,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    /// Share of the work done, from 0 to 1, weighted by file size so one
+    /// large file counts for more than many tiny ones. Falls back to the file
+    /// count when every file is empty.
+    pub fraction: f64,
+    /// None until there is enough throughput data for an estimate
+    pub eta_seconds: Option<u64>,
+}
+
+impl BackupProgress {
+    /// e.g. `142/500 files, 3.1/8.0 GB`
+    pub fn describe(&self) -> String {
+        format!(
+            "{}/{} files, {}",
+            self.files_done,
+            self.files_total,
+            describe_bytes(self.bytes_done, self.bytes_total)
+        )
+    }
+}
+
+/// `bytes_done` and `bytes_total` in the unit that suits the total, e.g.
+/// `3.1/8.0 GB` or `512/2048 B`
+fn describe_bytes(bytes_done: u64, bytes_total: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes_total < 1024 {
+        return format!("{}/{} B", bytes_done, bytes_total);
+    }
+    let mut scale = 1024.0;
+    let mut unit = 0;
+    while unit + 1 < UNITS.len() && bytes_total as f64 >= scale * 1024.0 {
+        scale *= 1024.0;
+        unit += 1;
+    }
+    format!(
+        "{:.1}/{:.1} {}",
+        bytes_done as f64 / scale,
+        bytes_total as f64 / scale,
+        UNITS[unit]
+    )
+}
+
+/// Counts processed files and bytes and decides when a progress event is due
+pub struct ProgressTracker {
+    files_total: usize,
+    bytes_total: u64,
+    files_done: usize,
+    bytes_done: u64,
+    last_sample: Instant,
+    last_sample_bytes: u64,
+    last_emit: Option<Instant>,
+    /// Rolling throughput in bytes per second
+    throughput: f64,
+}
+
+impl ProgressTracker {
+    pub fn new(files_total: usize, bytes_total: u64) -> Self {
+        Self::new_at(files_total, bytes_total, Instant::now())
+    }
+
+    fn new_at(files_total: usize, bytes_total: u64, now: Instant) -> Self {
+        // Seed with the throughput of earlier backups in this session, if any
+        let throughput =
+            PERFORMANCE_METRICS.get_stats().compression_throughput_mbps() * 1024.0 * 1024.0;
+        Self {
+            files_total,
+            bytes_total,
+            files_done: 0,
+            bytes_done: 0,
+            last_sample: now,
+            last_sample_bytes: 0,
+            last_emit: None,
+            throughput,
+        }
+    }
+
+    /// Record one processed file of `bytes`. Returns a progress snapshot when
+    /// the throttle interval has passed or the backup is complete.
+    pub fn advance(&mut self, bytes: u64) -> Option<BackupProgress> {
+        self.advance_at(bytes, Instant::now())
+    }
+
+    fn advance_at(&mut self, bytes: u64, now: Instant) -> Option<BackupProgress> {
+        self.files_done += 1;
+        self.bytes_done += bytes;
+
+        let finished = self.files_done >= self.files_total;
+        let due = self
+            .last_emit
+            .is_none_or(|last| now.duration_since(last) >= EMIT_INTERVAL);
+        if !due && !finished {
+            return None;
+        }
+
+        let elapsed = now.duration_since(self.last_sample).as_secs_f64();
+        if elapsed > 0.0 {
+            let sample = (self.bytes_done - self.last_sample_bytes) as f64 / elapsed;
+            self.throughput = if self.throughput > 0.0 {
+                THROUGHPUT_SMOOTHING * sample + (1.0 - THROUGHPUT_SMOOTHING) * self.throughput
+            } else {
+                sample
+            };
+            self.last_sample = now;
+            self.last_sample_bytes = self.bytes_done;
+        }
+        self.last_emit = Some(now);
+
+        Some(self.snapshot(finished))
+    }
+
+    fn snapshot(&self, finished: bool) -> BackupProgress {
+        let remaining = self.bytes_total.saturating_sub(self.bytes_done);
+        let eta_seconds = if finished {
+            Some(0)
+        } else if self.throughput > 0.0 {
+            Some((remaining as f64 / self.throughput).ceil() as u64)
+        } else {
+            None
+        };
+        let fraction = if finished {
+            1.0
+        } else if self.bytes_total > 0 {
+            self.bytes_done as f64 / self.bytes_total as f64
+        } else {
+            self.files_done as f64 / self.files_total.max(1) as f64
+        };
+        BackupProgress {
+            files_done: self.files_done,
+            files_total: self.files_total,
+            bytes_done: self.bytes_done,
+            bytes_total: self.bytes_total,
+            fraction: fraction.min(1.0),
+            eta_seconds,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_is_throttled_and_estimates_eta() {
+        let start = Instant::now();
+        let mut tracker = ProgressTracker::new_at(4, 4000, start);
+        tracker.throughput = 0.0;
+
+        // First file always reports; 1000 bytes in 1s gives 1000 B/s
+        let first = tracker
+            .advance_at(1000, start + Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(first.files_done, 1);
+        assert_eq!(first.eta_seconds, Some(3));
+
+        // Within the throttle interval nothing is emitted
+        let quick = start + Duration::from_secs(1) + Duration::from_millis(10);
+        assert!(tracker.advance_at(1000, quick).is_none());
+
+        let later = tracker
+            .advance_at(1000, start + Duration::from_secs(3))
+            .unwrap();
+        assert_eq!(later.bytes_done, 3000);
+        assert!(later.eta_seconds.unwrap() >= 1);
+
+        // The last file is always reported, even inside the interval
+        let done = tracker
+            .advance_at(1000, start + Duration::from_secs(3))
+            .unwrap();
+        assert_eq!(done.files_done, 4);
+        assert_eq!(done.eta_seconds, Some(0));
+    }
+
+    #[test]
+    fn test_progress_is_weighted_by_bytes() {
+        let start = Instant::now();
+        // Two 1 MB files and two tiny ones
+        let mut tracker = ProgressTracker::new_at(4, 2 * 1_048_576 + 2, start);
+        let tiny = tracker.advance_at(1, start).unwrap();
+        assert_eq!(tiny.files_done, 1);
+        assert!(tiny.fraction < 0.001);
+
+        let later = start + Duration::from_secs(1);
+        let big = tracker.advance_at(1_048_576, later).unwrap();
+        assert!((big.fraction - 0.5).abs() < 0.001);
+        assert_eq!(big.describe(), "2/4 files, 1.0/2.0 MB");
+
+        let empty = ProgressTracker::new_at(2, 0, start).advance_at(0, start).unwrap();
+        assert_eq!(empty.fraction, 0.5);
+        assert_eq!(empty.describe(), "1/2 files, 0/0 B");
+        assert_eq!(describe_bytes(3_328_599_654, 8_589_934_592), "3.1/8.0 GB");
+    }
+
+    #[test]
+    fn test_exclusive_guard_excludes_operations() {
+        let running = OperationGuard::start();
+        assert!(ExclusiveGuard::try_start().is_none());
+        drop(running);
+
+        // Other tests may hold an operation guard for a moment
+        let exclusive = loop {
+            if let Some(guard) = ExclusiveGuard::try_start() {
+                break guard;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        };
+        let (started_tx, started_rx) = std::sync::mpsc::channel();
+        let operation = std::thread::spawn(move || {
+            let _running = OperationGuard::start();
+            started_tx.send(()).unwrap();
+        });
+        assert!(started_rx.recv_timeout(Duration::from_millis(100)).is_err());
+        drop(exclusive);
+        assert!(started_rx.recv_timeout(Duration::from_secs(5)).is_ok());
+        operation.join().unwrap();
+    }
+}
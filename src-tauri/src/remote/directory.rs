@@ -0,0 +1,184 @@
+use std::{
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use super::{RemoteBackend, RemoteError, RemoteErrorKind};
+use crate::storage::atomic::write_atomic_unique_with;
+
+const BUNDLE_SUFFIX: &str = ".saveme.tar.zst";
+const OBJECTS_SUFFIX: &str = ".objects";
+
+/// A remote that is a directory: a mounted network share or a folder kept
+/// in sync by another tool. Each backup is one bundle file, with its
+/// objects in a `<name>.objects` directory beside it.
+pub struct DirectoryBackend {
+    root: PathBuf,
+}
+
+impl DirectoryBackend {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    fn bundle_path(&self, name: &str) -> Result<PathBuf, RemoteError> {
+        if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+            return Err(RemoteError::new(
+                RemoteErrorKind::Other,
+                format!("Invalid backup name: {:?}", name),
+            ));
+        }
+        Ok(self.root.join(format!("{}{}", name, BUNDLE_SUFFIX)))
+    }
+
+    fn objects_dir(&self, name: &str) -> Result<PathBuf, RemoteError> {
+        self.bundle_path(name)?;
+        Ok(self.root.join(format!("{}{}", name, OBJECTS_SUFFIX)))
+    }
+
+    fn object_path(&self, name: &str, key: &str) -> Result<PathBuf, RemoteError> {
+        let valid_key = !key.is_empty()
+            && !key.starts_with('.')
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+        if !valid_key {
+            return Err(RemoteError::new(
+                RemoteErrorKind::Other,
+                format!("Invalid object key: {:?}", key),
+            ));
+        }
+        Ok(self.objects_dir(name)?.join(key))
+    }
+}
+
+/// Copy `source` to `path` atomically. The temp file is unique to this
+/// call, as a timed-out attempt may still be writing when it is retried.
+fn write_file(path: &Path, source: &mut dyn Read) -> Result<(), RemoteError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let copy = |file: &mut fs::File| -> anyhow::Result<()> {
+        io::copy(source, file)?;
+        Ok(())
+    };
+    write_atomic_unique_with(path, copy).map_err(|e| match e.downcast::<std::io::Error>() {
+        Ok(io_error) => RemoteError::from(io_error),
+        Err(e) => RemoteError::new(RemoteErrorKind::Other, e.to_string()),
+    })
+}
+
+fn read_file(path: &Path, missing: impl FnOnce() -> String) -> Result<Vec<u8>, RemoteError> {
+    fs::read(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => RemoteError::new(RemoteErrorKind::NotFound, missing()),
+        _ => e.into(),
+    })
+}
+
+impl RemoteBackend for DirectoryBackend {
+    fn push(&self, name: &str, bundle: &mut dyn Read) -> Result<(), RemoteError> {
+        write_file(&self.bundle_path(name)?, bundle)
+    }
+
+    fn pull(&self, name: &str) -> Result<Vec<u8>, RemoteError> {
+        read_file(&self.bundle_path(name)?, || {
+            format!("Backup '{}' is not on the remote", name)
+        })
+    }
+
+    fn list(&self) -> Result<Vec<String>, RemoteError> {
+        if !self.root.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut names: Vec<String> = fs::read_dir(&self.root)?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let file_name = entry.file_name().to_string_lossy().into_owned();
+                file_name.strip_suffix(BUNDLE_SUFFIX).map(str::to_string)
+            })
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    fn push_object(&self, name: &str, key: &str, bytes: &[u8]) -> Result<(), RemoteError> {
+        write_file(&self.object_path(name, key)?, &mut &bytes[..])
+    }
+
+    fn pull_object(&self, name: &str, key: &str) -> Result<Vec<u8>, RemoteError> {
+        read_file(&self.object_path(name, key)?, || {
+            format!("'{}' of backup '{}' is not on the remote", key, name)
+        })
+    }
+
+    /// Temp files of uploads in progress (`<key>.<pid>-<n>.tmp`) are not
+    /// objects
+    fn list_objects(&self, name: &str) -> Result<Vec<String>, RemoteError> {
+        let dir = self.objects_dir(name)?;
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut keys: Vec<String> = fs::read_dir(&dir)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_file()))
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .filter(|key| !key.ends_with(".tmp"))
+            .collect();
+        keys.sort();
+        Ok(keys)
+    }
+
+    fn delete_object(&self, name: &str, key: &str) -> Result<(), RemoteError> {
+        match fs::remove_file(self.object_path(name, key)?) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_directory_backend_round_trip() -> anyhow::Result<()> {
+        let temp_dir = TempDir::new()?;
+        let backend = DirectoryBackend::new(temp_dir.path().join("remote"));
+        assert!(backend.list()?.is_empty());
+
+        backend.push("daily", &mut &b"bundle"[..])?;
+        backend.push("weekly", &mut &b"other"[..])?;
+        assert_eq!(backend.pull("daily")?, b"bundle");
+        assert_eq!(backend.list()?, ["daily", "weekly"]);
+
+        let missing = backend.pull("monthly").unwrap_err();
+        assert_eq!(missing.kind, RemoteErrorKind::NotFound);
+        assert!(!missing.is_retryable());
+        assert!(backend.push("../escape", &mut &b"x"[..]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_directory_backend_objects() -> anyhow::Result<()> {
+        let temp_dir = TempDir::new()?;
+        let backend = DirectoryBackend::new(temp_dir.path().join("remote"));
+        backend.push("daily", &mut &b"bundle"[..])?;
+        backend.push_object("daily", "manifest.json", b"{}")?;
+        backend.push_object("daily", "blob-abc", b"blob")?;
+
+        assert_eq!(backend.pull_object("daily", "blob-abc")?, b"blob");
+        assert_eq!(backend.list()?, ["daily"]);
+        let missing = backend.pull_object("daily", "blob-def").unwrap_err();
+        assert_eq!(missing.kind, RemoteErrorKind::NotFound);
+        assert!(backend.push_object("daily", "../escape", b"x").is_err());
+        assert!(backend.push_object("../daily", "blob-abc", b"x").is_err());
+
+        assert_eq!(backend.list_objects("daily")?, ["blob-abc", "manifest.json"]);
+        backend.delete_object("daily", "blob-abc")?;
+        backend.delete_object("daily", "blob-abc")?;
+        assert_eq!(backend.list_objects("daily")?, ["manifest.json"]);
+        assert!(backend.list_objects("weekly")?.is_empty());
+        assert!(backend.delete_object("daily", "../escape").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,205 @@
+pub mod directory;
+pub mod retry;
+
+use std::{
+    fmt,
+    fs::File,
+    io::{self, Read},
+    path::Path,
+    sync::Arc,
+};
+
+use anyhow::{anyhow, Result};
+
+use crate::settings::Settings;
+use directory::DirectoryBackend;
+use retry::{with_retry, RemoteAttempt, RetryPolicy};
+
+/// Why a remote operation failed, which decides whether it is retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemoteErrorKind {
+    /// No answer within the policy's timeout
+    Timeout,
+    /// Network or server-side failure (5xx, connection reset, busy share)
+    Unavailable,
+    /// Credentials were rejected or access is denied
+    Unauthorized,
+    /// The requested backup isn't on the remote
+    NotFound,
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteError {
+    pub kind: RemoteErrorKind,
+    pub message: String,
+}
+
+impl RemoteError {
+    pub fn new(kind: RemoteErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    /// Timeouts and transient failures may succeed on another attempt; auth
+    /// and missing-backup errors never will
+    pub fn is_retryable(&self) -> bool {
+        matches!(self.kind, RemoteErrorKind::Timeout | RemoteErrorKind::Unavailable)
+    }
+}
+
+impl fmt::Display for RemoteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for RemoteError {}
+
+impl From<io::Error> for RemoteError {
+    fn from(e: io::Error) -> Self {
+        let kind = match e.kind() {
+            io::ErrorKind::TimedOut => RemoteErrorKind::Timeout,
+            io::ErrorKind::NotFound => RemoteErrorKind::NotFound,
+            io::ErrorKind::PermissionDenied => RemoteErrorKind::Unauthorized,
+            io::ErrorKind::Interrupted
+            | io::ErrorKind::WouldBlock
+            | io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::NotConnected
+            | io::ErrorKind::BrokenPipe => RemoteErrorKind::Unavailable,
+            _ => RemoteErrorKind::Other,
+        };
+        Self::new(kind, e.to_string())
+    }
+}
+
+/// Somewhere backup bundles (as written by `export_backup`) can be synced
+/// to. Implementations make a single attempt; `Remote` adds the timeouts
+/// and retries.
+pub trait RemoteBackend: Send + Sync {
+    /// Upload the bundle of backup `name` read from `bundle`, replacing any
+    /// previous upload
+    fn push(&self, name: &str, bundle: &mut dyn Read) -> Result<(), RemoteError>;
+    /// Download the bundle of backup `name`
+    fn pull(&self, name: &str) -> Result<Vec<u8>, RemoteError>;
+    /// Names of the backups on the remote
+    fn list(&self) -> Result<Vec<String>, RemoteError>;
+    /// Upload one object of backup `name` (its manifest or a single blob)
+    /// next to the bundle, so restores can fetch just what they need
+    fn push_object(&self, name: &str, key: &str, bytes: &[u8]) -> Result<(), RemoteError>;
+    /// Download one object of backup `name`
+    fn pull_object(&self, name: &str, key: &str) -> Result<Vec<u8>, RemoteError>;
+    /// Keys of the objects of backup `name`
+    fn list_objects(&self, name: &str) -> Result<Vec<String>, RemoteError>;
+    /// Remove one object of backup `name`; removing a missing one succeeds
+    fn delete_object(&self, name: &str, key: &str) -> Result<(), RemoteError>;
+}
+
+/// A backend behind the configured timeout and retry policy
+pub struct Remote {
+    backend: Arc<dyn RemoteBackend>,
+    policy: RetryPolicy,
+}
+
+impl Remote {
+    pub fn new(backend: Arc<dyn RemoteBackend>, policy: RetryPolicy) -> Self {
+        Self { backend, policy }
+    }
+
+    /// The remote configured in `settings`
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        let dir = settings
+            .remote_dir
+            .clone()
+            .ok_or_else(|| anyhow!("No remote is configured (set `remote_dir`)"))?;
+        Ok(Self::new(Arc::new(DirectoryBackend::new(dir)), RetryPolicy::from_settings(settings)))
+    }
+
+    /// Upload the bundle file at `bundle`, streamed from disk and reopened
+    /// for each attempt
+    pub fn push(
+        &self,
+        name: &str,
+        bundle: &Path,
+        on_attempt: impl FnMut(RemoteAttempt),
+    ) -> Result<(), RemoteError> {
+        let backend = self.backend.clone();
+        let name = name.to_string();
+        let bundle = bundle.to_path_buf();
+        with_retry(&self.policy, "push", on_attempt, move || {
+            backend.push(&name, &mut File::open(&bundle)?)
+        })
+    }
+
+    pub fn pull(
+        &self,
+        name: &str,
+        on_attempt: impl FnMut(RemoteAttempt),
+    ) -> Result<Vec<u8>, RemoteError> {
+        let backend = self.backend.clone();
+        let name = name.to_string();
+        with_retry(&self.policy, "pull", on_attempt, move || backend.pull(&name))
+    }
+
+    pub fn push_object(
+        &self,
+        name: &str,
+        key: &str,
+        bytes: Vec<u8>,
+        on_attempt: impl FnMut(RemoteAttempt),
+    ) -> Result<(), RemoteError> {
+        let backend = self.backend.clone();
+        let (name, key) = (name.to_string(), key.to_string());
+        let bytes = Arc::new(bytes);
+        with_retry(&self.policy, "push object", on_attempt, move || {
+            backend.push_object(&name, &key, &bytes)
+        })
+    }
+
+    pub fn pull_object(
+        &self,
+        name: &str,
+        key: &str,
+        on_attempt: impl FnMut(RemoteAttempt),
+    ) -> Result<Vec<u8>, RemoteError> {
+        let backend = self.backend.clone();
+        let (name, key) = (name.to_string(), key.to_string());
+        with_retry(&self.policy, "pull object", on_attempt, move || {
+            backend.pull_object(&name, &key)
+        })
+    }
+
+    pub fn list_objects(
+        &self,
+        name: &str,
+        on_attempt: impl FnMut(RemoteAttempt),
+    ) -> Result<Vec<String>, RemoteError> {
+        let backend = self.backend.clone();
+        let name = name.to_string();
+        with_retry(&self.policy, "list objects", on_attempt, move || {
+            backend.list_objects(&name)
+        })
+    }
+
+    pub fn delete_object(
+        &self,
+        name: &str,
+        key: &str,
+        on_attempt: impl FnMut(RemoteAttempt),
+    ) -> Result<(), RemoteError> {
+        let backend = self.backend.clone();
+        let (name, key) = (name.to_string(), key.to_string());
+        with_retry(&self.policy, "delete object", on_attempt, move || {
+            backend.delete_object(&name, &key)
+        })
+    }
+
+    pub fn list(&self, on_attempt: impl FnMut(RemoteAttempt)) -> Result<Vec<String>, RemoteError> {
+        let backend = self.backend.clone();
+        with_retry(&self.policy, "list", on_attempt, move || backend.list())
+    }
+}
@@ -0,0 +1,224 @@
+use std::{
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError, TryRecvError},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use serde::Serialize;
+
+use super::{RemoteError, RemoteErrorKind};
+use crate::settings::Settings;
+
+/// Timeout and backoff applied to every remote operation
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Attempts in total, including the first
+    pub max_attempts: u32,
+    /// How long a single attempt may take
+    pub timeout: Duration,
+    /// Wait before the first retry; doubled after each further failure
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            timeout: Duration::from_secs(60),
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self {
+            max_attempts: settings.remote_max_attempts.max(1),
+            timeout: Duration::from_secs(settings.remote_timeout_secs.max(1)),
+            ..Self::default()
+        }
+    }
+
+    /// Wait after failed attempt `attempt` (1-based)
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+/// Progress of a remote operation, reported when an attempt starts and when
+/// it fails
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct RemoteAttempt {
+    pub operation: String,
+    /// 1-based
+    pub attempt: u32,
+    pub max_attempts: u32,
+    /// Why the attempt failed; None when it is starting
+    pub error: Option<String>,
+    /// Delay before the next attempt; None when giving up
+    pub retry_in_ms: Option<u64>,
+}
+
+/// Run `op` until it succeeds, fails with a non-retryable error or runs out
+/// of attempts, waiting with exponential backoff in between. Each attempt
+/// runs on its own thread so a hung call stops blocking the caller after
+/// the timeout. A timed-out call can't be stopped, so a retry only starts
+/// a new call once it has finished; while it is still running, the retry
+/// waits on it instead, and never runs `op` twice at the same time.
+pub fn with_retry<T, F>(
+    policy: &RetryPolicy,
+    operation: &str,
+    mut on_attempt: impl FnMut(RemoteAttempt),
+    op: F,
+) -> Result<T, RemoteError>
+where
+    T: Send + 'static,
+    F: Fn() -> Result<T, RemoteError> + Send + Sync + 'static,
+{
+    let op = Arc::new(op);
+    let max_attempts = policy.max_attempts.max(1);
+    let mut attempt = 1;
+    // The call of a timed-out attempt, while it may still be running
+    let mut in_flight: Option<Receiver<Result<T, RemoteError>>> = None;
+    loop {
+        on_attempt(RemoteAttempt {
+            operation: operation.to_string(),
+            attempt,
+            max_attempts,
+            error: None,
+            retry_in_ms: None,
+        });
+
+        let receiver = match in_flight.take() {
+            Some(receiver) => match receiver.try_recv() {
+                // It succeeded late, during the backoff
+                Ok(Ok(value)) => return Ok(value),
+                Err(TryRecvError::Empty) => Some(receiver),
+                Ok(Err(_)) | Err(TryRecvError::Disconnected) => None,
+            },
+            None => None,
+        };
+        let receiver = receiver.unwrap_or_else(|| {
+            let (sender, receiver) = mpsc::channel();
+            let run = op.clone();
+            thread::spawn(move || {
+                let _ = sender.send(run());
+            });
+            receiver
+        });
+        let error = match receiver.recv_timeout(policy.timeout) {
+            Ok(Ok(value)) => return Ok(value),
+            Ok(Err(e)) => e,
+            Err(RecvTimeoutError::Timeout) => {
+                in_flight = Some(receiver);
+                RemoteError::new(
+                    RemoteErrorKind::Timeout,
+                    format!("Remote {} timed out after {:?}", operation, policy.timeout),
+                )
+            }
+            Err(RecvTimeoutError::Disconnected) => RemoteError::new(
+                RemoteErrorKind::Other,
+                format!("Remote {} failed unexpectedly", operation),
+            ),
+        };
+
+        let retry_in = (error.is_retryable() && attempt < max_attempts)
+            .then(|| policy.backoff(attempt));
+        on_attempt(RemoteAttempt {
+            operation: operation.to_string(),
+            attempt,
+            max_attempts,
+            error: Some(error.to_string()),
+            retry_in_ms: retry_in.map(|d| d.as_millis() as u64),
+        });
+        let Some(wait) = retry_in else {
+            return Err(error);
+        };
+        thread::sleep(wait);
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn quick_policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            timeout: Duration::from_millis(200),
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(2),
+        }
+    }
+
+    #[test]
+    fn test_retries_transient_errors_but_not_auth_failures() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = calls.clone();
+        let mut events = Vec::new();
+        let result = with_retry(&quick_policy(), "push", |e| events.push(e), move || {
+            match counter.fetch_add(1, Ordering::SeqCst) {
+                0 => Err(RemoteError::new(RemoteErrorKind::Unavailable, "503")),
+                _ => Ok(7),
+            }
+        });
+        assert_eq!(result, Ok(7));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        // start, failure (retrying), start
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[1].error.as_deref(), Some("503"));
+        assert_eq!(events[1].retry_in_ms, Some(1));
+        assert_eq!(events[2].attempt, 2);
+
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = calls.clone();
+        let result: Result<(), _> = with_retry(&quick_policy(), "pull", |_| {}, move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Err(RemoteError::new(RemoteErrorKind::Unauthorized, "401"))
+        });
+        assert_eq!(result.unwrap_err().kind, RemoteErrorKind::Unauthorized);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_hung_attempts_time_out_and_give_up() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = calls.clone();
+        let mut policy = quick_policy();
+        policy.timeout = Duration::from_millis(20);
+        let result: Result<(), _> = with_retry(&policy, "list", |_| {}, move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(200));
+            Ok(())
+        });
+        assert_eq!(result.unwrap_err().kind, RemoteErrorKind::Timeout);
+        // Retries waited on the hung call instead of starting another
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_retry_waits_on_a_slow_call_instead_of_overlapping_it() {
+        let running = Arc::new(AtomicU32::new(0));
+        let calls = Arc::new(AtomicU32::new(0));
+        let (running_in_op, counter) = (running.clone(), calls.clone());
+        let mut policy = quick_policy();
+        policy.timeout = Duration::from_millis(40);
+        let result = with_retry(&policy, "push", |_| {}, move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            assert_eq!(running_in_op.fetch_add(1, Ordering::SeqCst), 0);
+            thread::sleep(Duration::from_millis(60));
+            running_in_op.fetch_sub(1, Ordering::SeqCst);
+            Ok(5)
+        });
+        assert_eq!(result, Ok(5));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,226 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+
+use super::{copy_replaced, RestoreOutcome, RestoredFile};
+use crate::storage::{atomic::copy_atomic, entry::Entry, manifest::Manifest};
+
+/// A change already made by the commit phase, with how to undo it
+enum Applied {
+    /// A missing parent directory of a destination was created
+    CreatedDir(PathBuf),
+    /// The file did not exist before
+    Created(PathBuf),
+    /// The previous file was copied to `backup` in the restore's `replaced`
+    /// directory first
+    Replaced { dest: PathBuf, backup: PathBuf },
+}
+
+fn undo(applied: &Applied) -> Result<()> {
+    match applied {
+        Applied::CreatedDir(dir) => fs::remove_dir(dir)?,
+        Applied::Created(dest) => fs::remove_file(dest)?,
+        Applied::Replaced { dest, backup } => move_file(backup, dest)?,
+    }
+    Ok(())
+}
+
+/// `fs::rename`, falling back to an atomic copy + delete when staging and
+/// destination sit on different filesystems
+fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    copy_atomic(from, to)?;
+    fs::remove_file(from)?;
+    Ok(())
+}
+
+/// Restore every `(entry, destination)` pair or none of them.
+///
+/// All files are first extracted into `staging_dir`, which decodes and
+/// checks every blob before anything live is touched. They are then moved
+/// into place, existing files being copied into `replaced_dir` first as
+/// with `BackupThenOverwrite`. If a move fails, every change made so far is
+/// undone in reverse order: replaced files get their copy moved back, new
+/// files and the parent directories created for them are removed, and so is
+/// `replaced_dir` once every step is undone; if one fails it is kept and
+/// named in the error. `staging_dir` is removed either way.
+pub fn restore_entries_atomic(
+    manifest: &Manifest,
+    targets: &[(&Entry, PathBuf)],
+    staging_dir: &Path,
+    replaced_dir: &Path,
+) -> Result<Vec<RestoredFile>> {
+    let result = stage_and_commit(manifest, targets, staging_dir, replaced_dir);
+    let _ = fs::remove_dir_all(staging_dir);
+    result
+}
+
+fn stage_and_commit(
+    manifest: &Manifest,
+    targets: &[(&Entry, PathBuf)],
+    staging_dir: &Path,
+    replaced_dir: &Path,
+) -> Result<Vec<RestoredFile>> {
+    fs::create_dir_all(staging_dir)?;
+    let mut staged = Vec::with_capacity(targets.len());
+    for (index, (entry, dest)) in targets.iter().enumerate() {
+        let staged_path = staging_dir.join(index.to_string());
+        manifest.restore_blob_to(entry, &staged_path).map_err(|e| {
+            anyhow!("Nothing restored, staging {} failed: {}", dest.display(), e)
+        })?;
+        staged.push(staged_path);
+    }
+
+    let mut applied: Vec<Applied> = Vec::new();
+    let mut restored = Vec::new();
+    for ((_, dest), staged_path) in targets.iter().zip(&staged) {
+        match commit_one(dest, staged_path, replaced_dir, &mut applied) {
+            Ok(outcome) => restored.push(RestoredFile {
+                path: dest.to_string_lossy().into_owned(),
+                outcome,
+            }),
+            Err(e) => {
+                let mut rolled_back = true;
+                for step in applied.iter().rev() {
+                    if let Err(undo_err) = undo(step) {
+                        println!("Rollback step failed: {}", undo_err);
+                        rolled_back = false;
+                    }
+                }
+                // A failed undo may leave the only copy of a replaced file
+                // in `replaced_dir`
+                if !rolled_back {
+                    return Err(anyhow!(
+                        "Moving {} into place failed ({}) and the rollback was incomplete; \
+                         copies of the replaced files are in {}",
+                        dest.display(),
+                        e,
+                        replaced_dir.display()
+                    ));
+                }
+                let _ = fs::remove_dir_all(replaced_dir);
+                return Err(anyhow!(
+                    "Restore rolled back, moving {} into place failed: {}",
+                    dest.display(),
+                    e
+                ));
+            }
+        }
+    }
+    Ok(restored)
+}
+
+/// Create the missing ancestors of `dest`, outermost first, recording each
+/// in `applied`
+fn create_parents(dest: &Path, applied: &mut Vec<Applied>) -> Result<()> {
+    let missing: Vec<&Path> = dest
+        .ancestors()
+        .skip(1)
+        .take_while(|dir| !dir.as_os_str().is_empty() && dir.symlink_metadata().is_err())
+        .collect();
+    for dir in missing.into_iter().rev() {
+        fs::create_dir(dir)?;
+        applied.push(Applied::CreatedDir(dir.to_path_buf()));
+    }
+    Ok(())
+}
+
+/// Move `staged_path` to `dest`, recording every change in `applied` as it
+/// is made so a later failure can undo this one too
+fn commit_one(
+    dest: &Path,
+    staged_path: &Path,
+    replaced_dir: &Path,
+    applied: &mut Vec<Applied>,
+) -> Result<RestoreOutcome> {
+    create_parents(dest, applied)?;
+    if !dest.exists() {
+        move_file(staged_path, dest)?;
+        applied.push(Applied::Created(dest.to_path_buf()));
+        return Ok(RestoreOutcome::Created);
+    }
+
+    let backup = copy_replaced(dest, replaced_dir)?;
+    if let Err(e) = move_file(staged_path, dest) {
+        let _ = fs::remove_file(&backup);
+        return Err(e);
+    }
+    let outcome = RestoreOutcome::BackedUp {
+        backup_path: backup.to_string_lossy().into_owned(),
+    };
+    applied.push(Applied::Replaced {
+        dest: dest.to_path_buf(),
+        backup,
+    });
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::restore::tests::{entry_for, file_blob};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_atomic_restore_rolls_back_on_failure() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let live = temp_dir.path().join("live");
+        let staging = temp_dir.path().join("staging");
+        let replaced = temp_dir.path().join("replaced");
+        fs::create_dir_all(&live)?;
+        fs::write(live.join("a.conf"), b"local")?;
+        // The user's own backup, which the restore must not touch
+        fs::write(live.join("a.conf.bak"), b"mine")?;
+
+        let mut manifest = Manifest::empty("atomic-test".to_string());
+        manifest.add_blob_for_testing("blob1".to_string(), file_blob("a.conf", b"backup"));
+        manifest.add_blob_for_testing("blob2".to_string(), file_blob("b.conf", b"b"));
+        manifest.add_blob_for_testing("blob3".to_string(), file_blob("c.conf", b"c"));
+        let (entry_a, entry_b, entry_c) = (
+            entry_for("blob1", "a.conf"),
+            entry_for("blob2", "b.conf"),
+            entry_for("blob3", "c.conf"),
+        );
+
+        // Everything stages, then the last move fails: its parent is a file
+        let blocker = live.join("blocked");
+        fs::write(&blocker, b"not a directory")?;
+        let targets = vec![
+            (&entry_a, live.join("a.conf")),
+            (&entry_b, live.join("new").join("b.conf")),
+            (&entry_c, blocker.join("c.conf")),
+        ];
+        assert!(restore_entries_atomic(&manifest, &targets, &staging, &replaced).is_err());
+        assert_eq!(fs::read(live.join("a.conf"))?, b"local");
+        assert_eq!(fs::read(live.join("a.conf.bak"))?, b"mine");
+        assert!(!live.join("new").exists());
+        assert!(!staging.exists());
+        assert!(!replaced.exists());
+
+        // A blob that can't be staged stops the restore before any move
+        let missing = entry_for("gone", "d.conf");
+        let targets = vec![(&entry_a, live.join("a.conf")), (&missing, live.join("d.conf"))];
+        assert!(restore_entries_atomic(&manifest, &targets, &staging, &replaced).is_err());
+        assert_eq!(fs::read(live.join("a.conf"))?, b"local");
+
+        fs::remove_file(&blocker)?;
+        let targets = vec![
+            (&entry_a, live.join("a.conf")),
+            (&entry_c, blocker.join("c.conf")),
+        ];
+        let restored = restore_entries_atomic(&manifest, &targets, &staging, &replaced)?;
+        let RestoreOutcome::BackedUp { backup_path } = &restored[0].outcome else {
+            panic!("a.conf should be backed up, got {:?}", restored[0].outcome);
+        };
+        assert!(Path::new(backup_path).starts_with(&replaced));
+        assert_eq!(fs::read(backup_path)?, b"local");
+        assert_eq!(fs::read(live.join("a.conf"))?, b"backup");
+        assert_eq!(fs::read(live.join("a.conf.bak"))?, b"mine");
+        assert_eq!(fs::read_dir(&live)?.count(), 3);
+        assert_eq!(fs::read(blocker.join("c.conf"))?, b"c");
+        Ok(())
+    }
+}
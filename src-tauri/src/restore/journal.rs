@@ -0,0 +1,101 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::storage::{atomic::write_atomic, entry::Entry, manifest::Manifest};
+
+const JOURNAL_FILE: &str = "restore_journal.json";
+
+/// Record of entries already restored from a backup, so a restore that
+/// failed part way can be re-run without rewriting finished files.
+/// Lives next to the backup's `manifest.json` and is removed once a restore
+/// completes successfully. Progress only carries over to a restore of the
+/// same backup content to the same destinations (see `fingerprint`).
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct RestoreJournal {
+    /// `fingerprint` of the restore this progress belongs to; empty in
+    /// journals written before it was recorded
+    #[serde(default)]
+    fingerprint: String,
+    completed: HashSet<String>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl RestoreJournal {
+    /// Load the journal for a backup directory, starting empty if none exists
+    pub fn load(backup_dir: &Path) -> Result<Self> {
+        let path = backup_dir.join(JOURNAL_FILE);
+        let mut journal = if path.exists() {
+            serde_json::from_str::<Self>(&fs::read_to_string(&path)?)?
+        } else {
+            Self::default()
+        };
+        journal.path = path;
+        Ok(journal)
+    }
+
+    fn key(entry: &Entry) -> String {
+        format!("{}|{}|{}", entry.target_hint, entry.logical_path, entry.blob_id)
+    }
+
+    /// Identifies one restore: the backup's content hash plus every entry
+    /// it restores and where to. Re-saving the backup, selecting other apps
+    /// or files, or restoring elsewhere makes a different restore.
+    pub fn fingerprint(manifest: &Manifest, targets: &[(&Entry, PathBuf)]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(manifest.calculate_backup_hash().as_bytes());
+        for (entry, dest) in targets {
+            hasher.update(Self::key(entry).as_bytes());
+            hasher.update(b"\0");
+            hasher.update(dest.to_string_lossy().as_bytes());
+            hasher.update(b"\0");
+        }
+        hex::encode(hasher.finalize())
+    }
+
+    /// Keep the recorded progress only if it belongs to the restore with
+    /// `fingerprint`; otherwise start over, discarding the stale journal
+    pub fn resume(&mut self, fingerprint: &str) -> Result<()> {
+        if self.fingerprint != fingerprint {
+            if !self.completed.is_empty() {
+                println!("Discarding restore journal of a different restore");
+            }
+            self.clear()?;
+            self.fingerprint = fingerprint.to_string();
+        }
+        Ok(())
+    }
+
+    pub fn is_completed(&self, entry: &Entry) -> bool {
+        self.completed.contains(&Self::key(entry))
+    }
+
+    pub fn completed_count(&self) -> usize {
+        self.completed.len()
+    }
+
+    /// Mark an entry as restored and persist the journal immediately
+    pub fn mark_completed(&mut self, entry: &Entry) -> Result<()> {
+        self.completed.insert(Self::key(entry));
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        write_atomic(&self.path, serde_json::to_string(self)?.as_bytes())
+    }
+
+    /// Forget all progress, called after a fully successful restore
+    pub fn clear(&mut self) -> Result<()> {
+        self.completed.clear();
+        if self.path.exists() {
+            fs::remove_file(&self.path)?;
+        }
+        Ok(())
+    }
+}
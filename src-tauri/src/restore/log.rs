@@ -0,0 +1,165 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+};
+
+use anyhow::Result;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use super::{RestoreOutcome, RestoredFile};
+
+const LOG_FILE: &str = "restore_log.jsonl";
+/// The previous log, replaced each time the current one is rotated
+const ROTATED_LOG_FILE: &str = "restore_log.1.jsonl";
+/// Size past which the log is rotated before the next append
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+
+/// One restore run in the global, append-only restore log. Unlike a
+/// backup's `last_restored_at`, the log keeps every run in order.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RestoreLogEntry {
+    pub timestamp: String,
+    pub backup: String,
+    pub app_ids: Vec<String>,
+    /// Files written (created, overwritten, kept both or backed up first)
+    pub files_restored: usize,
+    /// Entries that couldn't be restored, and the error that stopped the
+    /// run if one did
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failures: Vec<String>,
+}
+
+impl RestoreLogEntry {
+    /// Entry for a restore of `app_ids` from `backup` that ended in `result`
+    pub fn new(
+        backup: &str,
+        app_ids: &[String],
+        result: Result<&[RestoredFile], &anyhow::Error>,
+    ) -> Self {
+        let (files_restored, failures) = match result {
+            Ok(files) => {
+                let restored = files.iter().filter(|file| wrote_file(&file.outcome)).count();
+                let failures = files
+                    .iter()
+                    .filter_map(|file| match &file.outcome {
+                        RestoreOutcome::UnknownApp { target_hint } => {
+                            Some(format!("{}: no app for {}", file.path, target_hint))
+                        }
+                        RestoreOutcome::UnsupportedPlatform { app, .. } => {
+                            Some(format!("{}: {} is not available here", file.path, app))
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                (restored, failures)
+            }
+            Err(e) => (0, vec![e.to_string()]),
+        };
+        Self {
+            timestamp: Utc::now().to_rfc3339(),
+            backup: backup.to_string(),
+            app_ids: app_ids.to_vec(),
+            files_restored,
+            failures,
+        }
+    }
+}
+
+fn wrote_file(outcome: &RestoreOutcome) -> bool {
+    matches!(
+        outcome,
+        RestoreOutcome::Created
+            | RestoreOutcome::Overwritten
+            | RestoreOutcome::KeptBoth { .. }
+            | RestoreOutcome::BackedUp { .. }
+    )
+}
+
+/// Append `entry` to the log in `storage_dir`, first rotating a log that
+/// has grown past `MAX_LOG_BYTES`
+pub fn append_in(storage_dir: &Path, entry: &RestoreLogEntry) -> Result<()> {
+    fs::create_dir_all(storage_dir)?;
+    let path = storage_dir.join(LOG_FILE);
+    if fs::metadata(&path).is_ok_and(|meta| meta.len() > MAX_LOG_BYTES) {
+        fs::rename(&path, storage_dir.join(ROTATED_LOG_FILE))?;
+    }
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// The `limit` most recent entries of the log in `storage_dir`, newest
+/// first, reaching into the rotated log when the current one is short.
+/// Lines that don't parse (e.g. cut off by a crash) are skipped.
+pub fn read_in(storage_dir: &Path, limit: usize) -> Result<Vec<RestoreLogEntry>> {
+    let mut entries = Vec::new();
+    for file in [LOG_FILE, ROTATED_LOG_FILE] {
+        let content = match fs::read_to_string(storage_dir.join(file)) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+        for line in content.lines().rev() {
+            if entries.len() == limit {
+                return Ok(entries);
+            }
+            if let Ok(entry) = serde_json::from_str(line) {
+                entries.push(entry);
+            }
+        }
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_restore_log_appends_reads_and_rotates() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let storage_dir = temp_dir.path();
+        let apps = ["zed".to_string()];
+        let files = vec![
+            RestoredFile {
+                path: "/home/me/.config/zed/settings.json".to_string(),
+                outcome: RestoreOutcome::Created,
+            },
+            RestoredFile {
+                path: "/home/me/.config/other.conf".to_string(),
+                outcome: RestoreOutcome::UnknownApp {
+                    target_hint: "app:other".to_string(),
+                },
+            },
+        ];
+        append_in(storage_dir, &RestoreLogEntry::new("daily", &apps, Ok(&files)))?;
+        let error = anyhow!("boom");
+        append_in(storage_dir, &RestoreLogEntry::new("weekly", &apps, Err(&error)))?;
+
+        let log = read_in(storage_dir, 10)?;
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].backup, "weekly");
+        assert_eq!(log[0].failures, ["boom"]);
+        assert_eq!(log[1].files_restored, 1);
+        assert_eq!(log[1].failures.len(), 1);
+        assert_eq!(read_in(storage_dir, 1)?.len(), 1);
+
+        // A full log is rotated, and reading still reaches the old entries
+        let path = storage_dir.join(LOG_FILE);
+        let mut content = fs::read_to_string(&path)?;
+        content.push_str(&"x".repeat(MAX_LOG_BYTES as usize));
+        fs::write(&path, content)?;
+        append_in(storage_dir, &RestoreLogEntry::new("monthly", &apps, Ok(&[])))?;
+        assert!(storage_dir.join(ROTATED_LOG_FILE).is_file());
+        let backups: Vec<String> =
+            read_in(storage_dir, 10)?.into_iter().map(|entry| entry.backup).collect();
+        assert_eq!(backups, ["monthly", "weekly", "daily"]);
+        Ok(())
+    }
+}
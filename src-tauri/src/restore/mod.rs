@@ -0,0 +1,1101 @@
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use globset::{Glob, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::apps::App;
+use crate::storage::{entry::Entry, manifest::Manifest, paths::join_relative};
+
+pub mod atomic;
+pub mod journal;
+pub mod log;
+pub mod prompt;
+pub mod restore_point;
+
+use journal::RestoreJournal;
+
+/// Work out where `entry` should be written on this machine.
+///
+/// Entries carrying a `relative_path` are re-rooted under the app's
+/// `app_path()` for the *current* platform, so a backup made on Linux
+/// (`~/.config/Code/settings.json`) lands in `%APPDATA%\Code\settings.json`
+/// on Windows. Legacy entries without one fall back to their stored absolute
+/// path when the backup came from this OS, or to matching the file name
+/// against the app's current config files otherwise. The app root does not
+/// need to exist yet; missing directories are created by `restore_entry`.
+pub fn migrate_os_path(app: &dyn App, entry: &Entry, os_source: &str) -> Result<PathBuf> {
+    let current_os = tauri_plugin_os::platform();
+
+    let app_root = app.app_path().map_err(|e| {
+        anyhow!(
+            "'{}' is not available on {} (backup made on {}): {}",
+            app.name(),
+            current_os,
+            os_source,
+            e
+        )
+    })?;
+
+    if let Some(relative) = &entry.relative_path {
+        return join_relative(&app_root, relative)
+            .ok_or_else(|| anyhow!("Invalid relative path in backup entry: {}", relative));
+    }
+
+    if os_source == current_os {
+        return Ok(PathBuf::from(&entry.logical_path));
+    }
+
+    // A freshly installed app may not have created its config dir yet, in
+    // which case `config_path()` fails or finds nothing; fall back to the
+    // member name under the expected root instead of giving up
+    let member = entry.tar_member.clone().unwrap_or_default();
+    let existing = crate::apps::cached_config_path(app).unwrap_or_default();
+    if let Some(path) = existing.into_iter().find(|path| path.ends_with(&member)) {
+        return Ok(path);
+    }
+    join_relative(&app_root, &member).ok_or_else(|| {
+        anyhow!(
+            "Cannot map '{}' from {} onto {}",
+            entry.logical_path,
+            os_source,
+            current_os
+        )
+    })
+}
+
+/// Length of the home directory prefix of an absolute path from any OS:
+/// `/home/<user>`, `/Users/<user>`, `/root` or `<drive>:\Users\<user>`
+fn home_prefix_len(path: &str) -> Option<usize> {
+    let mut parts = path.split(['/', '\\']);
+    let root = parts.next()?;
+    let drive = root.len() == 2 && root.ends_with(':');
+    if !root.is_empty() && !drive {
+        return None;
+    }
+    let base = parts.next()?;
+    if root.is_empty() && base == "root" {
+        return Some("/root".len());
+    }
+    let user = parts.next().filter(|user| !user.is_empty())?;
+    let known_base = matches!(base, "home" | "Users");
+    known_base.then(|| root.len() + base.len() + user.len() + 2)
+}
+
+/// Move destinations under another account's home (`/home/alice/...` in a
+/// backup restored by bob) to the same place under `home`, for backups
+/// restored onto a differently named account. Returns the absolute
+/// destinations outside `home` that aren't in any home directory; they are
+/// left at their stored path.
+pub fn remap_home(targets: &mut [(&Entry, PathBuf)], home: &Path) -> Vec<String> {
+    let mut unmapped = Vec::new();
+    for (_, dest) in targets.iter_mut() {
+        if dest.starts_with(home) {
+            continue;
+        }
+        let text = dest.to_string_lossy().into_owned();
+        match home_prefix_len(&text).and_then(|len| join_relative(home, &text[len..])) {
+            Some(remapped) => *dest = remapped,
+            None if dest.is_absolute() => unmapped.push(text),
+            None => {}
+        }
+    }
+    unmapped
+}
+
+/// What to do when a restore destination already exists
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictStrategy {
+    /// Replace the existing file
+    Overwrite,
+    /// Leave the existing file untouched
+    Skip,
+    /// Keep the existing file and write the backup copy to `<dest>.restored`
+    KeepBoth,
+    /// Copy the existing file into the restore's `replaced` directory (see
+    /// `replaced_dir`), then replace it
+    #[default]
+    BackupThenOverwrite,
+}
+
+/// What happened to a single restored file
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub enum RestoreOutcome {
+    /// Nothing was there, the file was written
+    Created,
+    Overwritten,
+    Skipped,
+    KeptBoth { restored_to: String },
+    BackedUp { backup_path: String },
+    /// The existing file was modified since it was backed up and was left
+    /// alone (`skip_if_newer`)
+    KeptNewer,
+    /// No registered app owns this entry's `target_hint`, so there is no
+    /// destination for it
+    UnknownApp { target_hint: String },
+    /// The backup comes from another OS and this entry's app has no
+    /// location on this one
+    UnsupportedPlatform { app: String, reason: String },
+    /// `into_new_user` found no home directory in this absolute destination
+    /// to move under `home`, so the file went to its stored path. Reported
+    /// next to the file's own outcome.
+    NotRemapped { home: String },
+}
+
+/// A restore destination that already exists, as shown to a conflict
+/// resolver deciding what to do with it
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ConflictInfo {
+    /// The existing file
+    pub path: String,
+    pub logical_path: String,
+    pub target_hint: String,
+    pub existing_size: u64,
+    /// Last modification of the existing file, RFC 3339
+    pub existing_modified: Option<String>,
+    /// Size of the backed-up file, when the backup recorded it
+    pub backup_size: Option<u64>,
+}
+
+impl ConflictInfo {
+    fn new(entry: &Entry, dest: &Path) -> Self {
+        let metadata = std::fs::metadata(dest).ok();
+        Self {
+            path: dest.to_string_lossy().into_owned(),
+            logical_path: entry.logical_path.clone(),
+            target_hint: entry.target_hint.clone(),
+            existing_size: metadata.as_ref().map_or(0, |meta| meta.len()),
+            existing_modified: metadata
+                .and_then(|meta| meta.modified().ok())
+                .map(|modified| DateTime::<Utc>::from(modified).to_rfc3339()),
+            backup_size: entry.original_size,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RestoredFile {
+    pub path: String,
+    pub outcome: RestoreOutcome,
+}
+
+/// Sibling path with `suffix` appended: `settings.json` -> `settings.json.restored`
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Subdirectory of a backup's directory holding one directory per restore
+/// with copies of the files that restore replaced
+pub const REPLACED_DIR: &str = "replaced";
+
+/// A new directory under `<backup_dir>/replaced` for the copies one restore
+/// makes of the files it replaces, named after the current UTC time
+/// (numbered when a restore in the same second already has it). It is only
+/// created once a file is copied there.
+pub fn replaced_dir(backup_dir: &Path) -> PathBuf {
+    let base = backup_dir
+        .join(REPLACED_DIR)
+        .join(Utc::now().format("%Y%m%dT%H%M%SZ").to_string());
+    let mut candidate = base.clone();
+    let mut n = 1;
+    while candidate.symlink_metadata().is_ok() {
+        candidate = with_suffix(&base, &format!("-{}", n));
+        n += 1;
+    }
+    candidate
+}
+
+/// Where the copy of `dest` goes in `replaced_dir`: its whole path below the
+/// root, drive letters included, so files sharing a name don't collide
+fn replaced_copy_path(replaced_dir: &Path, dest: &Path) -> PathBuf {
+    let mut copy = replaced_dir.to_path_buf();
+    for component in dest.components() {
+        match component {
+            Component::Prefix(prefix) => {
+                copy.push(prefix.as_os_str().to_string_lossy().replace([':', '\\', '?'], ""))
+            }
+            Component::Normal(part) => copy.push(part),
+            Component::RootDir | Component::CurDir | Component::ParentDir => {}
+        }
+    }
+    copy
+}
+
+/// Copy `dest` to its place in `replaced_dir` before it is overwritten
+fn copy_replaced(dest: &Path, replaced_dir: &Path) -> Result<PathBuf> {
+    let copy = replaced_copy_path(replaced_dir, dest);
+    if let Some(parent) = copy.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::copy(dest, &copy)?;
+    Ok(copy)
+}
+
+/// Two restore destinations that only differ in letter case, e.g. `Config`
+/// and `config` from a Linux backup, which are the same file on macOS and
+/// Windows
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct CaseCollision {
+    pub path: String,
+    pub conflicts_with: String,
+    /// Where `path` is restored instead, when the collision was resolved
+    pub renamed_to: Option<String>,
+}
+
+/// Whether this platform's default filesystem ignores letter case
+pub fn case_insensitive_fs() -> bool {
+    cfg!(any(target_os = "macos", target_os = "windows"))
+}
+
+/// Find destinations that would land on the same file of a case-insensitive
+/// filesystem. With `KeepBoth` every later colliding destination is renamed
+/// to a `.restored` sibling (numbered if that collides too) and the
+/// collisions are returned for reporting; with any other strategy restoring
+/// would silently overwrite one file with another, so this fails listing
+/// them instead, before anything is written.
+pub fn resolve_case_collisions(
+    targets: &mut [(&Entry, PathBuf)],
+    strategy: ConflictStrategy,
+) -> Result<Vec<CaseCollision>> {
+    let fold = |path: &Path| path.to_string_lossy().to_lowercase();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut collisions = Vec::new();
+
+    for index in 0..targets.len() {
+        let dest = targets[index].1.clone();
+        let Some(&first) = seen.get(&fold(&dest)) else {
+            seen.insert(fold(&dest), index);
+            continue;
+        };
+        // The same entry listed twice is not a collision
+        if targets[first].1 == dest {
+            continue;
+        }
+
+        let mut collision = CaseCollision {
+            path: dest.to_string_lossy().into_owned(),
+            conflicts_with: targets[first].1.to_string_lossy().into_owned(),
+            renamed_to: None,
+        };
+        if strategy == ConflictStrategy::KeepBoth {
+            let mut renamed = with_suffix(&dest, ".restored");
+            let mut n = 2;
+            while seen.contains_key(&fold(&renamed)) {
+                renamed = with_suffix(&dest, &format!(".restored{}", n));
+                n += 1;
+            }
+            seen.insert(fold(&renamed), index);
+            collision.renamed_to = Some(renamed.to_string_lossy().into_owned());
+            targets[index].1 = renamed;
+        }
+        collisions.push(collision);
+    }
+
+    if strategy != ConflictStrategy::KeepBoth && !collisions.is_empty() {
+        let pairs: Vec<String> = collisions
+            .iter()
+            .map(|c| format!("'{}' and '{}'", c.conflicts_with, c.path))
+            .collect();
+        return Err(anyhow!(
+            "Backup has files that only differ in case, which this filesystem can't hold \
+             both of: {}. Restore with the keep-both strategy to rename them",
+            pairs.join(", ")
+        ));
+    }
+    Ok(collisions)
+}
+
+/// Keep only the targets whose entry matches one of the glob `patterns`;
+/// returns how many are left. Entries are matched on their path relative to
+/// the app's config root, or on the logical path without its leading `/`
+/// for entries that predate relative paths.
+pub fn retain_matching(targets: &mut Vec<(&Entry, PathBuf)>, patterns: &[String]) -> Result<usize> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern).map_err(|e| anyhow!("Invalid glob '{}': {}", pattern, e))?);
+    }
+    let only = builder.build()?;
+
+    targets.retain(|(entry, _)| {
+        let path = match &entry.relative_path {
+            Some(relative) => relative.clone(),
+            None => entry.logical_path.replace('\\', "/").trim_start_matches('/').to_string(),
+        };
+        only.is_match(path)
+    });
+    Ok(targets.len())
+}
+
+/// Take out of `targets` every destination file modified since it was
+/// backed up, so local edits made after the backup are not rolled back.
+/// Each file is compared with its entry's `original_mtime`; entries from
+/// older backups without one fall back to `created_at`, the backup's
+/// creation time, and are always restored when that is None too. Returns
+/// the kept files as `KeptNewer` outcomes.
+pub fn keep_newer(
+    targets: &mut Vec<(&Entry, PathBuf)>,
+    created_at: Option<DateTime<Utc>>,
+) -> Vec<RestoredFile> {
+    let mut kept = Vec::new();
+    targets.retain(|(entry, dest)| {
+        let backed_up_at = entry
+            .original_mtime
+            .and_then(|secs| DateTime::<Utc>::from_timestamp(secs, 0))
+            .or(created_at);
+        let modified = std::fs::metadata(dest)
+            .ok()
+            .filter(|metadata| metadata.is_file())
+            .and_then(|metadata| metadata.modified().ok())
+            // Whole seconds, like `original_mtime`
+            .map(|modified| DateTime::<Utc>::from(modified).timestamp());
+        match (modified, backed_up_at) {
+            (Some(modified), Some(backed_up_at)) if modified > backed_up_at.timestamp() => {
+                kept.push(RestoredFile {
+                    path: dest.to_string_lossy().into_owned(),
+                    outcome: RestoreOutcome::KeptNewer,
+                });
+                false
+            }
+            _ => true,
+        }
+    });
+    kept
+}
+
+/// Restore one entry to `dest`, applying `strategy` if something is already
+/// there. `BackupThenOverwrite` copies the existing file into `replaced_dir`.
+pub fn restore_entry(
+    manifest: &Manifest,
+    entry: &Entry,
+    dest: &Path,
+    strategy: ConflictStrategy,
+    replaced_dir: &Path,
+) -> Result<RestoreOutcome> {
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    if !dest.exists() {
+        manifest.restore_blob_to(entry, dest)?;
+        return Ok(RestoreOutcome::Created);
+    }
+
+    match strategy {
+        ConflictStrategy::Overwrite => {
+            manifest.restore_blob_to(entry, dest)?;
+            Ok(RestoreOutcome::Overwritten)
+        }
+        ConflictStrategy::Skip => Ok(RestoreOutcome::Skipped),
+        ConflictStrategy::KeepBoth => {
+            let restored_to = with_suffix(dest, ".restored");
+            manifest.restore_blob_to(entry, &restored_to)?;
+            Ok(RestoreOutcome::KeptBoth {
+                restored_to: restored_to.to_string_lossy().into_owned(),
+            })
+        }
+        ConflictStrategy::BackupThenOverwrite => {
+            let backup_path = copy_replaced(dest, replaced_dir).map_err(|e| {
+                anyhow!("Failed to back up {} before overwriting: {}", dest.display(), e)
+            })?;
+            manifest.restore_blob_to(entry, dest)?;
+            Ok(RestoreOutcome::BackedUp {
+                backup_path: backup_path.to_string_lossy().into_owned(),
+            })
+        }
+    }
+}
+
+/// Restore each `(entry, destination)` pair, skipping entries the journal
+/// already records as done. Progress is journaled after every file so a
+/// failed run can be resumed; the journal is cleared once all succeed. A
+/// journal left by a different restore, of other backup content, entries
+/// or destinations, is discarded rather than resumed.
+/// Returns what happened to each file handled by this run.
+///
+/// `on_conflict` picks the strategy for each destination that already
+/// exists. Conflicts are reported one at a time in `targets` order, each
+/// before its file is written and only for entries this run restores (not
+/// ones the journal already has). Files replaced with `BackupThenOverwrite`
+/// are copied into `replaced_dir`. Destinations that don't exist yet are
+/// written last, in parallel with `Manifest::restore_blobs_batch`.
+pub fn restore_entries(
+    manifest: &Manifest,
+    targets: &[(&Entry, PathBuf)],
+    journal: &mut RestoreJournal,
+    replaced_dir: &Path,
+    on_conflict: &mut dyn FnMut(&ConflictInfo) -> ConflictStrategy,
+) -> Result<Vec<RestoredFile>> {
+    journal.resume(&RestoreJournal::fingerprint(manifest, targets))?;
+    if journal.completed_count() > 0 {
+        println!(
+            "Resuming restore of '{}': {} entries already restored",
+            manifest.name,
+            journal.completed_count()
+        );
+    }
+
+    let mut restored: Vec<Option<RestoredFile>> = vec![None; targets.len()];
+    let mut fresh = Vec::new();
+    for (i, (entry, dest_path)) in targets.iter().enumerate() {
+        if journal.is_completed(entry) {
+            println!("Skipping already restored entry: {}", dest_path.display());
+            continue;
+        }
+        if !dest_path.exists() {
+            fresh.push(i);
+            continue;
+        }
+        let strategy = on_conflict(&ConflictInfo::new(entry, dest_path));
+        let outcome = restore_entry(manifest, entry, dest_path, strategy, replaced_dir)?;
+        journal.mark_completed(entry)?;
+        restored[i] = Some(RestoredFile {
+            path: dest_path.to_string_lossy().into_owned(),
+            outcome,
+        });
+    }
+
+    let batch = fresh.iter().map(|&i| (targets[i].0, targets[i].1.clone())).collect();
+    let journal_lock = Mutex::new(&mut *journal);
+    manifest.restore_blobs_batch(batch, |entry| {
+        journal_lock.lock().unwrap_or_else(|e| e.into_inner()).mark_completed(entry)
+    })?;
+    for i in fresh {
+        restored[i] = Some(RestoredFile {
+            path: targets[i].1.to_string_lossy().into_owned(),
+            outcome: RestoreOutcome::Created,
+        });
+    }
+
+    journal.clear()?;
+    Ok(restored.into_iter().flatten().collect())
+}
+
+/// Post-restore check of the files a restore wrote
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct RestoreSummary {
+    /// Files written by the restore; skipped ones are not counted
+    pub restored: usize,
+    /// Written files whose content hashes to their entry's `original_sha256`
+    pub verified: usize,
+    /// Written files that no longer match the backup, e.g. rewritten by a
+    /// running app or left unchanged by a permission problem
+    pub mismatched: Vec<String>,
+    /// Target hints of entries skipped because no registered app owns them
+    pub unknown_apps: Vec<String>,
+    /// Names of apps skipped because they don't exist on this OS
+    pub unsupported_apps: Vec<String>,
+    /// Destinations `into_new_user` left at their stored path
+    pub not_remapped: Vec<String>,
+}
+
+/// Re-read every file written for `targets` and compare it with the entry's
+/// `original_sha256`. Directory blobs and older backups record no hash:
+/// their files count as restored but neither verified nor mismatched.
+pub fn verify_restored(targets: &[(&Entry, PathBuf)], restored: &[RestoredFile]) -> RestoreSummary {
+    let expected: HashMap<PathBuf, Option<&str>> = targets
+        .iter()
+        .map(|(entry, dest)| (dest.clone(), entry.original_sha256.as_deref()))
+        .collect();
+
+    let mut summary = RestoreSummary::default();
+    for file in restored {
+        let written = match &file.outcome {
+            RestoreOutcome::Skipped | RestoreOutcome::KeptNewer => continue,
+            RestoreOutcome::UnknownApp { target_hint } => {
+                if !summary.unknown_apps.contains(target_hint) {
+                    summary.unknown_apps.push(target_hint.clone());
+                }
+                continue;
+            }
+            RestoreOutcome::UnsupportedPlatform { app, .. } => {
+                if !summary.unsupported_apps.contains(app) {
+                    summary.unsupported_apps.push(app.clone());
+                }
+                continue;
+            }
+            RestoreOutcome::NotRemapped { .. } => {
+                summary.not_remapped.push(file.path.clone());
+                continue;
+            }
+            RestoreOutcome::KeptBoth { restored_to } => restored_to.clone(),
+            _ => file.path.clone(),
+        };
+        summary.restored += 1;
+        let Some(Some(sha256)) = expected.get(Path::new(&file.path)) else {
+            continue;
+        };
+        match std::fs::read(&written) {
+            Ok(content) if hex::encode(Sha256::digest(&content)) == *sha256 => {
+                summary.verified += 1
+            }
+            _ => summary.mismatched.push(written),
+        }
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::blobs::BlobPayload;
+    use tempfile::TempDir;
+
+    pub(super) fn file_blob(name: &str, data: &[u8]) -> BlobPayload {
+        let mut tar_data = Vec::new();
+        {
+            let mut builder = tar::Builder::new(&mut tar_data);
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, data).unwrap();
+            builder.finish().unwrap();
+        }
+        BlobPayload::new("tar.zst".to_string(), &zstd::encode_all(&tar_data[..], 3).unwrap())
+    }
+
+    pub(super) fn entry_for(blob_id: &str, name: &str) -> Entry {
+        Entry {
+            blob_id: blob_id.to_string(),
+            target_hint: "app:test".to_string(),
+            logical_path: format!("/test/{}", name),
+            tar_member: Some(name.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_remap_home_moves_other_accounts_paths() {
+        let home = Path::new("/home/bob");
+        let entry = entry_for("blob", "settings.json");
+        let mut targets: Vec<(&Entry, PathBuf)> = [
+            "/home/alice/.config/zed/settings.json",
+            "/Users/alice/Library/app.plist",
+            "C:\\Users\\alice\\AppData\\Roaming\\Code\\settings.json",
+            "/root/.bashrc",
+            "/home/bob/.gitconfig",
+            "/etc/hosts",
+        ]
+        .iter()
+        .map(|path| (&entry, PathBuf::from(path)))
+        .collect();
+
+        let unmapped = remap_home(&mut targets, home);
+        let dests: Vec<PathBuf> = targets.into_iter().map(|(_, dest)| dest).collect();
+        assert_eq!(
+            dests,
+            [
+                home.join(".config/zed/settings.json"),
+                home.join("Library/app.plist"),
+                home.join("AppData/Roaming/Code/settings.json"),
+                home.join(".bashrc"),
+                home.join(".gitconfig"),
+                PathBuf::from("/etc/hosts"),
+            ]
+        );
+        assert_eq!(unmapped, ["/etc/hosts"]);
+        assert_eq!(home_prefix_len("/rootfs/x"), None);
+        assert_eq!(home_prefix_len("/home/"), None);
+    }
+
+    #[test]
+    fn test_interrupted_restore_write_keeps_original_file() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let replaced = temp_dir.path().join("replaced");
+        let dest = temp_dir.path().join("a.conf");
+        std::fs::write(&dest, b"original")?;
+
+        // The blob's TAR ends partway through the member, so the write
+        // stops after some of the new content has been copied out
+        let mut tar_data = Vec::new();
+        {
+            let mut builder = tar::Builder::new(&mut tar_data);
+            let data = vec![b'x'; 4096];
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, "a.conf", &data[..])?;
+            builder.finish()?;
+        }
+        tar_data.truncate(512 + 1000);
+        let mut manifest = Manifest::empty("interrupted-test".to_string());
+        let payload = zstd::encode_all(&tar_data[..], 3)?;
+        let blob = BlobPayload::new("tar.zst".to_string(), &payload);
+        manifest.add_blob_for_testing("blob1".to_string(), blob);
+
+        let entry = entry_for("blob1", "a.conf");
+        assert!(
+            restore_entry(&manifest, &entry, &dest, ConflictStrategy::Overwrite, &replaced)
+                .is_err()
+        );
+        assert_eq!(std::fs::read(&dest)?, b"original");
+        let leftovers = std::fs::read_dir(temp_dir.path())?.count();
+        assert_eq!(leftovers, 1, "no temp file should be left next to the config");
+        Ok(())
+    }
+
+    #[test]
+    fn test_directory_entry_restores_every_file() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let replaced = temp_dir.path().join("replaced");
+        let mut tar_data = Vec::new();
+        {
+            let mut builder = tar::Builder::new(&mut tar_data);
+            for (name, data) in [("a.toml", &b"a"[..]), ("dark/b.toml", &b"b"[..])] {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(data.len() as u64);
+                header.set_mode(0o644);
+                header.set_cksum();
+                builder.append_data(&mut header, name, data)?;
+            }
+            builder.finish()?;
+        }
+        let mut manifest = Manifest::empty("directory-restore-test".to_string());
+        let payload = zstd::encode_all(&tar_data[..], 3)?;
+        let blob = BlobPayload::new("tar.zst".to_string(), &payload);
+        manifest.add_blob_for_testing("dir".to_string(), blob);
+        let entry = Entry {
+            tar_member: None,
+            ..entry_for("dir", "themes")
+        };
+
+        let dest = temp_dir.path().join("themes");
+        std::fs::create_dir_all(&dest)?;
+        std::fs::write(dest.join("a.toml"), b"old")?;
+        restore_entry(&manifest, &entry, &dest, ConflictStrategy::Overwrite, &replaced)?;
+        assert_eq!(std::fs::read(dest.join("a.toml"))?, b"a");
+        assert_eq!(std::fs::read(dest.join("dark").join("b.toml"))?, b"b");
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_resumes_after_partial_failure() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let replaced = temp_dir.path().join("replaced");
+        let backup_dir = temp_dir.path().join("backup");
+        let dest_dir = temp_dir.path().join("dest");
+        std::fs::create_dir_all(&dest_dir)?;
+
+        let mut manifest = Manifest::empty("journal-test".to_string());
+        manifest.add_blob_for_testing("blob1".to_string(), file_blob("a.conf", b"a"));
+        manifest.add_blob_for_testing("blob2".to_string(), file_blob("b.conf", b"b"));
+        let entry_a = entry_for("blob1", "a.conf");
+        let entry_b = entry_for("blob2", "b.conf");
+
+        // Second destination's parent is a regular file, so the run dies there
+        let blocker = dest_dir.join("blocked");
+        std::fs::write(&blocker, b"not a directory")?;
+        let targets = vec![
+            (&entry_a, dest_dir.join("a.conf")),
+            (&entry_b, blocker.join("b.conf")),
+        ];
+
+        let mut journal = RestoreJournal::load(&backup_dir)?;
+        let strategy = ConflictStrategy::Overwrite;
+        assert!(
+            restore_entries(&manifest, &targets, &mut journal, &replaced, &mut |_| strategy)
+                .is_err()
+        );
+        assert_eq!(std::fs::read(dest_dir.join("a.conf"))?, b"a");
+
+        // The journal survives on disk and records the finished entry
+        let mut journal = RestoreJournal::load(&backup_dir)?;
+        assert!(journal.is_completed(&entry_a));
+        assert!(!journal.is_completed(&entry_b));
+
+        // Touch the already-restored file: a resumed run must not rewrite it
+        std::fs::write(dest_dir.join("a.conf"), b"edited")?;
+        std::fs::remove_file(&blocker)?;
+
+        let restored =
+            restore_entries(&manifest, &targets, &mut journal, &replaced, &mut |_| strategy)?;
+        assert_eq!(restored.len(), 1);
+        assert_eq!(std::fs::read(dest_dir.join("a.conf"))?, b"edited");
+        assert_eq!(std::fs::read(blocker.join("b.conf"))?, b"b");
+
+        // Full success clears the journal
+        assert!(!backup_dir.join("restore_journal.json").exists());
+        assert_eq!(RestoreJournal::load(&backup_dir)?.completed_count(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_journal_of_another_restore_is_discarded() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let replaced = temp_dir.path().join("replaced");
+        let backup_dir = temp_dir.path().join("backup");
+        let dest_dir = temp_dir.path().join("dest");
+        std::fs::create_dir_all(&dest_dir)?;
+
+        let mut manifest = Manifest::empty("journal-mismatch-test".to_string());
+        manifest.add_blob_for_testing("blob1".to_string(), file_blob("a.conf", b"a"));
+        manifest.add_blob_for_testing("blob2".to_string(), file_blob("b.conf", b"b"));
+        let entry_a = entry_for("blob1", "a.conf");
+        let entry_b = entry_for("blob2", "b.conf");
+        let blocker = dest_dir.join("blocked");
+        std::fs::write(&blocker, b"not a directory")?;
+        let strategy = ConflictStrategy::Overwrite;
+
+        // A failed restore of both files leaves a.conf journaled
+        let both = vec![
+            (&entry_a, dest_dir.join("a.conf")),
+            (&entry_b, blocker.join("b.conf")),
+        ];
+        let mut journal = RestoreJournal::load(&backup_dir)?;
+        assert!(
+            restore_entries(&manifest, &both, &mut journal, &replaced, &mut |_| strategy)
+                .is_err()
+        );
+        assert_eq!(RestoreJournal::load(&backup_dir)?.completed_count(), 1);
+
+        // Restoring only a.conf is another restore, so it is not skipped
+        std::fs::write(dest_dir.join("a.conf"), b"edited")?;
+        let only_a = vec![(&entry_a, dest_dir.join("a.conf"))];
+        let mut journal = RestoreJournal::load(&backup_dir)?;
+        let restored =
+            restore_entries(&manifest, &only_a, &mut journal, &replaced, &mut |_| strategy)?;
+        assert_eq!(restored.len(), 1);
+        assert_eq!(std::fs::read(dest_dir.join("a.conf"))?, b"a");
+
+        // Nor is a journal of the same files from before the backup changed
+        let mut journal = RestoreJournal::load(&backup_dir)?;
+        assert!(
+            restore_entries(&manifest, &both, &mut journal, &replaced, &mut |_| strategy)
+                .is_err()
+        );
+        std::fs::remove_file(&blocker)?;
+        std::fs::write(dest_dir.join("a.conf"), b"edited")?;
+        manifest.add_blob_for_testing("blob3".to_string(), file_blob("c.conf", b"c"));
+        let mut journal = RestoreJournal::load(&backup_dir)?;
+        let restored =
+            restore_entries(&manifest, &both, &mut journal, &replaced, &mut |_| strategy)?;
+        assert_eq!(restored.len(), 2);
+        assert_eq!(std::fs::read(dest_dir.join("a.conf"))?, b"a");
+        Ok(())
+    }
+
+    /// App whose config dir does not exist yet, like one the installer just set up
+    struct FreshApp {
+        root: PathBuf,
+    }
+
+    impl App for FreshApp {
+        fn id(&self) -> &'static str {
+            "fresh-test"
+        }
+        fn name(&self) -> &'static str {
+            "Fresh"
+        }
+        fn is_installed(&self) -> bool {
+            true
+        }
+        fn config_path(&self) -> Result<Vec<PathBuf>> {
+            let mut files = Vec::new();
+            crate::apps::collect_files_recursive(&self.root, &[], &mut files)?;
+            Ok(files)
+        }
+        fn app_path(&self) -> Result<PathBuf> {
+            Ok(self.root.clone())
+        }
+        fn target_hint(&self) -> &'static str {
+            "app:test"
+        }
+        fn package_id(&self) -> Option<&'static str> {
+            None
+        }
+    }
+
+    #[test]
+    fn test_restore_onto_missing_config_dir() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let replaced = temp_dir.path().join("replaced");
+        let app = FreshApp {
+            root: temp_dir.path().join("config").join("fresh"),
+        };
+        assert!(app.config_path().is_err());
+
+        let mut manifest = Manifest::empty("fresh-test".to_string());
+        manifest.add_blob_for_testing("blob1".to_string(), file_blob("settings.json", b"{}"));
+        manifest.add_blob_for_testing("blob2".to_string(), file_blob("keys.conf", b"k"));
+        let mut nested = entry_for("blob1", "settings.json");
+        nested.relative_path = Some("profiles/default/settings.json".to_string());
+        // Legacy entry from another OS, mapped by member name
+        let legacy = entry_for("blob2", "keys.conf");
+
+        let targets = vec![
+            (&nested, migrate_os_path(&app, &nested, "other-os")?),
+            (&legacy, migrate_os_path(&app, &legacy, "other-os")?),
+        ];
+        let mut journal = RestoreJournal::load(&temp_dir.path().join("backup"))?;
+        let restored = restore_entries(&manifest, &targets, &mut journal, &replaced, &mut |_| {
+            Default::default()
+        })?;
+
+        assert!(restored.iter().all(|f| f.outcome == RestoreOutcome::Created));
+        let settings = app.root.join("profiles").join("default").join("settings.json");
+        assert_eq!(std::fs::read(settings)?, b"{}");
+        assert_eq!(std::fs::read(app.root.join("keys.conf"))?, b"k");
+        Ok(())
+    }
+
+    #[test]
+    fn test_case_collisions_are_reported() -> Result<()> {
+        let upper = entry_for("blob1", "Config");
+        let lower = entry_for("blob2", "config");
+        let other = entry_for("blob3", "other");
+        let dir = PathBuf::from("/home/me/.config/app");
+        let targets = || {
+            vec![
+                (&upper, dir.join("Config")),
+                (&lower, dir.join("config")),
+                (&other, dir.join("other")),
+            ]
+        };
+
+        let err = resolve_case_collisions(&mut targets(), ConflictStrategy::Overwrite).unwrap_err();
+        assert!(err.to_string().contains("only differ in case"));
+
+        let mut renamed = targets();
+        let collisions = resolve_case_collisions(&mut renamed, ConflictStrategy::KeepBoth)?;
+        assert_eq!(collisions.len(), 1);
+        assert_eq!(collisions[0].path, dir.join("config").to_string_lossy());
+        assert_eq!(renamed[0].1, dir.join("Config"));
+        assert_eq!(renamed[1].1, dir.join("config.restored"));
+        assert_eq!(
+            collisions[0].renamed_to.as_deref(),
+            Some(&*dir.join("config.restored").to_string_lossy())
+        );
+        assert_eq!(renamed[2].1, dir.join("other"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_conflict_strategies() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let replaced = temp_dir.path().join("replaced");
+        let mut manifest = Manifest::empty("conflict-test".to_string());
+        manifest.add_blob_for_testing("blob1".to_string(), file_blob("a.conf", b"backup"));
+        let entry = entry_for("blob1", "a.conf");
+
+        let run = |strategy| -> Result<(RestoreOutcome, PathBuf)> {
+            let dest = temp_dir.path().join(format!("{:?}", strategy)).join("a.conf");
+            std::fs::create_dir_all(dest.parent().unwrap())?;
+            std::fs::write(&dest, b"local")?;
+            Ok((restore_entry(&manifest, &entry, &dest, strategy, &replaced)?, dest))
+        };
+
+        let (outcome, dest) = run(ConflictStrategy::Overwrite)?;
+        assert_eq!(outcome, RestoreOutcome::Overwritten);
+        assert_eq!(std::fs::read(&dest)?, b"backup");
+
+        let (outcome, dest) = run(ConflictStrategy::Skip)?;
+        assert_eq!(outcome, RestoreOutcome::Skipped);
+        assert_eq!(std::fs::read(&dest)?, b"local");
+
+        let (outcome, dest) = run(ConflictStrategy::KeepBoth)?;
+        assert!(matches!(outcome, RestoreOutcome::KeptBoth { .. }));
+        assert_eq!(std::fs::read(&dest)?, b"local");
+        assert_eq!(std::fs::read(with_suffix(&dest, ".restored"))?, b"backup");
+
+        // The copy goes to the restore's directory, nothing is left beside
+        // the config
+        let (outcome, dest) = run(ConflictStrategy::BackupThenOverwrite)?;
+        let copy = replaced_copy_path(&replaced, &dest);
+        assert_eq!(
+            outcome,
+            RestoreOutcome::BackedUp { backup_path: copy.to_string_lossy().into_owned() }
+        );
+        assert!(copy.starts_with(&replaced));
+        assert_eq!(std::fs::read(&dest)?, b"backup");
+        assert_eq!(std::fs::read(&copy)?, b"local");
+        assert_eq!(std::fs::read_dir(dest.parent().unwrap())?.count(), 1);
+
+        // No conflict, no strategy
+        let fresh = temp_dir.path().join("fresh").join("a.conf");
+        assert_eq!(
+            restore_entry(&manifest, &entry, &fresh, ConflictStrategy::Skip, &replaced)?,
+            RestoreOutcome::Created
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_entries_asks_per_conflict_in_order() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let replaced = temp_dir.path().join("replaced");
+        let mut manifest = Manifest::empty("ask-test".to_string());
+        manifest.add_blob_for_testing("blob1".to_string(), file_blob("a.conf", b"alpha"));
+        manifest.add_blob_for_testing("blob2".to_string(), file_blob("b.conf", b"beta"));
+        manifest.add_blob_for_testing("blob3".to_string(), file_blob("c.conf", b"gamma"));
+        let (a, b, c) = (
+            entry_for("blob1", "a.conf"),
+            entry_for("blob2", "b.conf"),
+            entry_for("blob3", "c.conf"),
+        );
+        let targets = vec![
+            (&a, temp_dir.path().join("a.conf")),
+            (&b, temp_dir.path().join("b.conf")),
+            (&c, temp_dir.path().join("c.conf")),
+        ];
+        std::fs::write(temp_dir.path().join("a.conf"), b"local a")?;
+        std::fs::write(temp_dir.path().join("c.conf"), b"local c")?;
+
+        let mut asked = Vec::new();
+        let mut journal = RestoreJournal::load(temp_dir.path())?;
+        let mut ask = |conflict: &ConflictInfo| {
+            asked.push((conflict.logical_path.clone(), conflict.existing_size));
+            if conflict.logical_path.ends_with("a.conf") {
+                ConflictStrategy::Skip
+            } else {
+                ConflictStrategy::Overwrite
+            }
+        };
+        let restored = restore_entries(&manifest, &targets, &mut journal, &replaced, &mut ask)?;
+
+        assert_eq!(asked, [("/test/a.conf".to_string(), 7), ("/test/c.conf".to_string(), 7)]);
+        let outcomes: Vec<_> = restored.into_iter().map(|file| file.outcome).collect();
+        assert_eq!(
+            outcomes,
+            [RestoreOutcome::Skipped, RestoreOutcome::Created, RestoreOutcome::Overwritten]
+        );
+        assert_eq!(std::fs::read(temp_dir.path().join("a.conf"))?, b"local a");
+        assert_eq!(std::fs::read(temp_dir.path().join("c.conf"))?, b"gamma");
+        Ok(())
+    }
+
+    #[test]
+    fn test_retain_matching() -> Result<()> {
+        let keymap = Entry {
+            relative_path: Some("keymap.json".to_string()),
+            ..entry_for("blob1", "keymap.json")
+        };
+        let nested = Entry {
+            relative_path: Some("themes/keymap.json".to_string()),
+            ..entry_for("blob2", "keymap.json")
+        };
+        let settings = entry_for("blob3", "settings.json");
+        let all = vec![
+            (&keymap, PathBuf::from("/live/keymap.json")),
+            (&nested, PathBuf::from("/live/themes/keymap.json")),
+            (&settings, PathBuf::from("/live/settings.json")),
+        ];
+
+        let mut targets = all.clone();
+        assert_eq!(retain_matching(&mut targets, &["**/keymap.json".to_string()])?, 2);
+        assert_eq!(targets[1].0.blob_id, "blob2");
+
+        // Without a relative path the logical path is matched
+        let mut targets = all.clone();
+        assert_eq!(retain_matching(&mut targets, &["test/*.json".to_string()])?, 1);
+        assert_eq!(targets[0].0.blob_id, "blob3");
+
+        let mut targets = all;
+        assert_eq!(retain_matching(&mut targets, &["*.toml".to_string()])?, 0);
+        assert!(retain_matching(&mut targets, &["[".to_string()]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_restored() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let replaced = temp_dir.path().join("replaced");
+        let mut manifest = Manifest::empty("verify-restore-test".to_string());
+        manifest.add_blob_for_testing("blob1".to_string(), file_blob("a.conf", b"alpha"));
+        manifest.add_blob_for_testing("blob2".to_string(), file_blob("b.conf", b"beta"));
+        manifest.add_blob_for_testing("blob3".to_string(), file_blob("c.conf", b"gamma"));
+        let sha256 = |data: &[u8]| Some(hex::encode(Sha256::digest(data)));
+        let entry_a = Entry {
+            original_sha256: sha256(b"alpha"),
+            ..entry_for("blob1", "a.conf")
+        };
+        let entry_b = Entry {
+            original_sha256: sha256(b"beta"),
+            ..entry_for("blob2", "b.conf")
+        };
+        let legacy = entry_for("blob3", "c.conf");
+        let targets = vec![
+            (&entry_a, temp_dir.path().join("a.conf")),
+            (&entry_b, temp_dir.path().join("b.conf")),
+            (&legacy, temp_dir.path().join("c.conf")),
+        ];
+        std::fs::write(temp_dir.path().join("b.conf"), b"local")?;
+
+        let mut journal = RestoreJournal::load(temp_dir.path())?;
+        let keep_both = &mut |_: &ConflictInfo| ConflictStrategy::KeepBoth;
+        let restored = restore_entries(&manifest, &targets, &mut journal, &replaced, keep_both)?;
+        let summary = verify_restored(&targets, &restored);
+        assert_eq!((summary.restored, summary.verified), (3, 2));
+        assert!(summary.mismatched.is_empty());
+
+        let not_remapped = RestoredFile {
+            path: targets[2].1.to_string_lossy().into_owned(),
+            outcome: RestoreOutcome::NotRemapped { home: "/home/me".to_string() },
+        };
+        let summary = verify_restored(&targets, &[restored.clone(), vec![not_remapped]].concat());
+        assert_eq!((summary.restored, summary.verified), (3, 2));
+        assert_eq!(summary.not_remapped, vec![targets[2].1.to_string_lossy().into_owned()]);
+
+        // Something rewrote the file right after the restore
+        std::fs::write(temp_dir.path().join("a.conf"), b"changed")?;
+        let summary = verify_restored(&targets, &restored);
+        assert_eq!(summary.verified, 1);
+        assert_eq!(summary.mismatched, vec![targets[0].1.to_string_lossy().into_owned()]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_keep_newer_skips_files_edited_after_backup() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let created_at = DateTime::parse_from_rfc3339("2024-05-01T12:00:00Z")?.with_timezone(&Utc);
+        let set_mtime = |path: &Path, time: DateTime<Utc>| -> Result<()> {
+            std::fs::write(path, b"local")?;
+            let file = std::fs::File::options().write(true).open(path)?;
+            file.set_modified(time.into())?;
+            Ok(())
+        };
+        let hours = chrono::Duration::hours;
+        // Saved an hour before the backup was created, edited since
+        let edited = temp_dir.path().join("edited.conf");
+        set_mtime(&edited, created_at - hours(1))?;
+        let mut entry_edited = entry_for("blob1", "edited.conf");
+        entry_edited.original_mtime = Some((created_at - hours(2)).timestamp());
+        // Untouched since it was saved
+        let same = temp_dir.path().join("same.conf");
+        set_mtime(&same, created_at - hours(2))?;
+        let mut entry_same = entry_for("blob2", "same.conf");
+        entry_same.original_mtime = Some((created_at - hours(2)).timestamp());
+        // An older backup's entry, compared with the backup's creation time
+        let legacy = temp_dir.path().join("legacy.conf");
+        set_mtime(&legacy, created_at + hours(1))?;
+        let entry_legacy = entry_for("blob3", "legacy.conf");
+        let entry_missing = entry_for("blob4", "missing.conf");
+        let mut targets = vec![
+            (&entry_edited, edited.clone()),
+            (&entry_same, same.clone()),
+            (&entry_legacy, legacy.clone()),
+            (&entry_missing, temp_dir.path().join("missing.conf")),
+        ];
+
+        let kept = keep_newer(&mut targets.clone(), None);
+        assert_eq!(kept.len(), 1, "without a creation time only mtimes count");
+
+        let kept = keep_newer(&mut targets, Some(created_at));
+        let kept_paths: Vec<_> = kept.iter().map(|file| file.path.clone()).collect();
+        assert_eq!(
+            kept_paths,
+            [edited.to_string_lossy(), legacy.to_string_lossy()]
+        );
+        assert!(kept.iter().all(|file| file.outcome == RestoreOutcome::KeptNewer));
+        let left: Vec<_> = targets.iter().map(|(entry, _)| entry.blob_id.as_str()).collect();
+        assert_eq!(left, ["blob2", "blob4"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+use super::{ConflictInfo, ConflictStrategy};
+
+/// How long a restore waits for an answer before leaving the file alone
+const ANSWER_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// Conflicts sent to the frontend that a restore is waiting on, by id
+static PENDING: Lazy<Mutex<HashMap<u64, Sender<ConflictStrategy>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// A conflict sent to the frontend; `id` goes back with the decision
+#[derive(Serialize, Debug, Clone)]
+pub struct ConflictPrompt {
+    pub id: u64,
+    #[serde(flatten)]
+    pub conflict: ConflictInfo,
+}
+
+/// Register a prompt for `conflict`, returning it and where its answer
+/// arrives
+fn open(conflict: &ConflictInfo) -> (ConflictPrompt, Receiver<ConflictStrategy>) {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let (sender, receiver) = mpsc::channel();
+    PENDING.lock().unwrap_or_else(|e| e.into_inner()).insert(id, sender);
+    let prompt = ConflictPrompt {
+        id,
+        conflict: conflict.clone(),
+    };
+    (prompt, receiver)
+}
+
+/// Send `conflict` out with `send` and block until `answer` is called for
+/// it. A prompt nobody answers within `ANSWER_TIMEOUT` is skipped, so
+/// an abandoned restore never overwrites anything.
+pub fn ask(conflict: &ConflictInfo, send: impl FnOnce(&ConflictPrompt)) -> ConflictStrategy {
+    let (prompt, receiver) = open(conflict);
+    send(&prompt);
+    let strategy = receiver.recv_timeout(ANSWER_TIMEOUT).unwrap_or_else(|_| {
+        println!("No answer for the conflict on {}; skipping it", conflict.path);
+        ConflictStrategy::Skip
+    });
+    PENDING.lock().unwrap_or_else(|e| e.into_inner()).remove(&prompt.id);
+    strategy
+}
+
+/// Answer prompt `id`; false when no restore is waiting on it
+pub fn answer(id: u64, strategy: ConflictStrategy) -> bool {
+    let sender = PENDING.lock().unwrap_or_else(|e| e.into_inner()).remove(&id);
+    sender.is_some_and(|sender| sender.send(strategy).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ask_waits_for_answer() {
+        let conflict = ConflictInfo {
+            path: "/home/me/.config/zed/settings.json".to_string(),
+            logical_path: "/home/me/.config/zed/settings.json".to_string(),
+            target_hint: "app:zed".to_string(),
+            existing_size: 2,
+            existing_modified: None,
+            backup_size: Some(3),
+        };
+        let strategy = ask(&conflict, |prompt| {
+            let id = prompt.id;
+            std::thread::spawn(move || assert!(answer(id, ConflictStrategy::KeepBoth)));
+        });
+        assert_eq!(strategy, ConflictStrategy::KeepBoth);
+        assert!(!answer(u64::MAX, ConflictStrategy::Skip));
+    }
+}
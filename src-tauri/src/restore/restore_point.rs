@@ -0,0 +1,70 @@
+use std::process::Command;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+
+use crate::apps::App;
+
+/// Description of the restore points SaveMe creates, as shown in System Restore
+const RESTORE_POINT_DESCRIPTION: &str = "SaveMe config restore";
+
+/// Whether restoring `app` touches system-wide configuration rather than
+/// the user's own, going by its `sys:` target hint
+pub fn is_system_app(app: &dyn App) -> bool {
+    app.target_hint().starts_with("sys:")
+}
+
+/// Whether a restore of `apps` should be preceded by a restore point
+fn wants_restore_point(apps: &[Arc<dyn App>], enabled: bool) -> bool {
+    enabled && apps.iter().any(|app| is_system_app(app.as_ref()))
+}
+
+/// Create a Windows System Restore point before restoring `apps` when the
+/// `create_restore_point` setting is on and one of them is system-level.
+/// A failure, usually missing administrator rights or System Protection
+/// being off, is only a warning: the restore goes ahead.
+pub fn create_before_restore(apps: &[Arc<dyn App>]) {
+    if !cfg!(target_os = "windows")
+        || !wants_restore_point(apps, crate::settings::current().create_restore_point)
+    {
+        return;
+    }
+    match checkpoint_computer(RESTORE_POINT_DESCRIPTION) {
+        Ok(()) => println!("Created System Restore point '{}'", RESTORE_POINT_DESCRIPTION),
+        Err(e) => println!("Warning: could not create a System Restore point: {}", e),
+    }
+}
+
+/// `Checkpoint-Computer` through PowerShell
+fn checkpoint_computer(description: &str) -> Result<()> {
+    let output = Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command"])
+        .arg(format!(
+            "Checkpoint-Computer -Description '{}' -RestorePointType MODIFY_SETTINGS -ErrorAction Stop",
+            description
+        ))
+        .output()
+        .map_err(|e| anyhow!("Failed to run PowerShell: {}", e))?;
+    if !output.status.success() {
+        return Err(anyhow!("{}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apps::get_app;
+
+    #[test]
+    fn test_restore_point_only_for_system_apps() {
+        let zed = get_app("zed").unwrap();
+        let network = get_app("networkmanager").unwrap();
+        assert!(is_system_app(network.as_ref()));
+        assert!(!is_system_app(zed.as_ref()));
+
+        assert!(!wants_restore_point(&[Arc::clone(&zed)], true));
+        assert!(wants_restore_point(&[Arc::clone(&zed), Arc::clone(&network)], true));
+        assert!(!wants_restore_point(&[network], false));
+    }
+}
@@ -0,0 +1,215 @@
+use std::{path::PathBuf, sync::RwLock};
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::storage::{atomic::write_atomic, manifest::Manifest};
+
+/// Names the collectors always skip: VCS metadata and OS cruft that bloats
+/// backups and confuses restores
+pub const DEFAULT_IGNORED_NAMES: &[&str] = &[".git", ".DS_Store", "Thumbs.db"];
+
+/// Where blob deduplication looks for existing content when saving
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DedupScope {
+    /// Reuse matching blobs from any backup. Smallest storage, but every
+    /// save loads every manifest, and a blob can't be freed by deleting the
+    /// backup that first stored it while a newer one still references it.
+    #[default]
+    Global,
+    /// Only reuse blobs already in the backup being saved. Each backup stays
+    /// self-contained and saves are faster; shared files cost extra space.
+    WithinBackup,
+    /// Never look for matching blobs. Fastest and fully independent, at the
+    /// cost of the most disk; identical files within one backup still share
+    /// the blob their content hash names.
+    None,
+}
+
+/// How file blobs pick their compression codec
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CodecSelection {
+    /// Always zstd
+    #[default]
+    Zstd,
+    /// Trial-compress a sample of each large blob with zstd, xz and lz4 and
+    /// keep the smallest. Slower saves; meant for cold/archival backups.
+    BestRatio,
+    /// Like `BestRatio`, but weighs the ratio against compression time so a
+    /// marginally smaller but much slower codec loses
+    BestRatioPerSecond,
+}
+
+/// Which `PerformanceConfig` preset saves compress with
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PerformanceProfile {
+    /// Pick one from the estimated size of the backup (see
+    /// `PerformanceConfig::resolve_profile`)
+    #[default]
+    Auto,
+    Fast,
+    Balanced,
+    MaxCompression,
+}
+
+/// User-facing settings persisted as `settings.json` in the storage directory
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Settings {
+    /// Collect dotfiles and dot-directories found inside config directories.
+    /// On by default since many configs are dotfiles themselves.
+    pub include_hidden: bool,
+    /// Extra file/directory names to skip, on top of `DEFAULT_IGNORED_NAMES`
+    pub ignored_names: Vec<String>,
+    /// Back up user-installed font files, not just fontconfig rules. Off by
+    /// default because font collections can be hundreds of MB.
+    pub include_font_files: bool,
+    /// Back up Windows Terminal's `state.json` (open windows and tabs) along
+    /// with its settings. Off by default: it changes on every launch, so
+    /// each backup would store a new copy.
+    pub include_terminal_state: bool,
+    /// Store a directory as a single TAR blob instead of one blob per file.
+    /// Off by default: theme and snippet directories hold many files that
+    /// rarely change, and per-file blobs let those dedup between backups.
+    pub directory_as_single_blob: bool,
+    /// Encrypt blob payloads at rest with a key derived from the user's
+    /// passphrase. Required before apps that hold credentials (chat
+    /// clients, ...) can be included in a backup.
+    pub encrypt_blobs: bool,
+    /// Explicit opt-in for apps whose files store credentials in plain text
+    /// (Wi-Fi PSKs, VPN secrets). Blob encryption must be on as well.
+    pub include_credentials: bool,
+    pub dedup_scope: DedupScope,
+    /// Buffer new blob files during a save and append them to the backup's
+    /// pack in one write, instead of one synced file per blob. Much faster on
+    /// spinning disks and network mounts.
+    pub batch_blob_writes: bool,
+    /// Largest resource file (brush, palette, plugin data) collected from
+    /// apps with big resource directories, in MB
+    pub resource_size_limit_mb: u64,
+    /// When set, every successful save deletes backups created more than
+    /// this many days ago, keeping a rolling window of backups
+    pub max_backup_age_days: Option<u32>,
+    pub codec_selection: CodecSelection,
+    pub performance_profile: PerformanceProfile,
+    /// Write blobs to the storage-wide pool (`blobs/` in the storage
+    /// directory) instead of each backup's own directory, so a file shared
+    /// by several backups is stored once whatever the dedup scope
+    pub pooled_blobs: bool,
+    /// Directory backups are pushed to and pulled from: a mounted network
+    /// share or a synced folder. No remote when unset.
+    pub remote_dir: Option<PathBuf>,
+    /// Longest a single remote operation may take before it is retried
+    pub remote_timeout_secs: u64,
+    /// Attempts per remote operation, including the first
+    pub remote_max_attempts: u32,
+    /// Windows only: create a System Restore point before restoring
+    /// system-level apps. Needs administrator rights; without them the
+    /// restore goes ahead with a warning.
+    pub create_restore_point: bool,
+    /// When set, every backup is verified again in the background once its
+    /// last verification is this many hours old, catching blob files that
+    /// rot on disk. Off by default.
+    pub scrub_interval_hours: Option<u32>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            include_hidden: true,
+            ignored_names: Vec::new(),
+            include_font_files: false,
+            include_terminal_state: false,
+            directory_as_single_blob: false,
+            encrypt_blobs: false,
+            include_credentials: false,
+            dedup_scope: DedupScope::Global,
+            batch_blob_writes: false,
+            resource_size_limit_mb: 5,
+            max_backup_age_days: None,
+            codec_selection: CodecSelection::Zstd,
+            performance_profile: PerformanceProfile::Auto,
+            pooled_blobs: false,
+            remote_dir: None,
+            remote_timeout_secs: 60,
+            remote_max_attempts: 4,
+            create_restore_point: false,
+            scrub_interval_hours: None,
+        }
+    }
+}
+
+/// Global settings instance, loaded from disk on first access
+pub static SETTINGS: Lazy<RwLock<Settings>> = Lazy::new(|| RwLock::new(Settings::load()));
+
+impl Settings {
+    fn settings_path() -> Result<PathBuf> {
+        Ok(Manifest::base_storage_dir()?.join("settings.json"))
+    }
+
+    /// Load settings from disk, falling back to defaults if missing or invalid
+    pub fn load() -> Self {
+        Self::settings_path()
+            .and_then(|path| Ok(std::fs::read_to_string(path)?))
+            .and_then(|content| Ok(serde_json::from_str(&content)?))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::settings_path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        write_atomic(&path, serde_json::to_string_pretty(self)?.as_bytes())
+    }
+
+    /// Whether a file or directory with this name should be skipped by collectors
+    pub fn is_ignored_name(&self, name: &str) -> bool {
+        if !self.include_hidden && name.starts_with('.') {
+            return true;
+        }
+        DEFAULT_IGNORED_NAMES.contains(&name) || self.ignored_names.iter().any(|n| n == name)
+    }
+}
+
+/// Snapshot of the current settings
+pub fn current() -> Settings {
+    SETTINGS.read().map(|s| s.clone()).unwrap_or_default()
+}
+
+/// Persist new settings and make them the current ones
+pub fn update(settings: Settings) -> Result<()> {
+    settings.save()?;
+    if let Ok(mut current) = SETTINGS.write() {
+        *current = settings;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_ignore_list() {
+        let settings = Settings::default();
+        assert!(settings.is_ignored_name(".git"));
+        assert!(settings.is_ignored_name(".DS_Store"));
+        assert!(settings.is_ignored_name("Thumbs.db"));
+        assert!(!settings.is_ignored_name(".zshrc"));
+        assert!(!settings.is_ignored_name("settings.json"));
+    }
+
+    #[test]
+    fn test_hidden_and_extra_names() {
+        let settings = Settings {
+            include_hidden: false,
+            ignored_names: vec!["node_modules".to_string()],
+            ..Default::default()
+        };
+        assert!(settings.is_ignored_name(".zshrc"));
+        assert!(settings.is_ignored_name("node_modules"));
+        assert!(!settings.is_ignored_name("keymap.json"));
+    }
+}
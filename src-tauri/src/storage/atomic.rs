@@ -0,0 +1,149 @@
+use std::{
+    ffi::OsString,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use anyhow::{Context, Result};
+
+/// Sibling temp path used while writing: `manifest.json` -> `manifest.json.tmp`
+fn temp_path_for(path: &Path) -> PathBuf {
+    let mut name: OsString = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+/// Sibling temp path no other writer uses: `bundle` -> `bundle.<pid>-<n>.tmp`
+fn unique_temp_path_for(path: &Path) -> PathBuf {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    let mut name: OsString = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(
+        ".{}-{}.tmp",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+    path.with_file_name(name)
+}
+
+/// Write `bytes` to `path` without ever leaving a truncated file behind.
+///
+/// The data is written and synced to `<path>.tmp` first and then renamed over
+/// `path`. The rename is atomic on the same filesystem, so readers see either
+/// the previous contents or the new ones, never a partial write.
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> Result<()> {
+    write_atomic_with(path, |file| Ok(file.write_all(bytes)?))
+}
+
+/// `write_atomic` for content produced by `write`, e.g. streamed out of a
+/// TAR. `write` may also set the temp file's permissions. If it fails, the
+/// temp file is removed and `path` keeps its previous contents.
+pub fn write_atomic_with(
+    path: &Path,
+    write: impl FnOnce(&mut fs::File) -> Result<()>,
+) -> Result<()> {
+    write_through(&temp_path_for(path), path, write)
+}
+
+/// `write_atomic_with` through a temp file of its own, for writers that may
+/// overlap with another write of the same `path` (e.g. a timed-out remote
+/// upload still running while it is retried). Each complete write replaces
+/// `path` whole; none can truncate the temp file of another.
+pub fn write_atomic_unique_with(
+    path: &Path,
+    write: impl FnOnce(&mut fs::File) -> Result<()>,
+) -> Result<()> {
+    write_through(&unique_temp_path_for(path), path, write)
+}
+
+fn write_through(
+    tmp: &Path,
+    path: &Path,
+    write: impl FnOnce(&mut fs::File) -> Result<()>,
+) -> Result<()> {
+    let result = (|| -> Result<()> {
+        let mut file = fs::File::create(tmp)
+            .with_context(|| format!("Failed to create temp file: {}", tmp.display()))?;
+        write(&mut file)?;
+        file.sync_all()?;
+        fs::rename(tmp, path)
+            .with_context(|| format!("Failed to move temp file into place: {}", path.display()))?;
+        Ok(())
+    })();
+
+    if result.is_err() && tmp.is_file() {
+        let _ = fs::remove_file(tmp);
+    }
+    result
+}
+
+/// Copy `from` over `to` through a temp file next to `to`, so `to` is never
+/// left half-copied
+pub fn copy_atomic(from: &Path, to: &Path) -> Result<()> {
+    let mut source = fs::File::open(from)
+        .with_context(|| format!("Failed to open {}", from.display()))?;
+    let permissions = source.metadata()?.permissions();
+    write_atomic_with(to, |file| {
+        std::io::copy(&mut source, file)?;
+        Ok(file.set_permissions(permissions)?)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_write_atomic_replaces_contents() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("manifest.json");
+
+        write_atomic(&path, b"first")?;
+        write_atomic(&path, b"second")?;
+
+        assert_eq!(fs::read(&path)?, b"second");
+        assert!(!temp_path_for(&path).exists(), "Temp file should not be left behind");
+        Ok(())
+    }
+
+    #[test]
+    fn test_failed_write_keeps_previous_manifest() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("manifest.json");
+        write_atomic(&path, b"{\"name\":\"intact\"}")?;
+
+        // Occupy the temp path with a directory so creating the temp file fails
+        fs::create_dir(temp_path_for(&path))?;
+
+        assert!(write_atomic(&path, b"{\"name\":\"trunc").is_err());
+        assert_eq!(fs::read(&path)?, b"{\"name\":\"intact\"}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_interrupted_streamed_write_keeps_previous_file() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("settings.json");
+        write_atomic(&path, b"{\"theme\":\"dark\"}")?;
+
+        let result = write_atomic_with(&path, |file| {
+            file.write_all(b"{\"theme\":")?;
+            Err(anyhow::anyhow!("interrupted"))
+        });
+        assert!(result.is_err());
+        assert_eq!(fs::read(&path)?, b"{\"theme\":\"dark\"}");
+        assert!(!temp_path_for(&path).exists());
+
+        // Overlapping writers each get their own temp file
+        assert_ne!(unique_temp_path_for(&path), unique_temp_path_for(&path));
+        write_atomic_unique_with(&path, |file| Ok(file.write_all(b"{\"theme\":\"light\"}")?))?;
+        assert_eq!(fs::read(&path)?, b"{\"theme\":\"light\"}");
+
+        let copy = temp_dir.path().join("copy.json");
+        copy_atomic(&path, &copy)?;
+        assert_eq!(fs::read(&copy)?, fs::read(&path)?);
+        Ok(())
+    }
+}
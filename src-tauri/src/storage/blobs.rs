@@ -9,6 +9,9 @@ pub struct BlobPayload {
     sha256: String,
     size: u64,
     b64: String,
+    /// ID of the backup's zstd dictionary this blob was compressed against
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dictionary: Option<String>,
     // Blockchain fields - each blob links to the previous blob
     pub previous_blob_hash: Option<String>,
     pub blob_chain_hash: Option<String>,
@@ -19,17 +22,60 @@ impl BlobPayload {
         let mut hasher = Sha256::new();
         hasher.update(data);
         let sha256 = format!("{:x}", hasher.finalize());
+        Self::new_with_hash(format, data, sha256)
+    }
+
+    /// Like `new`, for callers that already hold the (lowercase hex) SHA256
+    /// of `data`, e.g. from hashing while compressing. The hash is trusted,
+    /// not checked.
+    pub fn new_with_hash(format: String, data: &[u8], sha256: String) -> Self {
+        debug_assert_eq!(sha256, hex::encode(Sha256::digest(data)));
         let b64 = general_purpose::STANDARD.encode(data);
         BlobPayload {
             format,
             sha256,
             size: data.len() as u64,
             b64,
+            dictionary: None,
             previous_blob_hash: None,
             blob_chain_hash: None,
         }
     }
 
+    /// This payload without its data, for manifests whose blobs are
+    /// fetched one by one (see `with_data`)
+    pub fn without_data(&self) -> Self {
+        Self {
+            b64: String::new(),
+            ..self.clone()
+        }
+    }
+
+    /// Put the stored bytes back into a payload from `without_data`,
+    /// refusing bytes that aren't the ones it describes
+    pub fn with_data(&self, data: &[u8]) -> Result<Self, anyhow::Error> {
+        let mut hasher = Sha256::new();
+        hasher.update(data);
+        let sha256 = format!("{:x}", hasher.finalize());
+        if sha256 != self.sha256 {
+            return Err(anyhow::anyhow!(
+                "Blob data has SHA256 {} but {} was expected",
+                sha256,
+                self.sha256
+            ));
+        }
+        Ok(Self {
+            b64: general_purpose::STANDARD.encode(data),
+            ..self.clone()
+        })
+    }
+
+    /// Whether the manifest carries this blob's bytes, false for a payload
+    /// from `without_data` that relies on the blob file alone
+    pub fn has_data(&self) -> bool {
+        !self.b64.is_empty()
+    }
+
     pub fn decode(&self) -> Result<Vec<u8>, base64::DecodeError> {
         general_purpose::STANDARD.decode(&self.b64)
     }
@@ -42,6 +88,21 @@ impl BlobPayload {
         &self.sha256
     }
 
+    /// Size of the stored (compressed, possibly sealed) bytes
+    pub fn get_size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn get_dictionary(&self) -> Option<&str> {
+        self.dictionary.as_deref()
+    }
+
+    /// Record the dictionary the stored bytes need; call before chaining,
+    /// the reference is part of the content hash
+    pub fn set_dictionary(&mut self, dictionary: Option<String>) {
+        self.dictionary = dictionary;
+    }
+
     // Blockchain methods for blob chaining
     pub fn get_previous_blob_hash(&self) -> Option<&String> {
         self.previous_blob_hash.as_ref()
@@ -62,40 +123,71 @@ impl BlobPayload {
         hasher.update(self.sha256.as_bytes());
         hasher.update(&self.size.to_le_bytes());
         hasher.update(self.b64.as_bytes());
+        if let Some(dictionary) = &self.dictionary {
+            hasher.update(dictionary.as_bytes());
+        }
         hex::encode(hasher.finalize())
     }
 
-    pub fn finalize_blob_chain_hash(&mut self) -> Result<(), anyhow::Error> {
+    /// Chain hash of this blob when linked after `previous_hash`, computed
+    /// from its stored fields as they are, without decoding the data
+    pub fn chain_hash_after(&self, previous_hash: Option<&str>) -> String {
         let mut hasher = Sha256::new();
-        
+
         // Include previous blob hash if available
-        if let Some(prev_hash) = &self.previous_blob_hash {
+        if let Some(prev_hash) = previous_hash {
             hasher.update(prev_hash.as_bytes());
         }
-        
+
         // Include current blob content hash
         let content_hash = self.calculate_blob_content_hash();
         hasher.update(content_hash.as_bytes());
-        
-        self.blob_chain_hash = Some(hex::encode(hasher.finalize()));
+
+        hex::encode(hasher.finalize())
+    }
+
+    pub fn finalize_blob_chain_hash(&mut self) -> Result<(), anyhow::Error> {
+        self.blob_chain_hash = Some(self.chain_hash_after(self.previous_blob_hash.as_deref()));
         Ok(())
     }
 
     pub fn verify_blob_integrity(&self) -> bool {
         // Verify that the blob chain hash is correct
-        let mut hasher = Sha256::new();
-        
-        if let Some(prev_hash) = &self.previous_blob_hash {
-            hasher.update(prev_hash.as_bytes());
-        }
-        
-        let content_hash = self.calculate_blob_content_hash();
-        hasher.update(content_hash.as_bytes());
-        let expected_chain_hash = hex::encode(hasher.finalize());
-        
+        let expected_chain_hash = self.chain_hash_after(self.previous_blob_hash.as_deref());
+
         match &self.blob_chain_hash {
             Some(stored_hash) => *stored_hash == expected_chain_hash,
             None => false, // No chain hash means not properly initialized
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_with_hash_matches_new() {
+        let data = b"compressed bytes";
+        let hash = hex::encode(Sha256::digest(data));
+        let with_hash = BlobPayload::new_with_hash("tar.zst".to_string(), data, hash);
+        let hashed = BlobPayload::new("tar.zst".to_string(), data);
+        assert_eq!(with_hash.get_sha256(), hashed.get_sha256());
+        assert_eq!(with_hash.get_size(), hashed.get_size());
+        assert_eq!(with_hash.decode().unwrap(), data);
+        assert_eq!(with_hash.calculate_blob_content_hash(), hashed.calculate_blob_content_hash());
+    }
+
+    #[test]
+    fn test_chain_hash_after_matches_finalized_hash() {
+        let mut blob = BlobPayload::new("tar.zst".to_string(), b"compressed bytes");
+        blob.set_dictionary(Some("dict-1".to_string()));
+        blob.set_previous_blob_hash(Some("previous".to_string()));
+        blob.finalize_blob_chain_hash().unwrap();
+
+        let expected = blob.chain_hash_after(Some("previous"));
+        assert_eq!(blob.get_blob_chain_hash(), Some(&expected));
+        assert_ne!(blob.chain_hash_after(None), expected);
+        assert!(blob.verify_blob_integrity());
+    }
+}
@@ -0,0 +1,248 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::{Read, Write},
+    path::Path,
+};
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::storage::{
+    dedup_index, dictionary::DICTIONARY_DIR, entry::Entry, manifest::Manifest, pack,
+    pool::POOL_DIR, refcount,
+};
+
+/// Bundle member holding the backup's encrypted chain metadata, which lives
+/// next to (not inside) the backup directory in storage
+const CHAIN_MEMBER: &str = "blob_chain.encrypted";
+
+const BUNDLE_COMPRESSION_LEVEL: i32 = 3;
+
+fn chain_metadata_path(storage_dir: &Path, name: &str) -> std::path::PathBuf {
+    storage_dir.join(format!("{}_blob_chain.encrypted", name))
+}
+
+/// Stream backup `name` as a single tar.zst bundle into `writer`.
+///
+/// The bundle holds `manifest.json`, the blob files (loose and packed), any
+/// compression dictionaries, the chain metadata, and any blob the entries
+/// borrow from another backup through global dedup, so it can be imported
+/// on its own. Files are streamed one at a time; nothing is buffered beyond
+/// a single blob. Blobs are copied as stored, never decompressed or
+/// re-encoded, so their IDs and hashes stay valid in the imported backup.
+pub fn export_backup_to<W: Write>(storage_dir: &Path, name: &str, writer: W) -> Result<W> {
+    let backup_dir = storage_dir.join(name);
+    let manifest_path = backup_dir.join("manifest.json");
+    let manifest: Manifest = serde_json::from_str(
+        &fs::read_to_string(&manifest_path)
+            .with_context(|| format!("Backup not found: {}", name))?,
+    )?;
+
+    let encoder = zstd::stream::write::Encoder::new(writer, BUNDLE_COMPRESSION_LEVEL)?;
+    let mut builder = tar::Builder::new(encoder);
+    builder.append_path_with_name(&manifest_path, "manifest.json")?;
+
+    let blob_dir = backup_dir.join("blobs");
+    if blob_dir.is_dir() {
+        builder.append_dir_all("blobs", &blob_dir)?;
+    }
+    let dictionary_dir = backup_dir.join(DICTIONARY_DIR);
+    if dictionary_dir.is_dir() {
+        builder.append_dir_all(DICTIONARY_DIR, &dictionary_dir)?;
+    }
+
+    // Blobs stored by other backups, exported as loose files
+    let own_index = pack::PackIndex::load(&blob_dir)?;
+    let borrowed: BTreeSet<&str> = manifest
+        .entries
+        .iter()
+        .filter_map(Entry::stored_blob_id)
+        .filter(|id| !own_index.has_blob(&blob_dir, id))
+        .collect();
+    for blob_id in borrowed {
+        let bytes = pack::read_blob_in_storage(storage_dir, name, blob_id)?;
+        let mut header = tar::Header::new_gnu();
+        header.set_size(bytes.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, format!("blobs/{}.tar.zst", blob_id), &bytes[..])?;
+    }
+
+    let chain_path = chain_metadata_path(storage_dir, name);
+    if chain_path.is_file() {
+        builder.append_path_with_name(&chain_path, CHAIN_MEMBER)?;
+    }
+
+    let encoder = builder.into_inner()?;
+    Ok(encoder.finish()?)
+}
+
+/// Import a bundle written by `export_backup_to` from `reader` into
+/// `storage_dir`, optionally under a new name. Returns the imported name.
+///
+/// Members are unpacked into a staging directory and moved into place only
+/// once the whole stream has been read, so a broken pipe leaves no partial
+/// backup. An existing backup with the same name is never overwritten.
+pub fn import_backup_from<R: Read>(
+    storage_dir: &Path,
+    reader: R,
+    rename: Option<&str>,
+) -> Result<String> {
+    fs::create_dir_all(storage_dir)?;
+    let staging = storage_dir.join(format!(
+        ".import-{}-{}",
+        std::process::id(),
+        chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    ));
+    fs::create_dir_all(&staging)?;
+
+    let result = unpack_staged(storage_dir, &staging, reader, rename);
+    if result.is_err() {
+        let _ = fs::remove_dir_all(&staging);
+    }
+    result
+}
+
+fn unpack_staged<R: Read>(
+    storage_dir: &Path,
+    staging: &Path,
+    reader: R,
+    rename: Option<&str>,
+) -> Result<String> {
+    let decoder = zstd::stream::read::Decoder::new(reader)?;
+    let mut archive = tar::Archive::new(decoder);
+    for member in archive.entries().context("Not a backup bundle")? {
+        // unpack_in refuses members that would escape the staging directory
+        member?.unpack_in(staging)?;
+    }
+
+    let staged_manifest = staging.join("manifest.json");
+    let mut manifest: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(&staged_manifest).context("Bundle has no manifest.json")?,
+    )?;
+    let name = match rename {
+        Some(new_name) => {
+            manifest["name"] = serde_json::Value::String(new_name.to_string());
+            fs::write(&staged_manifest, serde_json::to_string_pretty(&manifest)?)?;
+            new_name.to_string()
+        }
+        None => manifest["name"]
+            .as_str()
+            .ok_or_else(|| anyhow!("Bundle manifest has no name"))?
+            .to_string(),
+    };
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') || name == POOL_DIR {
+        return Err(anyhow!("Invalid backup name in bundle: {:?}", name));
+    }
+
+    let target = storage_dir.join(&name);
+    if target.exists() {
+        return Err(anyhow!("A backup named '{}' already exists", name));
+    }
+
+    // Counted before the backup becomes visible; a failed move only leaves
+    // the counts too high, which never frees a blob early
+    let entries: Option<Vec<Entry>> = serde_json::from_value(manifest["entries"].clone())?;
+    let entries = entries.unwrap_or_default();
+    refcount::add_references(storage_dir, entries.iter().filter_map(Entry::stored_blob_id))?;
+
+    let staged_chain = staging.join(CHAIN_MEMBER);
+    if staged_chain.is_file() {
+        fs::rename(&staged_chain, chain_metadata_path(storage_dir, &name))?;
+    }
+    fs::rename(staging, &target)?;
+    dedup_index::invalidate(storage_dir);
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_backup(storage_dir: &Path, name: &str, blobs: &[(&str, &[u8])]) -> Result<()> {
+        let mut manifest = Manifest::empty(name.to_string());
+        let blob_dir = storage_dir.join(name).join("blobs");
+        fs::create_dir_all(&blob_dir)?;
+        for (blob_id, bytes) in blobs {
+            manifest.entries.push(crate::storage::entry::Entry {
+                blob_id: blob_id.to_string(),
+                target_hint: "app:test".to_string(),
+                logical_path: format!("/cfg/{}", blob_id),
+                ..Default::default()
+            });
+            if name == "shared" || !blob_id.starts_with("shared") {
+                fs::write(blob_dir.join(format!("{}.tar.zst", blob_id)), bytes)?;
+            }
+        }
+        fs::write(
+            storage_dir.join(name).join("manifest.json"),
+            serde_json::to_string(&manifest)?,
+        )?;
+        fs::write(chain_metadata_path(storage_dir, name), b"chain")?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_import_keeps_blob_bytes() -> Result<()> {
+        use sha2::{Digest, Sha256};
+
+        let source = TempDir::new()?;
+        let files = TempDir::new()?;
+        let mut manifest = Manifest::new_in("daily".to_string(), source.path());
+        manifest.set_inline_threshold(0);
+        for (name, content) in [("a.conf", "alpha = 1"), ("b.conf", "beta = 2")] {
+            let path = files.path().join(name);
+            fs::write(&path, content)?;
+            manifest.create_blob_from_file(&path, "app:test", Some(files.path()))?;
+        }
+        manifest.save()?;
+
+        let mut bundle = Vec::new();
+        export_backup_to(source.path(), "daily", &mut bundle)?;
+        let target = TempDir::new()?;
+        import_backup_from(target.path(), &bundle[..], None)?;
+
+        let imported = Manifest::load_in(target.path(), "daily")?;
+        assert_eq!(imported.blobs.len(), 2);
+        for (blob_id, blob) in &manifest.blobs {
+            assert_eq!(imported.blobs[blob_id].get_sha256(), blob.get_sha256());
+            let stored = pack::read_blob_in_storage(target.path(), "daily", blob_id)?;
+            assert_eq!(hex::encode(Sha256::digest(&stored)), blob.get_sha256());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_import_round_trip_through_a_stream() -> Result<()> {
+        let source = TempDir::new()?;
+        write_backup(source.path(), "shared", &[("shared-a", b"aaa")])?;
+        // "shared-a" is deduplicated against the other backup
+        write_backup(source.path(), "daily", &[("own-b", b"bbb"), ("shared-a", b"aaa")])?;
+
+        let mut bundle = Vec::new();
+        export_backup_to(source.path(), "daily", &mut bundle)?;
+
+        let target = TempDir::new()?;
+        let name = import_backup_from(target.path(), &bundle[..], None)?;
+        assert_eq!(name, "daily");
+        let blob_dir = target.path().join("daily").join("blobs");
+        assert_eq!(fs::read(blob_dir.join("own-b.tar.zst"))?, b"bbb");
+        assert_eq!(fs::read(blob_dir.join("shared-a.tar.zst"))?, b"aaa");
+        assert_eq!(fs::read(chain_metadata_path(target.path(), "daily"))?, b"chain");
+
+        // Same name again is refused, a rename is fine
+        assert!(import_backup_from(target.path(), &bundle[..], None).is_err());
+        let renamed = import_backup_from(target.path(), &bundle[..], Some("daily-copy"))?;
+        let manifest: Manifest = serde_json::from_str(&fs::read_to_string(
+            target.path().join(&renamed).join("manifest.json"),
+        )?)?;
+        assert_eq!(manifest.name, "daily-copy");
+
+        // A cut-off stream leaves nothing behind
+        let before = fs::read_dir(target.path())?.count();
+        assert!(import_backup_from(target.path(), &bundle[..bundle.len() / 2], Some("x")).is_err());
+        assert_eq!(fs::read_dir(target.path())?.count(), before);
+        Ok(())
+    }
+}
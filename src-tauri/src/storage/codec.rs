@@ -0,0 +1,216 @@
+use std::io::{Read, Write};
+use std::time::Instant;
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::settings::CodecSelection;
+
+/// Blobs smaller than this always use zstd; trial runs wouldn't pay off
+pub const AUTO_CODEC_MIN_SIZE: usize = 256 * 1024;
+
+/// Bytes taken from each of the start, middle and end of a blob for trials
+const SAMPLE_SLICE: usize = 64 * 1024;
+
+const XZ_PRESET: u32 = 6;
+
+/// Compression codec of a blob, recorded in `BlobPayload.format`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    Zstd,
+    Xz,
+    Lz4,
+}
+
+impl Codec {
+    /// Trial order; on a tie the earlier codec wins, so zstd is preferred
+    pub const ALL: [Codec; 3] = [Codec::Zstd, Codec::Xz, Codec::Lz4];
+
+    pub fn format(self) -> &'static str {
+        match self {
+            Codec::Zstd => "tar.zst",
+            Codec::Xz => "tar.xz",
+            Codec::Lz4 => "tar.lz4",
+        }
+    }
+
+    pub fn from_format(format: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|codec| codec.format() == format)
+    }
+
+    /// `zstd_level` only applies to zstd; xz and lz4 use fixed presets
+    pub fn compress(self, data: &[u8], zstd_level: i32) -> Result<Vec<u8>> {
+        match self {
+            Codec::Zstd => Ok(zstd::encode_all(data, zstd_level)?),
+            Codec::Xz => {
+                let mut encoder = xz2::write::XzEncoder::new(Vec::new(), XZ_PRESET);
+                encoder.write_all(data)?;
+                Ok(encoder.finish()?)
+            }
+            Codec::Lz4 => Ok(lz4_flex::compress_prepend_size(data)),
+        }
+    }
+
+    pub fn decompress(self, data: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Codec::Zstd => zstd::decode_all(data).context("Invalid zstd data"),
+            Codec::Xz => {
+                let mut decompressed = Vec::new();
+                xz2::read::XzDecoder::new(data)
+                    .read_to_end(&mut decompressed)
+                    .context("Invalid xz data")?;
+                Ok(decompressed)
+            }
+            Codec::Lz4 => lz4_flex::decompress_size_prepended(data)
+                .map_err(|e| anyhow!("Invalid lz4 data: {}", e)),
+        }
+    }
+}
+
+/// Start, middle and end of `data`, so trials see headers as well as bulk
+fn sample(data: &[u8]) -> Vec<u8> {
+    if data.len() <= SAMPLE_SLICE * 3 {
+        return data.to_vec();
+    }
+    let middle = data.len() / 2 - SAMPLE_SLICE / 2;
+    let mut sample = Vec::with_capacity(SAMPLE_SLICE * 3);
+    sample.extend_from_slice(&data[..SAMPLE_SLICE]);
+    sample.extend_from_slice(&data[middle..middle + SAMPLE_SLICE]);
+    sample.extend_from_slice(&data[data.len() - SAMPLE_SLICE..]);
+    sample
+}
+
+/// How much worse than the best ratio a faster codec may compress and
+/// still be picked by `BestRatioPerSecond`
+const RATIO_TOLERANCE: f64 = 0.05;
+
+/// Outcome of trial-compressing the sample with one codec
+struct Trial {
+    codec: Codec,
+    ratio: f64,
+    seconds: f64,
+}
+
+/// Pick the codec for `data` by trial-compressing a sample with each one
+pub fn select_codec(data: &[u8], zstd_level: i32, selection: CodecSelection) -> Codec {
+    if selection == CodecSelection::Zstd || data.len() < AUTO_CODEC_MIN_SIZE {
+        return Codec::Zstd;
+    }
+
+    let sample = sample(data);
+    let trials: Vec<Trial> = Codec::ALL
+        .into_iter()
+        .filter_map(|codec| {
+            let start = Instant::now();
+            let compressed = codec.compress(&sample, zstd_level).ok()?;
+            Some(Trial {
+                codec,
+                ratio: sample.len() as f64 / compressed.len().max(1) as f64,
+                seconds: start.elapsed().as_secs_f64(),
+            })
+        })
+        .collect();
+    pick(&trials, selection)
+}
+
+/// `BestRatio` keeps the best ratio. `BestRatioPerSecond` keeps the fastest
+/// codec within `RATIO_TOLERANCE` of that ratio, so speed only decides
+/// between codecs that compress about as well: a much faster codec with a
+/// clearly worse ratio (lz4, typically) doesn't win on speed alone. Ties go
+/// to the codec tried first.
+fn pick(trials: &[Trial], selection: CodecSelection) -> Codec {
+    let Some(best_ratio) = trials.iter().map(|trial| trial.ratio).reduce(f64::max) else {
+        return Codec::Zstd;
+    };
+    let close_enough = |trial: &&Trial| match selection {
+        CodecSelection::BestRatioPerSecond => trial.ratio >= best_ratio * (1.0 - RATIO_TOLERANCE),
+        _ => trial.ratio >= best_ratio,
+    };
+    trials
+        .iter()
+        .filter(close_enough)
+        .reduce(|fastest, trial| if trial.seconds < fastest.seconds { trial } else { fastest })
+        .map_or(Codec::Zstd, |trial| trial.codec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codecs_round_trip_and_selection() -> Result<()> {
+        let data: Vec<u8> = (0..AUTO_CODEC_MIN_SIZE * 2)
+            .map(|i| b"key = value\n"[i % 12])
+            .collect();
+        for codec in Codec::ALL {
+            let compressed = codec.compress(&data, 3)?;
+            assert_eq!(codec.decompress(&compressed)?, data);
+            assert_eq!(Codec::from_format(codec.format()), Some(codec));
+        }
+
+        // Small blobs and the default setting never pay for trials
+        assert_eq!(select_codec(&data[..1024], 3, CodecSelection::BestRatio), Codec::Zstd);
+        assert_eq!(select_codec(&data, 3, CodecSelection::Zstd), Codec::Zstd);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ratio_per_second_only_trades_a_little_ratio_for_speed() {
+        let trial = |codec, ratio, seconds| Trial { codec, ratio, seconds };
+        // Typical text: lz4 is far faster but compresses far worse
+        let trials = [
+            trial(Codec::Zstd, 8.0, 0.020),
+            trial(Codec::Xz, 8.2, 0.200),
+            trial(Codec::Lz4, 3.0, 0.002),
+        ];
+        assert_eq!(pick(&trials, CodecSelection::BestRatio), Codec::Xz);
+        assert_eq!(pick(&trials, CodecSelection::BestRatioPerSecond), Codec::Zstd);
+
+        // Incompressible data: all codecs about equal, so the fastest wins
+        let trials = [
+            trial(Codec::Zstd, 1.0, 0.010),
+            trial(Codec::Xz, 1.0, 0.100),
+            trial(Codec::Lz4, 0.99, 0.001),
+        ];
+        assert_eq!(pick(&trials, CodecSelection::BestRatio), Codec::Zstd);
+        assert_eq!(pick(&trials, CodecSelection::BestRatioPerSecond), Codec::Lz4);
+        assert_eq!(pick(&[], CodecSelection::BestRatioPerSecond), Codec::Zstd);
+    }
+
+    /// Run with `cargo test --release -- --ignored bench_codec_selection --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_codec_selection() -> Result<()> {
+        // Mix of text-like and noisy data, like a config dir with a binary cache
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut data = Vec::with_capacity(8 * 1024 * 1024);
+        while data.len() < 8 * 1024 * 1024 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            if (state >> 60) < 10 {
+                data.extend_from_slice(b"\"editor.fontSize\": 14,\n");
+            } else {
+                data.extend_from_slice(&state.to_le_bytes()[..3]);
+            }
+        }
+
+        for codec in Codec::ALL {
+            let start = Instant::now();
+            let compressed = codec.compress(&data, 19)?;
+            println!(
+                "{:?}: {} -> {} bytes ({:.2}x) in {:?}",
+                codec,
+                data.len(),
+                compressed.len(),
+                data.len() as f64 / compressed.len() as f64,
+                start.elapsed()
+            );
+        }
+        for selection in [CodecSelection::BestRatio, CodecSelection::BestRatioPerSecond] {
+            let start = Instant::now();
+            let codec = select_codec(&data, 19, selection);
+            println!("{:?} picked {:?} in {:?}", selection, codec, start.elapsed());
+        }
+        Ok(())
+    }
+}
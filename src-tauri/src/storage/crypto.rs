@@ -0,0 +1,251 @@
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+
+/// Appended to a blob's compressed format once it is sealed with `encrypt`,
+/// e.g. `tar.zst.enc`
+pub const ENCRYPTED_SUFFIX: &str = ".enc";
+
+/// Environment variable that replaces the master key in debug and test
+/// builds, so tests can write with one key and read with another. Release
+/// builds never look at it.
+pub const TEST_KEY_ENV: &str = "SAVEME_TEST_KEY";
+
+/// Environment variable holding the blob passphrase for the CLI; the app
+/// sets it with `set_passphrase` instead
+pub const PASSPHRASE_ENV: &str = "SAVEME_PASSPHRASE";
+
+/// PBKDF2-HMAC-SHA256 rounds turning the passphrase into a blob key
+const KDF_ITERATIONS: u32 = 100_000;
+
+const SALT_LEN: usize = 16;
+
+/// Passphrase set by the app for this session
+static PASSPHRASE: RwLock<Option<String>> = RwLock::new(None);
+
+/// Salt of the blobs sealed by this process, so the key is derived once
+static SEAL_SALT: Lazy<[u8; SALT_LEN]> = Lazy::new(|| {
+    let mut salt = [0u8; SALT_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    salt
+});
+
+/// Blob keys already derived from the current passphrase, by salt
+static BLOB_KEYS: Lazy<Mutex<HashMap<[u8; SALT_LEN], [u8; 32]>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Key of the blob-chain metadata. It is built into the app, so it only
+/// guards the chain against casual edits; blob contents use `seal`.
+pub(crate) fn encryption_key() -> [u8; 32] {
+    #[cfg(any(test, debug_assertions))]
+    if let Some(key) = std::env::var_os(TEST_KEY_ENV) {
+        return test_key(key.as_encoded_bytes());
+    }
+
+    // In a production environment, this key should be derived from:
+    // 1. User password + salt
+    // 2. Hardware-specific information
+    // 3. Application-specific secret
+    // For now, we use a deterministic key for the demo
+    let mut hasher = Sha256::new();
+    hasher.update(b"saveme_config_blob_chain_master_key");
+    hasher.update(b"application_specific_salt_2024");
+    let hash = hasher.finalize();
+
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&hash[..32]);
+    key
+}
+
+/// The key `SAVEME_TEST_KEY=<secret>` selects
+#[cfg(any(test, debug_assertions))]
+pub(crate) fn test_key(secret: &[u8]) -> [u8; 32] {
+    Sha256::digest(secret).into()
+}
+
+/// Set (or with None, forget) the passphrase blobs are sealed with, taking
+/// precedence over `SAVEME_PASSPHRASE`
+pub fn set_passphrase(passphrase: Option<String>) {
+    *PASSPHRASE.write().unwrap_or_else(|e| e.into_inner()) = passphrase;
+    BLOB_KEYS.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+fn passphrase() -> Result<String> {
+    let set = PASSPHRASE.read().unwrap_or_else(|e| e.into_inner()).clone();
+    set.or_else(|| std::env::var(PASSPHRASE_ENV).ok())
+        .filter(|passphrase| !passphrase.is_empty())
+        .ok_or_else(|| {
+            anyhow!(
+                "Blob encryption needs a passphrase: set one in the app or with {}",
+                PASSPHRASE_ENV
+            )
+        })
+}
+
+/// The blob key for `salt`, derived from the passphrase on first use
+fn blob_key(salt: &[u8; SALT_LEN]) -> Result<[u8; 32]> {
+    if let Some(key) = BLOB_KEYS.lock().unwrap_or_else(|e| e.into_inner()).get(salt) {
+        return Ok(*key);
+    }
+    let key = derive_key(passphrase()?.as_bytes(), salt, KDF_ITERATIONS);
+    BLOB_KEYS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(*salt, key);
+    Ok(key)
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut block = [0u8; 64];
+    if key.len() > block.len() {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let pad = |byte: u8| block.map(|b| b ^ byte);
+    let inner = Sha256::new().chain_update(pad(0x36)).chain_update(message).finalize();
+    Sha256::new().chain_update(pad(0x5c)).chain_update(inner).finalize().into()
+}
+
+/// PBKDF2-HMAC-SHA256 (RFC 8018) of `secret`, one 32 byte block
+fn derive_key(secret: &[u8], salt: &[u8], iterations: u32) -> [u8; 32] {
+    let mut first = salt.to_vec();
+    first.extend_from_slice(&1u32.to_be_bytes());
+    let mut round = hmac_sha256(secret, &first);
+    let mut key = round;
+    for _ in 1..iterations {
+        round = hmac_sha256(secret, &round);
+        key.iter_mut().zip(round).for_each(|(k, r)| *k ^= r);
+    }
+    key
+}
+
+/// Encrypt blob contents with a key derived from the passphrase, returning
+/// the salt followed by what `encrypt_with_key` produces
+pub fn seal(data: &[u8]) -> Result<Vec<u8>> {
+    let salt = *SEAL_SALT;
+    let mut sealed = salt.to_vec();
+    sealed.extend(encrypt_with_key(&blob_key(&salt)?, data)?);
+    Ok(sealed)
+}
+
+/// Reverse of `seal`; fails on a wrong passphrase
+pub fn open(sealed: &[u8]) -> Result<Vec<u8>> {
+    if sealed.len() < SALT_LEN {
+        return Err(anyhow!("Invalid encrypted data: too short"));
+    }
+    let (salt, encrypted) = sealed.split_at(SALT_LEN);
+    let salt: [u8; SALT_LEN] = salt.try_into()?;
+    decrypt_with_key(&blob_key(&salt)?, encrypted)
+        .map_err(|_| anyhow!("Failed to decrypt: wrong passphrase or damaged blob"))
+}
+
+/// AES-256-GCM encrypt `data` with the blob-chain key, returning the random
+/// nonce followed by the ciphertext
+pub fn encrypt(data: &[u8]) -> Result<Vec<u8>> {
+    encrypt_with_key(&encryption_key(), data)
+}
+
+pub(crate) fn encrypt_with_key(key: &[u8; 32], data: &[u8]) -> Result<Vec<u8>> {
+    let cipher = Aes256Gcm::new_from_slice(key)?;
+
+    // Generate a random nonce
+    let mut nonce_bytes = [0u8; 12];
+    rand::thread_rng().fill_bytes(&mut nonce_bytes);
+    let nonce = Nonce::from_slice(&nonce_bytes);
+
+    let ciphertext = cipher
+        .encrypt(nonce, data)
+        .map_err(|e| anyhow!("Encryption failed: {}", e))?;
+
+    // Prepend nonce to ciphertext for storage
+    let mut encrypted_data = nonce_bytes.to_vec();
+    encrypted_data.extend_from_slice(&ciphertext);
+    Ok(encrypted_data)
+}
+
+/// Reverse of `encrypt`
+pub fn decrypt(encrypted_data: &[u8]) -> Result<Vec<u8>> {
+    decrypt_with_key(&encryption_key(), encrypted_data)
+}
+
+pub(crate) fn decrypt_with_key(key: &[u8; 32], encrypted_data: &[u8]) -> Result<Vec<u8>> {
+    if encrypted_data.len() < 12 {
+        return Err(anyhow!("Invalid encrypted data: too short"));
+    }
+
+    let cipher = Aes256Gcm::new_from_slice(key)?;
+
+    let (nonce_bytes, ciphertext) = encrypted_data.split_at(12);
+    let nonce = Nonce::from_slice(nonce_bytes);
+
+    cipher
+        .decrypt(nonce, ciphertext)
+        .map_err(|e| anyhow!("Decryption failed: {}", e))
+}
+
+/// Passphrase every test sealing blobs sets, as tests share the global one
+#[cfg(test)]
+pub(crate) const TEST_PASSPHRASE: &str = "correct horse battery staple";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_round_trip() -> Result<()> {
+        let sealed = encrypt(b"token=secret")?;
+        assert_ne!(&sealed[12..], b"token=secret");
+        assert_eq!(decrypt(&sealed)?, b"token=secret");
+
+        let mut tampered = sealed.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 0xff;
+        assert!(decrypt(&tampered).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_decrypting_with_another_key_fails() -> Result<()> {
+        // Other tests run in parallel against the default key, so this one
+        // passes keys explicitly instead of setting SAVEME_TEST_KEY
+        let writer = test_key(b"writer");
+        let sealed = encrypt_with_key(&writer, b"token=secret")?;
+        assert_eq!(decrypt_with_key(&writer, &sealed)?, b"token=secret");
+        assert!(decrypt_with_key(&test_key(b"reader"), &sealed).is_err());
+        assert!(decrypt(&sealed).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_derive_key_matches_pbkdf2_sha256() {
+        // RFC 7914 section 11, first 32 bytes
+        assert_eq!(
+            hex::encode(derive_key(b"passwd", b"salt", 1)),
+            "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc"
+        );
+    }
+
+    #[test]
+    fn test_sealed_blobs_need_the_passphrase() -> Result<()> {
+        set_passphrase(Some(TEST_PASSPHRASE.to_string()));
+        let sealed = seal(b"token=secret")?;
+        assert_eq!(open(&sealed)?, b"token=secret");
+        assert!(decrypt(&sealed[SALT_LEN..]).is_err());
+
+        // A blob sealed under another passphrase doesn't open
+        let other_salt = [7u8; SALT_LEN];
+        let wrong = derive_key(b"wrong passphrase", &other_salt, 1);
+        let mut forged = other_salt.to_vec();
+        forged.extend(encrypt_with_key(&wrong, b"token=secret")?);
+        assert!(open(&forged).is_err());
+        Ok(())
+    }
+}
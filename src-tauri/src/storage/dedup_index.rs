@@ -0,0 +1,134 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+
+use crate::storage::{manifest::Manifest, performance::PERFORMANCE_METRICS};
+
+/// Content hash -> (backup, blob ID) of every blob stored in one storage
+/// directory, so cross-backup dedup doesn't load each manifest per blob
+#[derive(Default)]
+struct DirIndex {
+    by_hash: HashMap<String, (String, String)>,
+    /// Every manifest on disk has been scanned, so a hash missing from
+    /// `by_hash` is known not to be stored anywhere
+    complete: bool,
+}
+
+static DEDUP_INDEX: Lazy<Mutex<HashMap<PathBuf, DirIndex>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Result of a dedup lookup and whether the index answered it alone
+pub(crate) struct Lookup {
+    pub found: Option<(String, String)>,
+    pub from_index: bool,
+}
+
+/// Find a stored blob with this content in any backup of `storage_dir`,
+/// recording a cache hit when the index answers and a miss when the
+/// manifests had to be scanned.
+///
+/// The index lives for the process and is filled by the first scan, then
+/// kept current by `record` on every manifest save. A hit whose backup has
+/// been deleted since (e.g. by another process) falls back to a scan.
+pub fn find_blob(storage_dir: &Path, content_hash: &str) -> Result<Option<(String, String)>> {
+    let lookup = lookup(storage_dir, content_hash)?;
+    if lookup.from_index {
+        PERFORMANCE_METRICS.add_cache_hit();
+    } else {
+        PERFORMANCE_METRICS.add_cache_miss();
+    }
+    Ok(lookup.found)
+}
+
+pub(crate) fn lookup(storage_dir: &Path, content_hash: &str) -> Result<Lookup> {
+    let mut index = DEDUP_INDEX.lock().unwrap_or_else(|e| e.into_inner());
+    let dir_index = index.entry(storage_dir.to_path_buf()).or_default();
+
+    match dir_index.by_hash.get(content_hash) {
+        Some(found) if storage_dir.join(&found.0).join("manifest.json").is_file() => {
+            return Ok(Lookup {
+                found: Some(found.clone()),
+                from_index: true,
+            });
+        }
+        None if dir_index.complete => {
+            return Ok(Lookup {
+                found: None,
+                from_index: true,
+            });
+        }
+        _ => {}
+    }
+
+    *dir_index = scan(storage_dir)?;
+    Ok(Lookup {
+        found: dir_index.by_hash.get(content_hash).cloned(),
+        from_index: false,
+    })
+}
+
+fn scan(storage_dir: &Path) -> Result<DirIndex> {
+    let mut dir_index = DirIndex {
+        by_hash: HashMap::new(),
+        complete: true,
+    };
+    if !storage_dir.is_dir() {
+        return Ok(dir_index);
+    }
+    for dir in fs::read_dir(storage_dir)? {
+        let dir = dir?;
+        let name = dir.file_name().to_string_lossy().into_owned();
+        // Dot directories are imports still being staged
+        if name.starts_with('.') || !dir.path().join("manifest.json").is_file() {
+            continue;
+        }
+        let manifest = Manifest::load_in(storage_dir, &name)?;
+        for (blob_id, blob) in &manifest.blobs {
+            dir_index
+                .by_hash
+                .entry(blob.get_sha256().to_string())
+                .or_insert_with(|| (name.clone(), blob_id.clone()));
+        }
+    }
+    Ok(dir_index)
+}
+
+/// Add the blobs of a manifest just written to `storage_dir`
+pub fn record(storage_dir: &Path, manifest: &Manifest) {
+    let mut index = DEDUP_INDEX.lock().unwrap_or_else(|e| e.into_inner());
+    let dir_index = index.entry(storage_dir.to_path_buf()).or_default();
+    for (blob_id, blob) in &manifest.blobs {
+        dir_index
+            .by_hash
+            .entry(blob.get_sha256().to_string())
+            .or_insert_with(|| (manifest.name.clone(), blob_id.clone()));
+    }
+}
+
+/// Drop the blobs of a deleted backup; they are looked up again by the next
+/// scan if another backup holds the same content
+pub fn forget_backup(storage_dir: &Path, backup: &str) {
+    let mut index = DEDUP_INDEX.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(dir_index) = index.get_mut(storage_dir) {
+        let before = dir_index.by_hash.len();
+        dir_index.by_hash.retain(|_, (name, _)| name != backup);
+        if dir_index.by_hash.len() != before {
+            dir_index.complete = false;
+        }
+    }
+}
+
+/// Make the next lookup in `storage_dir` rescan the manifests, after a
+/// backup appeared without going through `Manifest::save_in` (e.g. import)
+pub fn invalidate(storage_dir: &Path) {
+    let mut index = DEDUP_INDEX.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(dir_index) = index.get_mut(storage_dir) {
+        dir_index.complete = false;
+    }
+}
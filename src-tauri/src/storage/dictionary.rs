@@ -0,0 +1,156 @@
+use std::{
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context, Result};
+use sha2::{Digest, Sha256};
+
+use crate::storage::atomic::write_atomic;
+
+/// Directory of a backup holding its trained zstd dictionaries, one
+/// `<sha256>.zdict` file each
+pub const DICTIONARY_DIR: &str = "dictionaries";
+
+/// Largest dictionary trained; config files share short keys and
+/// boilerplate, so a small dictionary already captures most of it
+const DICTIONARY_MAX_SIZE: usize = 32 * 1024;
+
+/// Below this many samples zstd can't train anything useful
+const MIN_SAMPLES: usize = 8;
+
+/// Files larger than this compress well enough on their own and are not
+/// used as samples
+const SAMPLE_MAX_FILE_SIZE: u64 = 128 * 1024;
+
+/// Total sample bytes read for training at most
+const SAMPLE_BUDGET: usize = 8 * 1024 * 1024;
+
+/// Contents of the small files among `paths`, up to `SAMPLE_BUDGET` bytes,
+/// as training samples. Unreadable files are left out.
+pub fn samples_from_files(paths: &[PathBuf]) -> Vec<Vec<u8>> {
+    let mut samples = Vec::new();
+    let mut budget = SAMPLE_BUDGET;
+    for path in paths {
+        let small = fs::metadata(path)
+            .is_ok_and(|m| m.is_file() && m.len() <= SAMPLE_MAX_FILE_SIZE);
+        if !small {
+            continue;
+        }
+        let Ok(content) = fs::read(path) else {
+            continue;
+        };
+        if content.is_empty() || content.len() > budget {
+            continue;
+        }
+        budget -= content.len();
+        samples.push(content);
+    }
+    samples
+}
+
+/// Train a dictionary over `samples`; fails when there are too few of them
+/// or zstd finds nothing worth sharing
+pub fn train(samples: &[Vec<u8>]) -> Result<Vec<u8>> {
+    if samples.len() < MIN_SAMPLES {
+        return Err(anyhow!(
+            "Need at least {} small files to train a dictionary, found {}",
+            MIN_SAMPLES,
+            samples.len()
+        ));
+    }
+    let total: usize = samples.iter().map(Vec::len).sum();
+    // zstd wants several times more sample data than dictionary
+    let max_size = DICTIONARY_MAX_SIZE.min(total / 8).max(256);
+    zstd::dict::from_samples(samples, max_size).context("Failed to train compression dictionary")
+}
+
+/// Where dictionary `id` of the backup in `backup_dir` is kept
+pub fn dictionary_path(backup_dir: &Path, id: &str) -> PathBuf {
+    backup_dir.join(DICTIONARY_DIR).join(format!("{}.zdict", id))
+}
+
+/// Store `dictionary` under `backup_dir`; returns its ID (the SHA256)
+pub fn write(backup_dir: &Path, dictionary: &[u8]) -> Result<String> {
+    let id = hex::encode(Sha256::digest(dictionary));
+    fs::create_dir_all(backup_dir.join(DICTIONARY_DIR))?;
+    write_atomic(&dictionary_path(backup_dir, &id), dictionary)?;
+    Ok(id)
+}
+
+/// Read dictionary `id` of backup `backup`, looking in the other backups of
+/// `storage_dir` for blobs borrowed through global dedup. The content is
+/// checked against the ID.
+pub fn load(storage_dir: &Path, backup: &str, id: &str) -> Result<Vec<u8>> {
+    let own = dictionary_path(&storage_dir.join(backup), id);
+    let path = if own.is_file() {
+        own
+    } else {
+        fs::read_dir(storage_dir)
+            .ok()
+            .into_iter()
+            .flatten()
+            .filter_map(|dir| dir.ok())
+            .map(|dir| dictionary_path(&dir.path(), id))
+            .find(|path| path.is_file())
+            .ok_or_else(|| anyhow!("Compression dictionary {} not found", id))?
+    };
+
+    let dictionary = fs::read(&path)?;
+    if hex::encode(Sha256::digest(&dictionary)) != id {
+        return Err(anyhow!("Compression dictionary {} is corrupted", id));
+    }
+    Ok(dictionary)
+}
+
+/// Decompress a zstd stream compressed against `dictionary`
+pub fn decompress(raw: &[u8], dictionary: &[u8]) -> Result<Vec<u8>> {
+    let mut decoder = zstd::stream::read::Decoder::with_dictionary(raw, dictionary)?;
+    let mut decompressed = Vec::new();
+    decoder
+        .read_to_end(&mut decompressed)
+        .context("Invalid zstd data for this dictionary")?;
+    Ok(decompressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::hashing::{compress_and_hash, compress_and_hash_with_dictionary};
+    use tempfile::TempDir;
+
+    fn config_sample(i: usize) -> Vec<u8> {
+        format!(
+            "{{\n  \"editor.fontSize\": {},\n  \"editor.tabSize\": 4,\n  \
+             \"workbench.colorTheme\": \"Theme {}\",\n  \"files.autoSave\": \"afterDelay\",\n  \
+             \"terminal.integrated.shell\": \"/bin/zsh\"\n}}\n",
+            10 + i % 7,
+            i
+        )
+        .into_bytes()
+    }
+
+    #[test]
+    fn test_dictionary_round_trip_beats_plain_zstd() -> Result<()> {
+        let samples: Vec<Vec<u8>> = (0..200).map(config_sample).collect();
+        let dictionary = train(&samples)?;
+        assert!(train(&samples[..3]).is_err());
+
+        let temp_dir = TempDir::new()?;
+        let id = write(&temp_dir.path().join("daily"), &dictionary)?;
+        let loaded = load(temp_dir.path(), "daily", &id)?;
+        assert_eq!(loaded, dictionary);
+        // Found from another backup too
+        assert_eq!(load(temp_dir.path(), "other", &id)?, dictionary);
+        assert!(load(temp_dir.path(), "daily", "missing").is_err());
+
+        let data = config_sample(1000);
+        let (plain, _) = compress_and_hash(&data, 19)?;
+        let (with_dictionary, hash) = compress_and_hash_with_dictionary(&data, 19, &dictionary)?;
+        assert!(with_dictionary.len() < plain.len());
+        assert_eq!(hash, hex::encode(Sha256::digest(&with_dictionary)));
+        assert_eq!(decompress(&with_dictionary, &loaded)?, data);
+        Ok(())
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+
+/// Bytes available to this user on the filesystem holding `path`, which
+/// doesn't need to exist yet
+pub fn available_space(path: &Path) -> Result<u64> {
+    fs2::available_space(existing_ancestor(path))
+        .map_err(|e| anyhow!("Failed to read the free space for {}: {}", path.display(), e))
+}
+
+/// `path` or its closest existing ancestor, since restore destinations and
+/// new backup directories often don't exist yet
+fn existing_ancestor(path: &Path) -> &Path {
+    path.ancestors().find(|p| p.exists()).unwrap_or(path)
+}
+
+/// Identifies the filesystem holding the existing `path`, so that needs of
+/// destinations sharing one add up
+#[cfg(unix)]
+fn filesystem_id(path: &Path) -> String {
+    use std::os::unix::fs::MetadataExt;
+    path.metadata()
+        .map(|metadata| metadata.dev().to_string())
+        .unwrap_or_else(|_| path.display().to_string())
+}
+
+#[cfg(not(unix))]
+fn filesystem_id(path: &Path) -> String {
+    match path.components().next() {
+        Some(std::path::Component::Prefix(prefix)) => {
+            prefix.as_os_str().to_string_lossy().to_uppercase()
+        }
+        _ => path.display().to_string(),
+    }
+}
+
+/// Check that `needs`, `(destination, bytes)` pairs, fit in the space
+/// `available` reports for their filesystems. The error names the first
+/// filesystem that is too full with what is needed and what is free.
+pub fn ensure_space(
+    needs: impl IntoIterator<Item = (PathBuf, u64)>,
+    available: &dyn Fn(&Path) -> Result<u64>,
+) -> Result<()> {
+    let mut by_filesystem: BTreeMap<String, (PathBuf, u64)> = BTreeMap::new();
+    for (path, bytes) in needs {
+        let existing = existing_ancestor(&path).to_path_buf();
+        let (_, needed) = by_filesystem
+            .entry(filesystem_id(&existing))
+            .or_insert((existing, 0));
+        *needed += bytes;
+    }
+
+    for (path, needed) in by_filesystem.into_values() {
+        let free = available(&path)?;
+        if needed > free {
+            return Err(anyhow!(
+                "Not enough free space on {}: need {}, have {}",
+                path.display(),
+                describe_size(needed),
+                describe_size(free)
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Room each saved file takes beyond its content: TAR header, padding and
+/// end-of-archive blocks, its manifest entry and blob chain metadata
+const SAVE_OVERHEAD_PER_FILE: u64 = 4096;
+
+/// Upper bound on the storage a save of `files` files totalling `bytes`
+/// takes. Already compressed content doesn't shrink and zstd may grow it a
+/// little. Each blob is then stored twice: as its own file and base64
+/// encoded, so 4/3 of its size, in the manifest. That makes about 2.4
+/// times the raw size, which is what is asked for.
+pub fn save_space_needed(files: usize, bytes: u64) -> u64 {
+    let stored = bytes
+        .saturating_add(bytes / 64)
+        .saturating_add((files as u64).saturating_mul(SAVE_OVERHEAD_PER_FILE));
+    stored.saturating_add(stored.saturating_mul(4) / 3)
+}
+
+/// `bytes` in the largest unit that keeps it at 1 or more, e.g. "1.5 GB"
+fn describe_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = None;
+    for candidate in UNITS {
+        if size < 1024.0 {
+            break;
+        }
+        size /= 1024.0;
+        unit = Some(candidate);
+    }
+    match unit {
+        Some(unit) => format!("{:.1} {}", size, unit),
+        None => format!("{} B", bytes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_needs_on_one_filesystem_add_up() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        let asked = RefCell::new(Vec::new());
+        let one_kb = |path: &Path| -> Result<u64> {
+            asked.borrow_mut().push(path.to_path_buf());
+            Ok(1024)
+        };
+
+        let needs = |a, b| [(root.join("a.conf"), a), (root.join("new").join("b.conf"), b)];
+        ensure_space(needs(512, 512), &one_kb)?;
+        assert_eq!(*asked.borrow(), [root.to_path_buf()]);
+
+        let error = ensure_space(needs(1024, 512), &one_kb).unwrap_err().to_string();
+        assert!(error.contains("need 1.5 KB, have 1.0 KB"), "{}", error);
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_errors_are_passed_on() {
+        let broken = |_: &Path| -> Result<u64> { Err(anyhow!("statvfs failed")) };
+        let needs = [(std::env::temp_dir().join("x"), 1)];
+        assert!(ensure_space(needs, &broken).is_err());
+        assert!(ensure_space(Vec::new(), &broken).is_ok());
+    }
+
+    #[test]
+    fn test_save_space_covers_blob_files_and_manifest_copies() {
+        assert_eq!(save_space_needed(0, 0), 0);
+        let mb = 1 << 20;
+        let needed = save_space_needed(1, 3 * mb);
+        assert!(needed >= 3 * mb + 4 * mb, "{}", needed);
+        assert!(needed < 3 * mb * 5 / 2, "{}", needed);
+        // Many tiny files are dominated by their per-file overhead
+        assert!(save_space_needed(1000, 1000) > 1000 * SAVE_OVERHEAD_PER_FILE);
+        assert_eq!(save_space_needed(usize::MAX, u64::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn test_describe_size() {
+        assert_eq!(describe_size(512), "512 B");
+        assert_eq!(describe_size(3 << 20), "3.0 MB");
+        assert_eq!(describe_size(5 << 30), "5.0 GB");
+    }
+}
@@ -1,9 +1,41 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct Entry {
     pub target_hint: String,  // ex: "app:zed:settings"
     pub logical_path: String, // ex: "config/zed/settings.json"
     pub blob_id: String,
     pub tar_member: Option<String>,
+    /// Path relative to the app's `app_path()` at backup time, `/`-separated
+    /// so it can be re-rooted on another OS. ex: "themes/dark.json"
+    #[serde(default)]
+    pub relative_path: Option<String>,
+    /// SHA256 of the file's own content (not the blob) at backup time, so a
+    /// deep verify can check what extraction yields. None for directory
+    /// blobs and older backups.
+    #[serde(default)]
+    pub original_sha256: Option<String>,
+    /// Size of the file's content at backup time; for directory blobs the
+    /// summed size of their files. None for older backups.
+    #[serde(default)]
+    pub original_size: Option<u64>,
+    /// Modification time of the file at backup time, in Unix seconds, so a
+    /// restore can tell whether the destination was edited since. None for
+    /// directory blobs, content not read from a file and older backups.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_mtime: Option<i64>,
+    /// Base64 of the zstd-compressed TAR of a file small enough to be kept
+    /// in the manifest instead of a blob file; `blob_id` is empty then
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inline_data: Option<String>,
+}
+
+impl Entry {
+    /// ID of the blob holding this entry's data, None for inline entries
+    pub fn stored_blob_id(&self) -> Option<&str> {
+        match self.inline_data {
+            Some(_) => None,
+            None => Some(self.blob_id.as_str()),
+        }
+    }
 }
@@ -0,0 +1,109 @@
+use std::io::{self, Write};
+
+use sha2::{Digest, Sha256};
+
+/// Writer that feeds everything written through it into a SHA256 as well,
+/// so output can be hashed while it is produced instead of in a second pass
+pub struct HashingWriter<W: Write> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> HashingWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// The inner writer and the hex SHA256 of everything written
+    pub fn finish(self) -> (W, String) {
+        (self.inner, hex::encode(self.hasher.finalize()))
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// zstd-compress `data` and hash the compressed output in the same pass.
+/// Produces exactly the bytes `zstd::encode_all` would.
+pub fn compress_and_hash(data: &[u8], level: i32) -> io::Result<(Vec<u8>, String)> {
+    let mut encoder = zstd::stream::Encoder::new(HashingWriter::new(Vec::new()), level)?;
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?.finish())
+}
+
+/// `compress_and_hash` against a trained zstd `dictionary`
+pub fn compress_and_hash_with_dictionary(
+    data: &[u8],
+    level: i32,
+    dictionary: &[u8],
+) -> io::Result<(Vec<u8>, String)> {
+    let mut encoder =
+        zstd::stream::Encoder::with_dictionary(HashingWriter::new(Vec::new()), level, dictionary)?;
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compress_and_hash_matches_two_pass() -> io::Result<()> {
+        let data = b"streaming hash test data ".repeat(1000);
+
+        let (compressed, hash) = compress_and_hash(&data, 19)?;
+
+        let two_pass = zstd::encode_all(&data[..], 19)?;
+        assert_eq!(compressed, two_pass);
+        assert_eq!(hash, hex::encode(Sha256::digest(&two_pass)));
+        Ok(())
+    }
+
+    /// CPU time of compress-then-hash vs hashing while compressing on 500MB.
+    /// Run with `cargo test --release -- --ignored bench_streaming_hash`.
+    #[test]
+    #[ignore]
+    fn bench_streaming_hash_500mb() -> io::Result<()> {
+        // Barely compressible, like already-compressed assets, so the
+        // second pass over the output is as large as it gets
+        let mut state = 0x2545f4914f6cdd1du64;
+        let data: Vec<u8> = (0..500 * 1024 * 1024)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 32) as u8 & 0x7f
+            })
+            .collect();
+
+        let start = std::time::Instant::now();
+        let compressed = zstd::encode_all(&data[..], 3)?;
+        let two_pass_hash = hex::encode(Sha256::digest(&compressed));
+        let two_pass = start.elapsed();
+
+        let start = std::time::Instant::now();
+        let (_, streamed_hash) = compress_and_hash(&data, 3)?;
+        let streamed = start.elapsed();
+
+        assert_eq!(two_pass_hash, streamed_hash);
+        println!(
+            "500MB -> {}MB compressed: two-pass {:?}, streaming {:?}",
+            compressed.len() / 1024 / 1024,
+            two_pass,
+            streamed
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, Result};
+use sha2::{Digest, Sha256};
+
+use crate::storage::{
+    atomic::write_atomic,
+    manifest::Manifest,
+    pack,
+    pool::{self, POOL_DIR},
+};
+
+fn is_intact(bytes: &[u8], expected_sha256: &str) -> bool {
+    hex::encode(Sha256::digest(bytes)) == expected_sha256
+}
+
+/// Repair blob `blob_id` of `backup` from an identical copy in another backup
+pub fn heal_blob(backup: &str, blob_id: &str) -> Result<bool> {
+    let storage_dir = Manifest::base_storage_dir()?;
+    heal_blob_in(&storage_dir, &Manifest::load_in(&storage_dir, backup)?, blob_id)
+}
+
+/// If the stored copy of `blob_id` is missing or doesn't hash to the
+/// manifest's `sha256`, look for an intact blob with the same ID in the
+/// blob pool and the other backups (content-addressed IDs make dedup copies
+/// identical) and write it into this backup's blob directory as a loose
+/// file, which takes precedence over a corrupted packed or pooled copy.
+///
+/// Returns whether the blob is intact afterwards: true when it was healed
+/// or had nothing to heal, false when no intact copy exists anywhere.
+pub fn heal_blob_in(storage_dir: &Path, manifest: &Manifest, blob_id: &str) -> Result<bool> {
+    let expected = manifest
+        .blobs
+        .get(blob_id)
+        .ok_or_else(|| anyhow!("Blob {} is not in backup '{}'", blob_id, manifest.name))?
+        .get_sha256()
+        .to_string();
+
+    let own_dir = storage_dir.join(&manifest.name).join("blobs");
+    let pool_dir = pool::pool_dir(storage_dir);
+    // The copy readers get: the backup's own, else the pooled one
+    let current =
+        pack::read_blob(&own_dir, blob_id).or_else(|_| pack::read_blob(&pool_dir, blob_id));
+    if current.is_ok_and(|bytes| is_intact(&bytes, &expected)) {
+        return Ok(true);
+    }
+
+    let sources = fs::read_dir(storage_dir)?
+        .filter_map(|dir| dir.ok())
+        .map(|dir| (dir.path().join("blobs"), dir.file_name()));
+    for (blob_dir, source) in std::iter::once((pool_dir.clone(), POOL_DIR.into())).chain(sources) {
+        if blob_dir == own_dir {
+            continue;
+        }
+        let Ok(bytes) = pack::read_blob(&blob_dir, blob_id) else {
+            continue;
+        };
+        if is_intact(&bytes, &expected) {
+            fs::create_dir_all(&own_dir)?;
+            write_atomic(&own_dir.join(format!("{}.tar.zst", blob_id)), &bytes)?;
+            println!(
+                "Healed blob {} of '{}' from {}",
+                blob_id,
+                manifest.name,
+                source.to_string_lossy()
+            );
+            return Ok(true);
+        }
+    }
+
+    println!("No intact copy of blob {} found to heal '{}'", blob_id, manifest.name);
+    Ok(false)
+}
@@ -0,0 +1,193 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::storage::{
+    blob_chain::BlobChainManager, entry::Entry, manifest::Manifest, pack::PackIndex, pool,
+};
+
+/// Health of a single backup directory
+#[derive(Serialize, Debug, Clone)]
+pub struct BackupHealth {
+    pub name: String,
+    pub manifest_ok: bool,
+    /// Blob IDs referenced by entries with no blob file on disk
+    pub missing_blobs: Vec<String>,
+    pub chain_ok: bool,
+    pub errors: Vec<String>,
+}
+
+impl BackupHealth {
+    pub fn is_healthy(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Result of `health_check`: storage-level status plus one entry per backup
+#[derive(Serialize, Debug, Clone)]
+pub struct HealthReport {
+    pub storage_dir: String,
+    pub writable: bool,
+    pub backups: Vec<BackupHealth>,
+}
+
+impl HealthReport {
+    pub fn is_healthy(&self) -> bool {
+        self.writable && self.backups.iter().all(|b| b.is_healthy())
+    }
+}
+
+/// Validate the storage directory and every backup in it
+pub fn health_check() -> Result<HealthReport> {
+    health_check_in(&Manifest::base_storage_dir()?)
+}
+
+pub fn health_check_in(storage_dir: &Path) -> Result<HealthReport> {
+    let writable = is_writable(storage_dir);
+
+    let mut backup_names = Vec::new();
+    if storage_dir.is_dir() {
+        for entry in fs::read_dir(storage_dir)? {
+            let entry = entry?;
+            if entry.path().join("manifest.json").exists() {
+                backup_names.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+    }
+    backup_names.sort();
+
+    // Entries deduplicated globally may point at blobs stored by another
+    // backup, and pooled blobs live outside every backup
+    let blob_dirs: Vec<(PathBuf, PackIndex)> = backup_names
+        .iter()
+        .map(|name| storage_dir.join(name).join("blobs"))
+        .chain(std::iter::once(pool::pool_dir(storage_dir)))
+        .map(|blob_dir| {
+            let index = PackIndex::load(&blob_dir).unwrap_or_default();
+            (blob_dir, index)
+        })
+        .collect();
+
+    let backups = backup_names
+        .par_iter()
+        .map(|name| check_backup(storage_dir, name, &blob_dirs))
+        .collect();
+
+    Ok(HealthReport {
+        storage_dir: storage_dir.to_string_lossy().into_owned(),
+        writable,
+        backups,
+    })
+}
+
+fn is_writable(storage_dir: &Path) -> bool {
+    let probe = storage_dir.join(".health_check");
+    let ok = fs::create_dir_all(storage_dir).is_ok() && fs::write(&probe, b"ok").is_ok();
+    let _ = fs::remove_file(&probe);
+    ok
+}
+
+fn check_backup(
+    storage_dir: &Path,
+    name: &str,
+    blob_dirs: &[(PathBuf, PackIndex)],
+) -> BackupHealth {
+    let mut health = BackupHealth {
+        name: name.to_string(),
+        manifest_ok: false,
+        missing_blobs: Vec::new(),
+        chain_ok: false,
+        errors: Vec::new(),
+    };
+
+    let manifest_path = storage_dir.join(name).join("manifest.json");
+    let manifest: Manifest = match fs::read_to_string(&manifest_path)
+        .map_err(anyhow::Error::from)
+        .and_then(|content| Ok(serde_json::from_str(&content)?))
+    {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            health.errors.push(format!("manifest.json is unreadable: {}", e));
+            return health;
+        }
+    };
+    health.manifest_ok = true;
+
+    let mut seen = HashSet::new();
+    for blob_id in manifest.entries.iter().filter_map(Entry::stored_blob_id) {
+        if !seen.insert(blob_id) {
+            continue;
+        }
+        let exists = blob_dirs.iter().any(|(dir, index)| index.has_blob(dir, blob_id));
+        if !exists {
+            health.missing_blobs.push(blob_id.to_string());
+        }
+    }
+    if !health.missing_blobs.is_empty() {
+        health.errors.push(format!(
+            "{} referenced blob file(s) missing",
+            health.missing_blobs.len()
+        ));
+    }
+
+    // Backups without blobs never get chain metadata written
+    if manifest.blobs.is_empty() {
+        health.chain_ok = true;
+    } else {
+        match BlobChainManager::open_existing(storage_dir.to_path_buf(), name.to_string()) {
+            Ok(_) => health.chain_ok = true,
+            Err(e) => health.errors.push(format!("Blob chain metadata unusable: {}", e)),
+        }
+    }
+
+    health
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_manifest(storage_dir: &Path, manifest: &Manifest) -> Result<()> {
+        let backup_dir = storage_dir.join(&manifest.name);
+        fs::create_dir_all(&backup_dir)?;
+        fs::write(backup_dir.join("manifest.json"), serde_json::to_string(manifest)?)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_health_check_reports_per_backup_problems() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let storage_dir = temp_dir.path();
+
+        write_manifest(storage_dir, &Manifest::empty("clean".to_string()))?;
+
+        let mut missing = Manifest::empty("missing-blob".to_string());
+        missing.entries.push(Entry {
+            blob_id: "gone".to_string(),
+            target_hint: "app:test".to_string(),
+            logical_path: "/cfg/a.conf".to_string(),
+            ..Default::default()
+        });
+        write_manifest(storage_dir, &missing)?;
+
+        fs::create_dir_all(storage_dir.join("corrupt"))?;
+        fs::write(storage_dir.join("corrupt").join("manifest.json"), b"{not json")?;
+
+        let report = health_check_in(storage_dir)?;
+        assert!(report.writable);
+        assert!(!report.is_healthy());
+
+        let by_name = |name: &str| report.backups.iter().find(|b| b.name == name).unwrap();
+        assert!(by_name("clean").is_healthy());
+        assert_eq!(by_name("missing-blob").missing_blobs, vec!["gone".to_string()]);
+        assert!(!by_name("corrupt").manifest_ok);
+        Ok(())
+    }
+}
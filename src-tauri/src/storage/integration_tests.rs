@@ -22,86 +22,44 @@ mod integration_tests {
         test_file2.flush()?;
         test_file3.flush()?;
 
-        // Create a manifest and add files as blobs (simulating the full process)
-        let mut manifest = Manifest::new(
-            "integration-test-backup".to_string(),
-            chrono::Utc::now().to_rfc3339(),
-            "linux".to_string(),
-        );
-
-        // This would normally be done by create_blob_from_file, but we simulate it here
-        // to avoid filesystem dependencies in the test
-        
-        // Simulate adding 3 files as blobs with proper blockchain linking
-        let blob_data1 = b"compressed_tar_data_file1";
-        let blob_data2 = b"compressed_tar_data_file2";
-        let blob_data3 = b"compressed_tar_data_file3";
-
-        let mut blob1 = crate::storage::blobs::BlobPayload::new("tar.zst".to_string(), blob_data1);
-        let mut blob2 = crate::storage::blobs::BlobPayload::new("tar.zst".to_string(), blob_data2);
-        let mut blob3 = crate::storage::blobs::BlobPayload::new("tar.zst".to_string(), blob_data3);
-
-        // Set up blob chain manager for test backup
-        let mut chain_manager = BlobChainManager::new(storage_dir.to_path_buf(), "test_backup".to_string())?;
-
-        // Add blobs to the chain in order
-        chain_manager.add_blob_to_chain("blob1", &mut blob1)?;
-        chain_manager.add_blob_to_chain("blob2", &mut blob2)?;
-        chain_manager.add_blob_to_chain("blob3", &mut blob3)?;
-
-        // Add blobs to manifest
-        manifest.add_blob_for_testing("blob1".to_string(), blob1);
-        manifest.add_blob_for_testing("blob2".to_string(), blob2);
-        manifest.add_blob_for_testing("blob3".to_string(), blob3);
-
-        // Add corresponding entries
-        manifest.entries.push(crate::storage::entry::Entry {
-            blob_id: "blob1".to_string(),
-            target_hint: "app:test1".to_string(),
-            logical_path: "/test/file1.conf".to_string(),
-            tar_member: Some("file1.conf".to_string()),
-        });
-
-        manifest.entries.push(crate::storage::entry::Entry {
-            blob_id: "blob2".to_string(),
-            target_hint: "app:test2".to_string(),
-            logical_path: "/test/file2.conf".to_string(),
-            tar_member: Some("file2.conf".to_string()),
-        });
-
-        manifest.entries.push(crate::storage::entry::Entry {
-            blob_id: "blob3".to_string(),
-            target_hint: "app:test3".to_string(),
-            logical_path: "/test/file3.conf".to_string(),
-            tar_member: Some("file3.conf".to_string()),
-        });
+        // Back the files up through the real save path, kept in storage_dir
+        let mut manifest = Manifest::new_in("integration-test-backup".to_string(), storage_dir);
+        manifest.set_inline_threshold(0);
+        let files = [(&test_file1, "app:test1"), (&test_file2, "app:test2"), (&test_file3, "app:test3")];
+        for (file, hint) in files {
+            manifest.create_blob_from_file(file.path(), hint, None)?;
+        }
+        manifest.save()?;
+        let manifest = Manifest::new_in("integration-test-backup".to_string(), storage_dir).load()?;
+        assert_eq!(manifest.entries.len(), 3);
+        assert!(storage_dir.join("integration-test-backup").join("manifest.json").exists());
 
         // Verify blob chain integrity
-        let integrity_check = manifest.verify_blob_chain_integrity_with_dir(Some(storage_dir.to_path_buf()))?;
+        let integrity_check = manifest.verify_blob_chain_integrity()?;
         assert!(integrity_check, "Blob chain integrity verification should pass");
 
         // Get blob chain info
-        let chain_info = manifest.get_blob_chain_info_with_dir(Some(storage_dir.to_path_buf()))?;
+        let chain_info = manifest.get_blob_chain_info()?;
         assert!(chain_info.contains("3 blobs"), "Chain info should mention 3 blobs: {}", chain_info);
         assert!(chain_info.contains("integrity hash"), "Chain info should mention integrity hash: {}", chain_info);
 
         // Verify that the encrypted metadata file was created
-        let metadata_file = storage_dir.join("test_backup_blob_chain.encrypted");
+        let metadata_file = storage_dir.join("integration-test-backup_blob_chain.encrypted");
         assert!(metadata_file.exists(), "Encrypted blockchain metadata file should exist");
 
         // Create a new chain manager to verify persistence
-        let chain_manager2 = BlobChainManager::new(storage_dir.to_path_buf(), "test_backup".to_string())?;
+        let chain_manager2 = BlobChainManager::new(storage_dir.to_path_buf(), "integration-test-backup".to_string())?;
         let chain_metadata = chain_manager2.get_chain_info();
         assert_eq!(chain_metadata.chain_order.len(), 3, "Chain should have 3 blobs after loading from disk");
-        assert_eq!(chain_metadata.chain_order[0], "blob1", "First blob should be blob1");
-        assert_eq!(chain_metadata.chain_order[1], "blob2", "Second blob should be blob2");
-        assert_eq!(chain_metadata.chain_order[2], "blob3", "Third blob should be blob3");
+        let blob_ids: Vec<&String> = manifest.entries.iter().map(|e| &e.blob_id).collect();
+        let chain_order: Vec<&String> = chain_metadata.chain_order.iter().collect();
+        assert_eq!(chain_order, blob_ids, "Chain should follow the order the files were added in");
 
         // Verify that the metadata integrity is preserved
         assert!(chain_metadata.verify_integrity(), "Chain metadata integrity should be preserved");
 
         println!("✅ Complete blob chain workflow test passed!");
-        println!("   - Created 3 blobs with proper blockchain linking");
+        println!("   - Created 3 blobs through create_blob_from_file");
         println!("   - Verified blob chain integrity");
         println!("   - Confirmed encrypted metadata storage");
         println!("   - Verified persistence across manager instances");
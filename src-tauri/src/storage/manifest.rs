@@ -1,7 +1,8 @@
 use rayon::prelude::*;
 use std::io::Write;
 use std::{
-    collections::HashMap,
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, HashSet},
     fs,
     io::Read,
     path::{Path, PathBuf},
@@ -9,19 +10,118 @@ use std::{
 };
 
 use anyhow::{anyhow, Context, Ok};
+use base64::{engine::general_purpose, Engine};
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use tar::Builder;
 use walkdir::WalkDir;
 use zstd::encode_all;
 
+use crate::messages;
+use crate::settings::{DedupScope, PerformanceProfile};
 use crate::storage::{
-    blob_chain::BlobChainManager,
+    atomic::{write_atomic, write_atomic_with},
+    blob_chain::{BlobChainManager, ChainLink},
     blobs::BlobPayload,
+    codec::{self, Codec},
+    crypto::{self, ENCRYPTED_SUFFIX},
+    dedup_index, dictionary,
     entry::Entry,
-    performance::{MemoryOperation, WorkComplexity, PERFORMANCE_CONFIG, PERFORMANCE_METRICS},
+    hashing::{compress_and_hash, compress_and_hash_with_dictionary, HashingWriter},
+    pack::{self, PackStats},
+    paths::{join_relative, relative_to},
+    performance::{
+        MemoryOperation, PerformanceConfig, WorkComplexity, PERFORMANCE_CONFIG, PERFORMANCE_METRICS,
+    },
+    pool, refcount,
 };
 
+/// State of a backup's blob chain and blob directory before a save, see
+/// `Manifest::save_checkpoint`
+pub struct SaveCheckpoint {
+    chain_path: PathBuf,
+    /// None when the backup had no chain yet
+    chain: Option<Vec<u8>>,
+    blob_dir: PathBuf,
+    blob_files: HashSet<std::ffi::OsString>,
+}
+
+/// Names of the files directly in `dir`; empty when it doesn't exist
+fn file_names_in(dir: &Path) -> Result<HashSet<std::ffi::OsString>, anyhow::Error> {
+    if !dir.is_dir() {
+        return Ok(HashSet::new());
+    }
+    let mut names = HashSet::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            names.insert(entry.file_name());
+        }
+    }
+    Ok(names)
+}
+
+/// `ProjectDirs` names of the storage directory; organization and
+/// application can be overridden with `SAVEME_ORG` and `SAVEME_APP`
+const STORAGE_QUALIFIER: &str = "com";
+const DEFAULT_STORAGE_ORG: &str = "saveme";
+const DEFAULT_STORAGE_APP: &str = "saveme-config";
+
+/// Where early builds kept their data, under a placeholder organization
+const LEGACY_STORAGE_NAMES: (&str, &str, &str) = ("com", "you", "saveconfig");
+
+/// Resolved once per process, since resolving may migrate legacy data
+static STORAGE_DIR: Lazy<Option<PathBuf>> = Lazy::new(|| {
+    // Unit tests get a throwaway directory instead of the user's backups
+    if cfg!(test) {
+        return Some(std::env::temp_dir().join(format!("saveme-tests-{}", std::process::id())));
+    }
+    let org = std::env::var("SAVEME_ORG").unwrap_or_else(|_| DEFAULT_STORAGE_ORG.to_string());
+    let app = std::env::var("SAVEME_APP").unwrap_or_else(|_| DEFAULT_STORAGE_APP.to_string());
+    let current = directories::ProjectDirs::from(STORAGE_QUALIFIER, &org, &app)?
+        .data_local_dir()
+        .to_path_buf();
+    let (qualifier, legacy_org, legacy_app) = LEGACY_STORAGE_NAMES;
+    match directories::ProjectDirs::from(qualifier, legacy_org, legacy_app) {
+        Some(legacy) => Some(migrate_legacy_storage(legacy.data_local_dir(), &current)),
+        None => Some(current),
+    }
+});
+
+/// Move `legacy` to `current` when only the legacy directory exists, and
+/// return the directory to use. If the move fails the legacy directory keeps
+/// being used so existing backups don't disappear. Reports go to stderr,
+/// as this runs before CLI commands that write their output to stdout.
+pub(crate) fn migrate_legacy_storage(legacy: &Path, current: &Path) -> PathBuf {
+    if legacy == current || current.exists() || !legacy.is_dir() {
+        return current.to_path_buf();
+    }
+    let moved = current
+        .parent()
+        .map_or(std::result::Result::Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::rename(legacy, current));
+    match moved {
+        std::result::Result::Ok(()) => {
+            eprintln!(
+                "Moved backups from {} to {}",
+                legacy.display(),
+                current.display()
+            );
+            current.to_path_buf()
+        }
+        Err(e) => {
+            eprintln!(
+                "Could not move backups from {} to {} ({}); still using the old location",
+                legacy.display(),
+                current.display(),
+                e
+            );
+            legacy.to_path_buf()
+        }
+    }
+}
+
 /// Thread pool configuration for optimal performance
 static THREAD_POOL_INIT: std::sync::Once = std::sync::Once::new();
 
@@ -32,6 +132,29 @@ const HUGE_FILE_THRESHOLD: usize = 50_000_000; // 50MB
 const PARALLEL_BATCH_SIZE: usize = 100; // Max files per batch
 const COMPRESSION_BUFFER_SIZE: usize = 1024 * 1024; // 1MB buffer
 
+/// Version of this build, stamped into every manifest it saves
+pub fn get_crate_version() -> &'static str {
+    env!("CARGO_PKG_VERSION")
+}
+
+fn unknown_version() -> String {
+    "unknown".to_string()
+}
+
+/// Whether `version` is a newer major or minor release than this build, so
+/// its manifests may use fields this build doesn't know. Patch releases and
+/// unparseable versions (like `unknown`) don't count.
+pub fn is_much_newer_version(version: &str) -> bool {
+    let major_minor = |version: &str| -> Option<(u64, u64)> {
+        let mut parts = version.split(['.', '-', '+']);
+        Some((parts.next()?.parse().ok()?, parts.next()?.parse().ok()?))
+    };
+    match (major_minor(version), major_minor(get_crate_version())) {
+        (Some(theirs), Some(ours)) => theirs > ours,
+        _ => false,
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Manifest {
     pub name: String,
@@ -39,6 +162,88 @@ pub struct Manifest {
     pub os_source: String,
     pub entries: Vec<Entry>,
     pub blobs: HashMap<String, BlobPayload>,
+    /// Name of the backup this one follows in the backup-level chain
+    #[serde(default)]
+    pub previous_backup: Option<String>,
+    /// `calculate_backup_hash()` of `previous_backup`, captured at link time
+    #[serde(default)]
+    pub previous_backup_hash: Option<String>,
+    /// When this backup was last restored successfully. Bookkeeping only:
+    /// not part of `calculate_backup_hash()`, so updating it never breaks
+    /// chain links pointing at this backup.
+    #[serde(default)]
+    pub last_restored_at: Option<String>,
+    /// SHA256 over `previous_backup_hash` and `calculate_backup_hash()`,
+    /// tying this backup to its position in the chain. Recomputed on every
+    /// save; `None` in manifests saved before it existed.
+    #[serde(default)]
+    pub backup_chain_hash: Option<String>,
+    /// Compression level override used for the last save, `None` when the
+    /// global `PERFORMANCE_CONFIG` level applied
+    #[serde(default)]
+    pub compression_profile: Option<i32>,
+    /// Machine identity this backup belongs to ("desktop", "laptop"), so
+    /// per-machine variants of the same apps can coexist
+    #[serde(default)]
+    pub machine_profile: Option<String>,
+    /// Stored size of the blobs this backup holds itself (borrowed blobs are
+    /// counted by the backup owning them). Refreshed on every save; 0 in
+    /// older manifests until they are saved again.
+    #[serde(default)]
+    pub total_blob_bytes: u64,
+    /// Size of the backed-up files before compression, borrowed blobs
+    /// included. Entries from before sizes were recorded count as 0.
+    #[serde(default)]
+    pub total_logical_bytes: u64,
+    /// Number of entries, refreshed with the size totals so list views don't
+    /// need the entries themselves; 0 in older manifests until saved again
+    #[serde(default)]
+    pub entry_count: usize,
+    /// Entries per `target_hint`, refreshed along with `entry_count`
+    #[serde(default)]
+    pub entry_counts_by_hint: BTreeMap<String, usize>,
+    /// ID of the zstd dictionary trained for this backup (stored under
+    /// `dictionaries/` in the backup directory), if one was requested
+    #[serde(default)]
+    pub compression_dictionary: Option<String>,
+    /// `get_crate_version()` of the build that last saved this backup;
+    /// `unknown` in manifests from before it was recorded
+    #[serde(default = "unknown_version")]
+    pub created_by_version: String,
+    /// Content of `compression_dictionary` while blobs are being created
+    #[serde(skip)]
+    dictionary_bytes: Option<Vec<u8>>,
+    /// Blob files buffered by `batch_blob_writes`, appended to the pack on save
+    #[serde(skip)]
+    pending_blob_writes: Vec<(String, Vec<u8>)>,
+    /// Preset picked by `select_performance_profile` for the current save,
+    /// used instead of the global `PERFORMANCE_CONFIG`
+    #[serde(skip)]
+    performance_config: Option<PerformanceConfig>,
+    /// `inline_threshold` override for saves on this manifest only
+    #[serde(skip)]
+    inline_threshold: Option<usize>,
+    /// Storage directory used instead of the global one (see `new_in`)
+    #[serde(skip)]
+    storage_dir: Option<PathBuf>,
+}
+
+/// A `logical_path` present in both merged backups
+#[derive(Serialize, Debug, Clone)]
+pub struct MergeConflict {
+    pub logical_path: String,
+    /// Backup whose entry was kept (the newer one)
+    pub kept_from: String,
+    pub dropped_from: String,
+}
+
+/// Outcome of `Manifest::merge_backups`
+#[derive(Serialize, Debug, Clone)]
+pub struct MergeReport {
+    pub name: String,
+    pub entry_count: usize,
+    pub blob_count: usize,
+    pub conflicts: Vec<MergeConflict>,
 }
 
 #[derive(Debug, Clone)]
@@ -49,6 +254,21 @@ pub struct EstimatedPerformance {
     pub memory_usage_mb: usize,
 }
 
+/// SHA256 of the content of the first member of an uncompressed TAR
+fn first_member_sha256(tar_data: &[u8]) -> Option<String> {
+    let mut archive = tar::Archive::new(tar_data);
+    let mut member = archive.entries().ok()?.next()?.ok()?;
+    let mut content = Vec::new();
+    member.read_to_end(&mut content).ok()?;
+    Some(hex::encode(Sha256::digest(&content)))
+}
+
+/// Modification time of `path` in Unix seconds, None if it can't be read
+fn mtime_of(path: &Path) -> Option<i64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    Some(chrono::DateTime::<chrono::Utc>::from(modified).timestamp())
+}
+
 impl Manifest {
     /// Initialize optimized thread pool for file operations
     fn init_thread_pool() {
@@ -56,17 +276,21 @@ impl Manifest {
             let config = &*PERFORMANCE_CONFIG;
             let stack_size = 8 * 1024 * 1024; // 8MB stack size for large operations
 
-            rayon::ThreadPoolBuilder::new()
+            let result = rayon::ThreadPoolBuilder::new()
                 .num_threads(config.thread_count)
                 .stack_size(stack_size)
                 .thread_name(|index| format!("saveme-worker-{}", index))
-                .build_global()
-                .expect("Failed to initialize thread pool");
-
-            println!(
-                "Initialized optimized thread pool with {} workers (max memory: {}MB)",
-                config.thread_count, config.max_memory_mb
-            );
+                .build_global();
+
+            // Any earlier parallel operation (e.g. chain verification) already
+            // started rayon's default pool; keep using it instead of panicking
+            match result {
+                std::result::Result::Ok(()) => println!(
+                    "Initialized optimized thread pool with {} workers (max memory: {}MB)",
+                    config.thread_count, config.max_memory_mb
+                ),
+                Err(e) => println!("Using existing global thread pool: {}", e),
+            }
         });
     }
 
@@ -77,9 +301,73 @@ impl Manifest {
         optimal_size.max(min_chunk_size).min(total_size / 2)
     }
 
-    /// Memory-efficient compression with adaptive strategy
-    fn adaptive_compress(data: &[u8]) -> Result<Vec<u8>, anyhow::Error> {
-        let config = &*PERFORMANCE_CONFIG;
+    /// Override the compression level for saves on this manifest only,
+    /// leaving the global profile untouched. `None` keeps the level an
+    /// earlier save recorded, so resaving without an override doesn't drop it.
+    pub fn set_compression_override(&mut self, level: Option<i32>) -> Result<(), anyhow::Error> {
+        let Some(level) = level else {
+            return Ok(());
+        };
+        if !(1..=22).contains(&level) {
+            return Err(anyhow!("Compression level must be between 1 and 22, got {}", level));
+        }
+        self.compression_profile = Some(level);
+        Ok(())
+    }
+
+    /// Override the size under which files are kept inline in their entry
+    /// for saves on this manifest only; 0 stores every file as a blob
+    pub fn set_inline_threshold(&mut self, threshold: usize) {
+        self.inline_threshold = Some(threshold);
+    }
+
+    /// Compress this save with `requested`, resolving `Auto` from the
+    /// estimated `total_size_mb`; logs and returns the profile used
+    pub fn select_performance_profile(
+        &mut self,
+        requested: PerformanceProfile,
+        total_size_mb: f64,
+    ) -> PerformanceProfile {
+        let (profile, reason) = PerformanceConfig::resolve_profile(requested, total_size_mb);
+        println!("Using {:?} performance profile ({})", profile, reason);
+        self.performance_config = Some(PerformanceConfig::for_profile(profile));
+        profile
+    }
+
+    /// Configuration to compress with: the selected performance profile (or
+    /// the global one), scoped to this manifest's compression and inline
+    /// overrides if it has them. An explicit level is used as-is, without adaptive lowering
+    /// for large files.
+    fn compression_config(&self) -> PerformanceConfig {
+        let base = self
+            .performance_config
+            .clone()
+            .unwrap_or_else(|| PERFORMANCE_CONFIG.clone());
+        let config = match self
+            .compression_profile
+            .map(|level| base.with_compression_level(level))
+        {
+            Some(std::result::Result::Ok(config)) => PerformanceConfig {
+                adaptive_compression: false,
+                ..config
+            },
+            _ => base,
+        };
+        match self.inline_threshold {
+            Some(inline_threshold) => PerformanceConfig {
+                inline_threshold,
+                ..config
+            },
+            None => config,
+        }
+    }
+
+    /// Memory-efficient compression with adaptive strategy. Returns the
+    /// compressed bytes and their SHA256, computed in the same pass.
+    fn adaptive_compress(
+        config: &PerformanceConfig,
+        data: &[u8],
+    ) -> Result<(Vec<u8>, String), anyhow::Error> {
         let size = data.len();
         let level = config.get_adaptive_compression_level(size);
 
@@ -99,7 +387,8 @@ impl Manifest {
 
         // Track performance metrics
         let start = Instant::now();
-        let result = encode_all(data, level).map_err(|e| anyhow!("Compression failed: {}", e))?;
+        let result =
+            compress_and_hash(data, level).map_err(|e| anyhow!("Compression failed: {}", e))?;
 
         PERFORMANCE_METRICS.add_bytes_compressed(size);
         PERFORMANCE_METRICS.add_compression_time(start.elapsed().as_millis() as usize);
@@ -107,6 +396,111 @@ impl Manifest {
         Ok(result)
     }
 
+    /// Join independently compressed chunks, hashing them on the way
+    fn concat_and_hash(chunks: Vec<Vec<u8>>) -> Result<(Vec<u8>, String), anyhow::Error> {
+        let total = chunks.iter().map(Vec::len).sum();
+        let mut writer = HashingWriter::new(Vec::with_capacity(total));
+        for chunk in chunks {
+            writer.write_all(&chunk)?;
+        }
+        Ok(writer.finish())
+    }
+
+    /// Compress new blobs of this backup against its zstd dictionary,
+    /// training one over `sample_paths` (the files about to be backed up)
+    /// when the backup has none yet. Returns false, leaving compression as
+    /// it was, when there are too few small files to train on.
+    pub fn use_compression_dictionary(
+        &mut self,
+        sample_paths: &[PathBuf],
+    ) -> Result<bool, anyhow::Error> {
+        let storage_dir = self.storage_root()?;
+        if let Some(id) = &self.compression_dictionary {
+            self.dictionary_bytes = Some(dictionary::load(&storage_dir, &self.name, id)?);
+            return Ok(true);
+        }
+
+        let trained = match dictionary::train(&dictionary::samples_from_files(sample_paths)) {
+            std::result::Result::Ok(trained) => trained,
+            Err(e) => {
+                log::info!("Not using a compression dictionary: {}", e);
+                return Ok(false);
+            }
+        };
+        let id = dictionary::write(&storage_dir.join(&self.name), &trained)?;
+        log::info!("Trained a {} byte compression dictionary ({})", trained.len(), id);
+        self.compression_dictionary = Some(id);
+        self.dictionary_bytes = Some(trained);
+        Ok(true)
+    }
+
+    /// Use `bytes` as the content of `compression_dictionary` instead of
+    /// loading it from storage, for manifests that aren't stored locally
+    pub(crate) fn set_dictionary_bytes(&mut self, bytes: Vec<u8>) {
+        self.dictionary_bytes = Some(bytes);
+    }
+
+    /// `adaptive_compress` against the backup's dictionary when one is in
+    /// use; the dictionary ID is returned alongside for the blob to record
+    fn compress_with_dictionary(
+        &self,
+        config: &PerformanceConfig,
+        data: &[u8],
+    ) -> Result<(Vec<u8>, String, Option<String>), anyhow::Error> {
+        let (Some(id), Some(dictionary)) = (&self.compression_dictionary, &self.dictionary_bytes)
+        else {
+            let (compressed, hash) = Self::adaptive_compress(config, data)?;
+            return Ok((compressed, hash, None));
+        };
+
+        let start = Instant::now();
+        let level = config.get_adaptive_compression_level(data.len());
+        let (compressed, hash) = compress_and_hash_with_dictionary(data, level, dictionary)
+            .map_err(|e| anyhow!("Compression failed: {}", e))?;
+        PERFORMANCE_METRICS.add_bytes_compressed(data.len());
+        PERFORMANCE_METRICS.add_compression_time(start.elapsed().as_millis() as usize);
+        Ok((compressed, hash, Some(id.clone())))
+    }
+
+    /// Encrypt a compressed tarball of `format` when blob encryption is
+    /// enabled in the settings, returning the stored bytes, their format
+    /// (`.enc` appended when sealed) and their SHA256. Sealed bytes are
+    /// rehashed, and every seal uses a fresh nonce, so sealed blobs are never
+    /// deduplicated.
+    /// Compress `tar_data` with the codec the `codec_selection` setting picks
+    /// for it, returning that codec, the compressed bytes and their SHA256.
+    /// None when the pick is zstd, which every caller compresses its own way
+    /// (chunked, with a dictionary, ...). Only blobs large enough to pay for
+    /// the trials are considered.
+    fn compress_with_selected_codec(
+        tar_data: &[u8],
+        zstd_level: i32,
+    ) -> Result<Option<(Codec, Vec<u8>, String)>, anyhow::Error> {
+        let selection = crate::settings::current().codec_selection;
+        let codec = codec::select_codec(tar_data, zstd_level, selection);
+        if codec == Codec::Zstd {
+            return Ok(None);
+        }
+        println!("Auto codec selection picked {:?}", codec);
+        let compressed = codec.compress(tar_data, zstd_level)?;
+        let hash = hex::encode(Sha256::digest(&compressed));
+        Ok(Some((codec, compressed, hash)))
+    }
+
+    fn seal_blob(
+        compressed: Vec<u8>,
+        compressed_hash: String,
+        format: &str,
+    ) -> Result<(Vec<u8>, String, String), anyhow::Error> {
+        if crate::settings::current().encrypt_blobs {
+            let sealed = crypto::seal(&compressed)?;
+            let sealed_hash = hex::encode(Sha256::digest(&sealed));
+            Ok((sealed, format!("{}{}", format, ENCRYPTED_SUFFIX), sealed_hash))
+        } else {
+            Ok((compressed, format.to_string(), compressed_hash))
+        }
+    }
+
     pub fn new(name: String, created_at: String, os_source: String) -> Self {
         Self::init_thread_pool();
         Self {
@@ -115,9 +509,36 @@ impl Manifest {
             os_source,
             entries: Vec::new(),
             blobs: HashMap::new(),
+            previous_backup: None,
+            previous_backup_hash: None,
+            last_restored_at: None,
+            backup_chain_hash: None,
+            compression_profile: None,
+            machine_profile: None,
+            total_blob_bytes: 0,
+            total_logical_bytes: 0,
+            entry_count: 0,
+            entry_counts_by_hint: BTreeMap::new(),
+            compression_dictionary: None,
+            created_by_version: get_crate_version().to_string(),
+            dictionary_bytes: None,
+            pending_blob_writes: Vec::new(),
+            performance_config: None,
+            inline_threshold: None,
+            storage_dir: None,
         }
     }
 
+    /// A new backup kept in `storage_dir` rather than the global storage
+    /// directory: saving, loading, blob files and the blob chain all go
+    /// there, so tests can run the real save path hermetically
+    pub fn new_in(name: String, storage_dir: &Path) -> Self {
+        let created_at = chrono::Utc::now().to_rfc3339();
+        let mut manifest = Self::new(name, created_at, std::env::consts::OS.to_string());
+        manifest.storage_dir = Some(storage_dir.to_path_buf());
+        manifest
+    }
+
     pub fn empty(name: String) -> Self {
         Self {
             name,
@@ -125,13 +546,41 @@ impl Manifest {
             os_source: "".to_string(),
             entries: Vec::new(),
             blobs: HashMap::new(),
+            previous_backup: None,
+            previous_backup_hash: None,
+            last_restored_at: None,
+            backup_chain_hash: None,
+            compression_profile: None,
+            machine_profile: None,
+            total_blob_bytes: 0,
+            total_logical_bytes: 0,
+            entry_count: 0,
+            entry_counts_by_hint: BTreeMap::new(),
+            compression_dictionary: None,
+            created_by_version: unknown_version(),
+            dictionary_bytes: None,
+            pending_blob_writes: Vec::new(),
+            performance_config: None,
+            inline_threshold: None,
+            storage_dir: None,
         }
     }
 
+    /// `created_at` as a timestamp; None for legacy or hand-edited manifests
+    pub fn created_at_datetime(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        chrono::DateTime::parse_from_rfc3339(&self.created_at)
+            .ok()
+            .map(|t| t.with_timezone(&chrono::Utc))
+    }
+
+    /// Per-user data directory holding every backup. Organization and
+    /// application names come from `SAVEME_ORG` / `SAVEME_APP` when set, so
+    /// forks and white-labeled builds get their own location. Data left in
+    /// the old `com.you.saveconfig` location is moved over on first use.
     pub fn base_storage_dir() -> Result<PathBuf, anyhow::Error> {
-        let proj = directories::ProjectDirs::from("com", "you", "saveconfig")
-            .ok_or_else(|| anyhow!("cannot get project dir"))?;
-        Ok(proj.data_local_dir().to_path_buf())
+        STORAGE_DIR
+            .clone()
+            .ok_or_else(|| anyhow!("cannot get project dir"))
     }
 
     pub fn load_from(name: &str) -> Result<Self, anyhow::Error> {
@@ -140,24 +589,211 @@ impl Manifest {
     }
 
     pub fn load(&self) -> Result<Self, anyhow::Error> {
-        let manifest_path = Self::base_storage_dir()?
+        let manifest_path = self.storage_root()?
             .join(&self.name)
             .join("manifest.json");
         let content = fs::read_to_string(manifest_path)?;
         let mut manifest: Manifest = serde_json::from_str(&content)?;
+        manifest.storage_dir = self.storage_dir.clone();
+        if is_much_newer_version(&manifest.created_by_version) {
+            println!(
+                "Warning: backup '{}' was saved by newer version {} (this is {})",
+                manifest.name,
+                manifest.created_by_version,
+                get_crate_version()
+            );
+        }
         manifest.ingest_blobs_dir()?;
         Ok(manifest)
     }
 
-    fn backup_dir(&self) -> Result<PathBuf, anyhow::Error> {
-        Ok(Self::base_storage_dir()?.join(&self.name))
+    /// The storage directory this manifest is kept in
+    fn storage_root(&self) -> Result<PathBuf, anyhow::Error> {
+        match &self.storage_dir {
+            Some(dir) => Ok(dir.clone()),
+            None => Self::base_storage_dir(),
+        }
+    }
+
+    pub fn backup_dir(&self) -> Result<PathBuf, anyhow::Error> {
+        Ok(self.storage_root()?.join(&self.name))
+    }
+
+    /// Where this backup's new blobs go: the blob pool or its own `blobs`
+    pub fn blob_dir(&self) -> Result<PathBuf, anyhow::Error> {
+        Ok(pool::write_dir(&self.storage_root()?, &self.name))
+    }
+
+    /// Blob files in this backup's own `blobs` directory that none of its
+    /// entries reference, e.g. left behind by an interrupted save
+    pub fn orphan_blobs(&self) -> Vec<String> {
+        self.storage_root()
+            .map(|storage_dir| self.orphan_blobs_in(&storage_dir))
+            .unwrap_or_default()
+    }
+
+    pub fn orphan_blobs_in(&self, storage_dir: &Path) -> Vec<String> {
+        let blob_dir = storage_dir.join(&self.name).join("blobs");
+        if !blob_dir.is_dir() {
+            return Vec::new();
+        }
+        let referenced: HashSet<&str> =
+            self.entries.iter().filter_map(Entry::stored_blob_id).collect();
+        pack::blob_ids(&blob_dir)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|blob_id| !referenced.contains(blob_id.as_str()))
+            .collect()
     }
 
     pub fn save(&mut self) -> Result<(), anyhow::Error> {
-        let backup_dir = self.backup_dir()?;
+        self.created_by_version = get_crate_version().to_string();
+        self.flush_blob_writes()?;
+        self.save_in(&self.storage_root()?)
+    }
+
+    /// Store a blob file, either straight away or, with `batch_blob_writes`
+    /// on, buffered until the next `save()` writes the batch into the pack
+    fn write_blob_file(
+        &mut self,
+        blob_dir: &Path,
+        id: &str,
+        bytes: &[u8],
+    ) -> Result<(), anyhow::Error> {
+        if crate::settings::current().batch_blob_writes {
+            self.pending_blob_writes.push((id.to_string(), bytes.to_vec()));
+            return Ok(());
+        }
+        let blob_path = blob_dir.join(format!("{id}.tar.zst"));
+        if !blob_path.exists() {
+            write_atomic(&blob_path, bytes)?;
+        }
+        Ok(())
+    }
+
+    /// Append buffered blob writes to the backup's pack with a single fsync
+    pub fn flush_blob_writes(&mut self) -> Result<(), anyhow::Error> {
+        if self.pending_blob_writes.is_empty() {
+            return Ok(());
+        }
+        let blob_dir = self.blob_dir()?;
+        let stats = pack::append_to_pack(&blob_dir, &self.pending_blob_writes)?;
+        println!(
+            "Flushed {} buffered blob writes ({} bytes) into pack",
+            stats.blobs_packed, stats.bytes_packed
+        );
+        self.pending_blob_writes.clear();
+        Ok(())
+    }
+
+    /// Remember the blob chain file and the blob files already written, so
+    /// a save abandoned before `save()` can be undone with `roll_back_to`
+    pub fn save_checkpoint(&self) -> Result<SaveCheckpoint, anyhow::Error> {
+        let chain_path = BlobChainManager::metadata_path_in(&self.storage_root()?, &self.name);
+        let chain = match fs::read(&chain_path) {
+            Result::Ok(bytes) => Some(bytes),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+        let blob_dir = self.blob_dir()?;
+        let blob_files = file_names_in(&blob_dir)?;
+        Ok(SaveCheckpoint {
+            chain_path,
+            chain,
+            blob_dir,
+            blob_files,
+        })
+    }
+
+    /// Undo the blob writes and chain updates made since `checkpoint`: the
+    /// chain file goes back to what it was and new blob files are removed,
+    /// so the manifest on disk still matches its chain
+    pub fn roll_back_to(&mut self, checkpoint: &SaveCheckpoint) -> Result<(), anyhow::Error> {
+        self.pending_blob_writes.clear();
+        match &checkpoint.chain {
+            Some(bytes) => write_atomic(&checkpoint.chain_path, bytes)?,
+            None if checkpoint.chain_path.exists() => fs::remove_file(&checkpoint.chain_path)?,
+            None => {}
+        }
+        for name in file_names_in(&checkpoint.blob_dir)? {
+            if !checkpoint.blob_files.contains(&name) {
+                fs::remove_file(checkpoint.blob_dir.join(name))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Consolidate this backup's loose blob files into its pack
+    pub fn pack_blobs(&mut self) -> Result<PackStats, anyhow::Error> {
+        self.flush_blob_writes()?;
+        pack::pack_loose_blobs(&self.blob_dir()?)
+    }
+
+    /// Stamp `last_restored_at` with the current time and persist the manifest
+    pub fn mark_restored(&mut self) -> Result<(), anyhow::Error> {
+        self.mark_restored_with_dir(None)
+    }
+
+    pub fn mark_restored_with_dir(
+        &mut self,
+        storage_dir_override: Option<PathBuf>,
+    ) -> Result<(), anyhow::Error> {
+        let storage_dir = match storage_dir_override {
+            Some(dir) => dir,
+            None => self.storage_root()?,
+        };
+        self.last_restored_at = Some(chrono::Utc::now().to_rfc3339());
+        self.save_in(&storage_dir)
+    }
+
+    /// Recompute `total_blob_bytes`, `total_logical_bytes` and the entry
+    /// counts from the blobs and entries, so list views don't have to walk
+    /// blob directories or entries
+    pub fn refresh_totals(&mut self) {
+        self.total_blob_bytes = self.blobs.values().map(BlobPayload::get_size).sum();
+        self.total_logical_bytes = self.entries.iter().filter_map(|e| e.original_size).sum();
+        self.entry_count = self.entries.len();
+        self.entry_counts_by_hint.clear();
+        for entry in &self.entries {
+            *self.entry_counts_by_hint.entry(entry.target_hint.clone()).or_default() += 1;
+        }
+    }
+
+    /// Write `manifest.json` with fresh size totals, keeping the blob
+    /// reference counts in step with the entries added or dropped since the
+    /// previous save
+    pub(crate) fn save_in(&mut self, storage_dir: &Path) -> Result<(), anyhow::Error> {
+        self.refresh_totals();
+        self.backup_chain_hash = Some(self.calculate_backup_chain_hash());
+        let previous_entries = Self::load_in(storage_dir, &self.name)
+            .map(|previous| previous.entries)
+            .unwrap_or_default();
+        let mut delta: HashMap<&str, i64> = HashMap::new();
+        for blob_id in self.entries.iter().filter_map(Entry::stored_blob_id) {
+            *delta.entry(blob_id).or_default() += 1;
+        }
+        for blob_id in previous_entries.iter().filter_map(Entry::stored_blob_id) {
+            *delta.entry(blob_id).or_default() -= 1;
+        }
+        let (mut added, mut removed) = (Vec::new(), Vec::new());
+        for (blob_id, n) in delta {
+            let side = if n > 0 { &mut added } else { &mut removed };
+            side.extend(std::iter::repeat_n(blob_id, n.unsigned_abs() as usize));
+        }
+
+        // Raise counts before the manifest references the blobs and lower
+        // them only once it no longer does
+        if !added.is_empty() {
+            refcount::add_references(storage_dir, added)?;
+        }
+        let backup_dir = storage_dir.join(&self.name);
         fs::create_dir_all(&backup_dir)?;
         let manifest_path = backup_dir.join("manifest.json");
-        fs::write(&manifest_path, serde_json::to_string_pretty(self)?)?;
+        write_atomic(&manifest_path, serde_json::to_string_pretty(self)?.as_bytes())?;
+        dedup_index::record(storage_dir, self);
+        if !removed.is_empty() {
+            refcount::release_references(storage_dir, removed)?;
+        }
         Ok(())
     }
 
@@ -171,41 +807,58 @@ impl Manifest {
         None
     }
 
+    /// Backup and ID of a blob with this content in any backup, answered
+    /// from the process-wide dedup index when it can be
     pub fn find_existing_blob_across_backups(
         content_hash: &str,
     ) -> Result<Option<(String, String)>, anyhow::Error> {
-        // Check across all existing backups for duplicate content
-        let storage_dir = Self::base_storage_dir()?;
-        if !storage_dir.exists() {
-            return Ok(None);
-        }
-
-        for entry in fs::read_dir(storage_dir)? {
-            let entry = entry?;
-            if !entry.file_type()?.is_dir() {
-                continue;
-            }
-
-            let manifest_path = entry.path().join("manifest.json");
-            if !manifest_path.exists() {
-                continue;
-            }
-
-            let backup_name = entry.file_name().to_string_lossy().into_owned();
-            let manifest = Self::load_from(&backup_name)?;
+        dedup_index::find_blob(&Self::base_storage_dir()?, content_hash)
+    }
 
-            if let Some(blob_id) = manifest.find_existing_blob_by_content(content_hash) {
-                return Ok(Some((backup_name, blob_id)));
-            }
+    /// Look for an existing blob with this content within `scope`
+    pub fn find_duplicate_blob(
+        &self,
+        content_hash: &str,
+        scope: DedupScope,
+    ) -> Result<Option<(String, String)>, anyhow::Error> {
+        match scope {
+            // Blobs added since the last save aren't on disk yet
+            DedupScope::Global => match self.find_existing_blob_by_content(content_hash) {
+                Some(blob_id) => Ok(Some((self.name.clone(), blob_id))),
+                None => dedup_index::find_blob(&self.storage_root()?, content_hash),
+            },
+            DedupScope::WithinBackup => Ok(self
+                .find_existing_blob_by_content(content_hash)
+                .map(|blob_id| (self.name.clone(), blob_id))),
+            DedupScope::None => Ok(None),
         }
+    }
 
-        Ok(None)
+    /// Blob to point a new entry at instead of storing its content again: a
+    /// duplicate found within `scope`, or else one this backup already stores
+    /// under the same ID. Blob IDs are always the content hash, so even with
+    /// `DedupScope::None` identical files in one backup share their blob.
+    pub(crate) fn reusable_blob(
+        &self,
+        content_hash: &str,
+        scope: DedupScope,
+    ) -> Result<Option<(String, String)>, anyhow::Error> {
+        if let Some(found) = self.find_duplicate_blob(content_hash, scope)? {
+            return Ok(Some(found));
+        }
+        Ok(self
+            .blobs
+            .contains_key(content_hash)
+            .then(|| (self.name.clone(), content_hash.to_string())))
     }
 
     /// Enhanced parallel compression with memory optimization
-    fn parallel_compress_worker(data_chunks: Vec<Vec<u8>>) -> Result<Vec<Vec<u8>>, anyhow::Error> {
-        let config = &*PERFORMANCE_CONFIG;
+    fn parallel_compress_worker(
+        config: &PerformanceConfig,
+        data_chunks: Vec<Vec<u8>>,
+    ) -> Result<Vec<Vec<u8>>, anyhow::Error> {
         let num_threads = config.thread_count.min(data_chunks.len()).max(1);
+        let total_bytes = data_chunks.iter().map(Vec::len).sum();
         println!(
             "Using {} threads for optimized parallel compression",
             num_threads
@@ -226,6 +879,7 @@ impl Manifest {
 
         // Track metrics
         let total_time = start.elapsed().as_millis() as usize;
+        PERFORMANCE_METRICS.add_bytes_compressed(total_bytes);
         PERFORMANCE_METRICS.add_compression_time(total_time);
 
         results
@@ -323,10 +977,12 @@ impl Manifest {
         results
     }
 
-    /// Batch processing for multiple files with optimal threading
+    /// Batch processing for multiple files with optimal threading. Entries
+    /// get their `relative_path` under the app root like
+    /// `create_blob_from_file`'s.
     pub fn create_blobs_from_files_batch(
         &mut self,
-        file_paths: Vec<(PathBuf, String)>, // (path, target_hint) pairs
+        file_paths: Vec<(PathBuf, String, Option<PathBuf>)>, // (path, target_hint, app_root)
     ) -> Result<Vec<String>, anyhow::Error> {
         let start_time = Instant::now();
         let num_files = file_paths.len();
@@ -347,8 +1003,9 @@ impl Manifest {
         for chunk in chunks {
             let mut chunk_blob_ids = Vec::new();
 
-            for (path, target_hint) in chunk {
-                let blob_id = self.create_single_file_blob_optimized(&path, &target_hint)?;
+            for (path, target_hint, app_root) in chunk {
+                let blob_id =
+                    self.create_single_file_blob_optimized(path, target_hint, app_root.as_deref())?;
                 chunk_blob_ids.push(blob_id);
             }
 
@@ -375,8 +1032,10 @@ impl Manifest {
         &mut self,
         src: &Path,
         target_hint: &str,
+        app_root: Option<&Path>,
     ) -> Result<String, anyhow::Error> {
-        let blob_dir = self.backup_dir()?.join("blobs");
+        let app_relative_path = app_root.and_then(|root| relative_to(src, root));
+        let blob_dir = self.blob_dir()?;
         fs::create_dir_all(&blob_dir)?;
 
         // Read and create TAR in memory with optimized buffer
@@ -392,42 +1051,59 @@ impl Manifest {
             builder.finish()?;
         }
 
-        // Optimized compression based on file size
-        let compressed = if tar_data.len() > 5_000_000 {
+        let original_sha256 = first_member_sha256(&tar_data);
+        let original_size = Some(file_size);
+
+        // Optimized compression based on file size, unless overridden
+        let level = if let Some(level) = self.compression_profile {
+            level
+        } else if tar_data.len() > 5_000_000 {
             // 5MB threshold for batch processing
-            encode_all(&tar_data[..], 15)? // Faster compression for batch
+            15 // Faster compression for batch
         } else {
-            encode_all(&tar_data[..], 19)? // Max compression for small files
+            19 // Max compression for small files
         };
-
-        // Quick hash calculation
-        let mut hasher = Sha256::new();
-        hasher.update(&compressed);
-        let content_hash = hex::encode(hasher.finalize());
+        // Hash is computed while compressing
+        let compress_start = Instant::now();
+        let (format, (compressed, compressed_hash)) =
+            match Self::compress_with_selected_codec(&tar_data, level)? {
+                Some((codec, compressed, hash)) => (codec.format(), (compressed, hash)),
+                None => (Codec::Zstd.format(), compress_and_hash(&tar_data, level)?),
+            };
+        PERFORMANCE_METRICS.add_file_processed();
+        PERFORMANCE_METRICS.add_bytes_compressed(tar_data.len());
+        PERFORMANCE_METRICS.add_compression_time(compress_start.elapsed().as_millis() as usize);
+
+        let (compressed, format, content_hash) =
+            Self::seal_blob(compressed, compressed_hash, format)?;
 
         // Check for duplicates (optimized for batch)
-        if let Some((_, existing_blob_id)) = Self::find_existing_blob_across_backups(&content_hash)?
+        let dedup_scope = crate::settings::current().dedup_scope;
+        if let Some((_, existing_blob_id)) = self.reusable_blob(&content_hash, dedup_scope)?
         {
+            PERFORMANCE_METRICS.add_dedup_save();
             self.entries.push(Entry {
                 blob_id: existing_blob_id.clone(),
                 target_hint: target_hint.to_string(),
                 logical_path: src.to_string_lossy().into_owned(),
                 tar_member: Some(src.file_name().unwrap().to_string_lossy().into_owned()),
+                relative_path: app_relative_path,
+                original_sha256,
+                original_size,
+                inline_data: None,
+                original_mtime: mtime_of(src),
             });
             return Ok(existing_blob_id);
         }
 
-        let id = content_hash;
+        let id = content_hash.clone();
 
         // Write blob to disk
-        let blob_path = blob_dir.join(format!("{id}.tar.zst"));
-        if !blob_path.exists() {
-            fs::write(&blob_path, &compressed)?;
-        }
+        self.write_blob_file(&blob_dir, &id, &compressed)?;
 
         // Create and chain blob
-        let mut blob = BlobPayload::new("tar.zst".to_string(), &compressed);
-        let storage_dir = Self::base_storage_dir()?;
+        let mut blob = BlobPayload::new_with_hash(format, &compressed, content_hash);
+        let storage_dir = self.storage_root()?;
         let mut chain_manager = BlobChainManager::new(storage_dir, self.name.clone())?;
 
         let chain_info = chain_manager.get_chain_info();
@@ -443,15 +1119,24 @@ impl Manifest {
             target_hint: target_hint.to_string(),
             logical_path: src.to_string_lossy().into_owned(),
             tar_member: Some(src.file_name().unwrap().to_string_lossy().into_owned()),
+            relative_path: app_relative_path,
+            original_sha256,
+            original_size,
+            inline_data: None,
+            original_mtime: mtime_of(src),
         });
 
         Ok(id)
     }
 
-    /// Batch restore with parallel processing
+    /// Batch restore with parallel processing. Entries are restored largest
+    /// blob first, so a huge blob doesn't start last and keep one worker
+    /// busy while the others sit idle. `on_restored` is called for each
+    /// entry once its file is written.
     pub fn restore_blobs_batch(
         &self,
-        entries_with_dest: Vec<(&Entry, PathBuf)>,
+        mut entries_with_dest: Vec<(&Entry, PathBuf)>,
+        on_restored: impl Fn(&Entry) -> Result<(), anyhow::Error> + Sync,
     ) -> Result<(), anyhow::Error> {
         let start_time = Instant::now();
         let num_entries = entries_with_dest.len();
@@ -461,11 +1146,16 @@ impl Manifest {
             return Ok(());
         }
 
+        entries_with_dest.sort_by_key(|(entry, _)| {
+            std::cmp::Reverse(self.blobs.get(&entry.blob_id).map_or(0, BlobPayload::get_size))
+        });
+
         // Process restores in parallel
         let results: Result<Vec<_>, anyhow::Error> = entries_with_dest
             .into_par_iter()
             .map(|(entry, dest)| -> Result<(), anyhow::Error> {
-                self.restore_blob_to(entry, &dest)
+                self.restore_blob_to(entry, &dest)?;
+                on_restored(entry)
             })
             .collect();
 
@@ -486,31 +1176,113 @@ impl Manifest {
         &mut self,
         src: &Path,
         target_hint: &str,
+        app_root: Option<&Path>,
     ) -> Result<(), anyhow::Error> {
-        let blob_dir = self.backup_dir()?.join("blobs");
+        let app_relative_path = app_root.and_then(|root| relative_to(src, root));
         println!("Creating blob from file");
-        fs::create_dir_all(&blob_dir)?;
-        println!("Created blob directory in {}", blob_dir.display());
 
         // Cria TAR na memória
         println!("Creating TAR archive");
+        let file_name = src
+            .file_name()
+            .ok_or_else(|| anyhow!("Invalid file name"))?;
         let mut tar_data = Vec::new();
         {
             let mut builder = Builder::new(&mut tar_data);
-            let file_name = src
-                .file_name()
-                .ok_or_else(|| anyhow!("Invalid file name"))?;
             builder.append_path_with_name(src, file_name)?;
             builder.finish()?;
         }
         println!("Created TAR archive");
 
+        self.store_tar_blob(
+            &tar_data,
+            Entry {
+                blob_id: String::new(),
+                target_hint: target_hint.to_string(),
+                logical_path: src.to_string_lossy().into_owned(),
+                tar_member: Some(file_name.to_string_lossy().into_owned()),
+                relative_path: app_relative_path,
+                original_sha256: first_member_sha256(&tar_data),
+                original_size: Some(fs::metadata(src)?.len()),
+                inline_data: None,
+                original_mtime: mtime_of(src),
+            },
+        )
+    }
+
+    /// Like `create_blob_from_file`, for content that doesn't come from a
+    /// file on disk (e.g. a git object). `logical_path`'s file name becomes
+    /// the TAR member, stored with unix permission bits `mode`.
+    pub fn create_blob_from_bytes(
+        &mut self,
+        data: &[u8],
+        mode: u32,
+        target_hint: &str,
+        logical_path: &str,
+        relative_path: Option<String>,
+    ) -> Result<(), anyhow::Error> {
+        let member = logical_path
+            .rsplit(['/', '\\'])
+            .next()
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| anyhow!("Invalid file name: {}", logical_path))?
+            .to_string();
+        let mut tar_data = Vec::new();
+        {
+            let mut builder = Builder::new(&mut tar_data);
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(mode);
+            header.set_mtime(chrono::Utc::now().timestamp().max(0) as u64);
+            header.set_cksum();
+            builder.append_data(&mut header, &member, data)?;
+            builder.finish()?;
+        }
+
+        self.store_tar_blob(
+            &tar_data,
+            Entry {
+                blob_id: String::new(),
+                target_hint: target_hint.to_string(),
+                logical_path: logical_path.to_string(),
+                tar_member: Some(member),
+                relative_path,
+                original_sha256: Some(hex::encode(Sha256::digest(data))),
+                original_size: Some(data.len() as u64),
+                inline_data: None,
+                original_mtime: None,
+            },
+        )
+    }
+
+    /// Compress, seal, deduplicate and chain one blob's TAR, then record
+    /// `entry` pointing at it (its `blob_id` is filled in here)
+    fn store_tar_blob(&mut self, tar_data: &[u8], mut entry: Entry) -> Result<(), anyhow::Error> {
+        PERFORMANCE_METRICS.add_file_processed();
+        let config = &self.compression_config();
+        if Self::should_inline(config, &entry) {
+            return self.store_inline_entry(tar_data, config.compression_level, entry);
+        }
+
+        let blob_dir = self.blob_dir()?;
+        fs::create_dir_all(&blob_dir)?;
+        println!("Created blob directory in {}", blob_dir.display());
+
         // Use adaptive compression strategy based on configuration
         println!("Compressing TAR archive with adaptive strategy");
         let start_time = Instant::now();
-        let config = &*PERFORMANCE_CONFIG;
 
-        let compressed = if config.should_use_parallel(tar_data.len()) {
+        // Chunked and non-zstd blobs are too large to gain from the dictionary
+        let mut dictionary_id = None;
+        let mut codec = Codec::Zstd;
+        let (compressed, compressed_hash) = if let Some((picked, compressed, hash)) =
+            Self::compress_with_selected_codec(tar_data, config.compression_level)?
+        {
+            codec = picked;
+            PERFORMANCE_METRICS.add_bytes_compressed(tar_data.len());
+            PERFORMANCE_METRICS.add_compression_time(start_time.elapsed().as_millis() as usize);
+            (compressed, hash)
+        } else if config.should_use_parallel(tar_data.len()) {
             // For large files, use parallel chunk compression
             let chunk_size = Self::get_optimal_chunk_size(tar_data.len(), COMPRESSION_BUFFER_SIZE);
             let chunks: Vec<Vec<u8>> = tar_data
@@ -524,11 +1296,13 @@ impl Manifest {
                 chunks.len()
             );
 
-            let compressed_chunks = Self::parallel_compress_worker(chunks)?;
-            compressed_chunks.into_iter().flatten().collect()
+            let compressed_chunks = Self::parallel_compress_worker(config, chunks)?;
+            Self::concat_and_hash(compressed_chunks)?
         } else {
             // For smaller files, use adaptive single-thread compression
-            Self::adaptive_compress(&tar_data)?
+            let (compressed, hash, used) = self.compress_with_dictionary(config, tar_data)?;
+            dictionary_id = used;
+            (compressed, hash)
         };
 
         let compression_time = start_time.elapsed();
@@ -538,16 +1312,15 @@ impl Manifest {
             compression_time, compression_ratio
         );
 
-        // SHA256 do conteúdo comprimido para verificar duplicação
-        println!("Calculating SHA256 hash for deduplication");
-        let mut hasher = Sha256::new();
-        hasher.update(&compressed);
-        let content_hash = hex::encode(hasher.finalize());
+        // SHA256 of the compressed content was computed while compressing
+        let (compressed, format, content_hash) =
+            Self::seal_blob(compressed, compressed_hash, codec.format())?;
 
         // Verificar se o blob já existe (deduplicação)
+        let dedup_scope = crate::settings::current().dedup_scope;
         println!("Checking for existing blob with same content");
         if let Some((existing_backup, existing_blob_id)) =
-            Self::find_existing_blob_across_backups(&content_hash)?
+            self.reusable_blob(&content_hash, dedup_scope)?
         {
             println!(
                 "Found duplicate content in backup '{}' with blob ID '{}'",
@@ -555,34 +1328,28 @@ impl Manifest {
             );
 
             // Usar referência do blob existente ao invés de criar novo
-            self.entries.push({
-                Entry {
-                    blob_id: existing_blob_id,
-                    target_hint: target_hint.to_string(),
-                    logical_path: src.to_string_lossy().into_owned(),
-                    tar_member: Some(src.file_name().unwrap().to_string_lossy().into_owned()),
-                }
-            });
+            PERFORMANCE_METRICS.add_dedup_save();
+            entry.blob_id = existing_blob_id;
+            self.entries.push(entry);
 
             println!("Reused existing blob - storage space saved!");
             return Ok(());
         }
 
-        let id = content_hash; // Use content hash as ID for better deduplication
+        // Content hash as ID for deduplication
+        let id = content_hash.clone();
 
         // Salva no disco
-        let blob_path = blob_dir.join(format!("{id}.tar.zst"));
-        if !blob_path.exists() {
-            fs::write(&blob_path, &compressed)?;
-        }
+        self.write_blob_file(&blob_dir, &id, &compressed)?;
 
         println!("Blob saved to disk");
 
         // Create blob and determine previous blob hash
-        let mut blob = BlobPayload::new("tar.zst".to_string(), &compressed);
+        let mut blob = BlobPayload::new_with_hash(format, &compressed, content_hash);
+        blob.set_dictionary(dictionary_id);
 
         // Initialize blob chain manager and add blob to chain
-        let storage_dir = Self::base_storage_dir()?;
+        let storage_dir = self.storage_root()?;
         let mut chain_manager = BlobChainManager::new(storage_dir, self.name.clone())?;
 
         let chain_info = chain_manager.get_chain_info();
@@ -604,24 +1371,71 @@ impl Manifest {
         // Adicionar blob ao manifest atual
         self.add_blob_for_testing(id.clone(), blob);
 
-        self.entries.push({
-            Entry {
-                blob_id: id,
-                target_hint: target_hint.to_string(),
-                logical_path: src.to_string_lossy().into_owned(),
-                tar_member: Some(src.file_name().unwrap().to_string_lossy().into_owned()),
-            }
-        });
+        entry.blob_id = id;
+        self.entries.push(entry);
 
         Ok(())
     }
 
+    /// Whether `entry`'s file is small enough to keep in the manifest.
+    /// Never with blob encryption on, as the manifest itself isn't sealed.
+    fn should_inline(config: &PerformanceConfig, entry: &Entry) -> bool {
+        entry.tar_member.is_some()
+            && entry.original_size.is_some_and(|size| config.should_inline(size))
+            && !crate::settings::current().encrypt_blobs
+    }
+
+    /// Record `entry` with its compressed TAR in `inline_data`, skipping
+    /// the blob file, deduplication and the blob chain
+    fn store_inline_entry(
+        &mut self,
+        tar_data: &[u8],
+        level: i32,
+        mut entry: Entry,
+    ) -> Result<(), anyhow::Error> {
+        let start_time = Instant::now();
+        let compressed = encode_all(tar_data, level).context("Compression failed")?;
+        PERFORMANCE_METRICS.add_bytes_compressed(tar_data.len());
+        PERFORMANCE_METRICS.add_compression_time(start_time.elapsed().as_millis() as usize);
+
+        entry.blob_id = String::new();
+        entry.inline_data = Some(general_purpose::STANDARD.encode(compressed));
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Back up every file under `src` as its own entry sharing
+    /// `target_hint`, so files unchanged since the last backup dedup. With
+    /// `directory_as_single_blob` on, the whole directory is one TAR blob.
     pub fn create_blob_from_directory(
         &mut self,
         src: &Path,
         target_hint: &str,
+        app_root: Option<&Path>,
     ) -> Result<(), anyhow::Error> {
-        let blob_dir = self.backup_dir()?.join("blobs");
+        if crate::settings::current().directory_as_single_blob {
+            return self.create_single_blob_from_directory(src, target_hint, app_root);
+        }
+        // Same collector as the apps' own directory walks, so ignored files
+        // stay out of the backup
+        let mut files = Vec::new();
+        crate::apps::collect_files_recursive(src, &[], &mut files)?;
+        files.sort();
+        println!("Creating {} file blobs from directory", files.len());
+        for file in files {
+            self.create_blob_from_file(&file, target_hint, app_root)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn create_single_blob_from_directory(
+        &mut self,
+        src: &Path,
+        target_hint: &str,
+        app_root: Option<&Path>,
+    ) -> Result<(), anyhow::Error> {
+        let app_relative_path = app_root.and_then(|root| relative_to(src, root));
+        let blob_dir = self.blob_dir()?;
         println!("Creating blob from directory");
         fs::create_dir_all(&blob_dir)?;
         println!("Created blob directory in {}", blob_dir.display());
@@ -629,6 +1443,7 @@ impl Manifest {
         // Cria TAR na memória
         println!("Creating TAR archive from directory");
         let mut tar_data = Vec::new();
+        let original_size: u64;
         {
             let mut builder = Builder::new(&mut tar_data);
 
@@ -657,6 +1472,7 @@ impl Manifest {
             sorted_files.sort_by_key(|(data, _, _)| std::cmp::Reverse(data.len()));
 
             println!("Adding {} files to TAR archive", sorted_files.len());
+            original_size = sorted_files.iter().map(|(data, _, _)| data.len() as u64).sum();
 
             // Escreve os arquivos sequencialmente no TAR
             for (file_data, _, relative_path) in sorted_files {
@@ -676,9 +1492,16 @@ impl Manifest {
         // Use enhanced adaptive compression for directories
         println!("Compressing directory TAR with enhanced adaptive strategy");
         let start_time = Instant::now();
-        let config = &*PERFORMANCE_CONFIG;
+        let config = &self.compression_config();
 
-        let compressed = if config.should_use_parallel(tar_data.len()) {
+        let mut dictionary_id = None;
+        let mut codec = Codec::Zstd;
+        let (compressed, compressed_hash) = if let Some((picked, compressed, hash)) =
+            Self::compress_with_selected_codec(&tar_data, config.compression_level)?
+        {
+            codec = picked;
+            (compressed, hash)
+        } else if config.should_use_parallel(tar_data.len()) {
             // For huge directories, use optimized parallel compression
             let chunk_size =
                 Self::get_optimal_chunk_size(tar_data.len(), COMPRESSION_BUFFER_SIZE * 4);
@@ -693,11 +1516,13 @@ impl Manifest {
                 chunks.len()
             );
 
-            let compressed_chunks = Self::parallel_compress_worker(chunks)?;
-            compressed_chunks.into_iter().flatten().collect()
+            let compressed_chunks = Self::parallel_compress_worker(config, chunks)?;
+            Self::concat_and_hash(compressed_chunks)?
         } else {
             // For smaller directories, use adaptive compression
-            Self::adaptive_compress(&tar_data)?
+            let (compressed, hash, used) = self.compress_with_dictionary(config, &tar_data)?;
+            dictionary_id = used;
+            (compressed, hash)
         };
 
         let compression_time = start_time.elapsed();
@@ -709,16 +1534,15 @@ impl Manifest {
             compression_time, compression_ratio, throughput
         );
 
-        // SHA256 do conteúdo comprimido para verificar duplicação
-        println!("Calculating SHA256 hash for deduplication");
-        let mut hasher = Sha256::new();
-        hasher.update(&compressed);
-        let content_hash = hex::encode(hasher.finalize());
+        // SHA256 of the compressed content was computed while compressing
+        let (compressed, format, content_hash) =
+            Self::seal_blob(compressed, compressed_hash, codec.format())?;
 
         // Verificar se o blob já existe (deduplicação)
+        let dedup_scope = crate::settings::current().dedup_scope;
         println!("Checking for existing blob with same content");
         if let Some((existing_backup, existing_blob_id)) =
-            Self::find_existing_blob_across_backups(&content_hash)?
+            self.reusable_blob(&content_hash, dedup_scope)?
         {
             println!(
                 "Found duplicate content in backup '{}' with blob ID '{}'",
@@ -726,12 +1550,18 @@ impl Manifest {
             );
 
             // Usar referência do blob existente ao invés de criar novo
+            PERFORMANCE_METRICS.add_dedup_save();
             self.entries.push({
                 Entry {
                     blob_id: existing_blob_id,
                     target_hint: target_hint.to_string(),
                     logical_path: src.to_string_lossy().into_owned(),
                     tar_member: None, // Para diretórios, não há membro específico
+                    relative_path: app_relative_path,
+                    original_sha256: None,
+                    original_size: Some(original_size),
+                    inline_data: None,
+                    original_mtime: None,
                 }
             });
 
@@ -739,21 +1569,20 @@ impl Manifest {
             return Ok(());
         }
 
-        let id = content_hash; // Use content hash as ID for better deduplication
+        // Content hash as ID for deduplication
+        let id = content_hash.clone();
 
         // Salva no disco
-        let blob_path = blob_dir.join(format!("{id}.tar.zst"));
-        if !blob_path.exists() {
-            fs::write(&blob_path, &compressed)?;
-        }
+        self.write_blob_file(&blob_dir, &id, &compressed)?;
 
         println!("Blob saved to disk");
 
         // Create blob and determine previous blob hash
-        let mut blob = BlobPayload::new("tar.zst".to_string(), &compressed);
+        let mut blob = BlobPayload::new_with_hash(format, &compressed, content_hash);
+        blob.set_dictionary(dictionary_id);
 
         // Initialize blob chain manager and add blob to chain
-        let storage_dir = Self::base_storage_dir()?;
+        let storage_dir = self.storage_root()?;
         let mut chain_manager = BlobChainManager::new(storage_dir, self.name.clone())?;
 
         let chain_info = chain_manager.get_chain_info();
@@ -781,6 +1610,11 @@ impl Manifest {
                 target_hint: target_hint.to_string(),
                 logical_path: src.to_string_lossy().into_owned(),
                 tar_member: None, // Para diretórios, não há membro específico
+                relative_path: app_relative_path,
+                original_sha256: None,
+                original_size: Some(original_size),
+                inline_data: None,
+                original_mtime: None,
             }
         });
 
@@ -819,22 +1653,61 @@ impl Manifest {
         Ok(())
     }
 
-    pub fn restore_blob_to(&self, entry: &Entry, dest: &Path) -> Result<(), anyhow::Error> {
-        let start_time = Instant::now();
+    /// Decode a blob's payload and decompress it into raw TAR bytes
+    fn decode_blob_tar(&self, blob_id: &str) -> Result<Vec<u8>, anyhow::Error> {
         let blob = self
             .blobs
-            .get(&entry.blob_id)
-            .ok_or_else(|| anyhow!("blob_id não encontrado no manifest: {}", entry.blob_id))?;
+            .get(blob_id)
+            .ok_or_else(|| anyhow!(messages::blob_not_in_manifest(blob_id)))?;
+
+        let raw = blob.decode().context(messages::BLOB_BASE64_INVALID)?;
+
+        println!("Starting decompression for blob: {}", blob_id);
+        let dictionary = self.dictionary_of(blob)?;
+        Self::stored_bytes_to_tar(blob.get_format(), raw, dictionary.as_deref())
+    }
 
-        let raw = blob
-            .decode()
-            .context("falha ao decodificar base64 do blob")?;
+    /// Raw TAR bytes holding `entry`'s data, from the entry itself when
+    /// inline and from its blob otherwise
+    fn entry_tar(&self, entry: &Entry) -> Result<Vec<u8>, anyhow::Error> {
+        let Some(inline) = &entry.inline_data else {
+            return self.decode_blob_tar(&entry.blob_id);
+        };
+        let compressed = general_purpose::STANDARD
+            .decode(inline)
+            .with_context(|| format!("Inline data of {} is not valid base64", entry.logical_path))?;
+        zstd::stream::decode_all(&compressed[..]).context(messages::ZSTD_DECOMPRESS_FAILED)
+    }
+
+    /// The zstd dictionary `blob` was compressed against, if any
+    fn dictionary_of(&self, blob: &BlobPayload) -> Result<Option<Vec<u8>>, anyhow::Error> {
+        let Some(id) = blob.get_dictionary() else {
+            return Ok(None);
+        };
+        if self.compression_dictionary.as_deref() == Some(id) {
+            if let Some(bytes) = &self.dictionary_bytes {
+                return Ok(Some(bytes.clone()));
+            }
+        }
+        Ok(Some(dictionary::load(&self.storage_root()?, &self.name, id)?))
+    }
 
-        println!("Starting decompression for blob: {}", entry.blob_id);
+    /// Turn a blob's stored bytes (as in its blob file) into raw TAR bytes
+    fn stored_bytes_to_tar(
+        format: &str,
+        raw: Vec<u8>,
+        dictionary: Option<&[u8]>,
+    ) -> Result<Vec<u8>, anyhow::Error> {
+        // Sealed blobs are a compressed tarball behind AES-GCM
+        let (format, raw) = match format.strip_suffix(ENCRYPTED_SUFFIX) {
+            Some(inner) => (inner, crypto::open(&raw).context("Failed to decrypt blob")?),
+            None => (format, raw),
+        };
 
-        let tar_bytes: Vec<u8> = match blob.get_format() {
-            "tar" => raw,
-            "tar.zst" => {
+        let tar_bytes: Vec<u8> = match (format, dictionary) {
+            ("tar", _) => raw,
+            ("tar.zst", Some(dictionary)) => dictionary::decompress(&raw, dictionary)?,
+            ("tar.zst", None) => {
                 // Use parallel decompression for large compressed data
                 if raw.len() > 20_000_000 {
                     // 20MB threshold
@@ -853,33 +1726,102 @@ impl Manifest {
                                 decompressed.extend_from_slice(&buffer[..n])
                             }
                             std::result::Result::Err(e) => {
-                                return Err(anyhow!("Decompression error: {}", e))
+                                return Err(anyhow!(messages::decompression_failed(e)))
                             }
                         }
                     }
                     decompressed
                 } else {
-                    zstd::stream::decode_all(&raw[..]).context("falha ao descomprimir zstd")?
+                    zstd::stream::decode_all(&raw[..]).context(messages::ZSTD_DECOMPRESS_FAILED)?
                 }
             }
-            other => return Err(anyhow!("formato de blob desconhecido: {}", other)),
+            (other, _) => match Codec::from_format(other) {
+                Some(codec) => codec.decompress(&raw)?,
+                None => return Err(anyhow!(messages::unknown_blob_format(other))),
+            },
         };
 
+        Ok(tar_bytes)
+    }
+
+    /// Extract every member of a blob into `dest_dir`, regardless of the
+    /// entries' `tar_member`. Diagnostic helper for inspecting blob contents.
+    pub fn extract_blob_to_dir(
+        &self,
+        blob_id: &str,
+        dest_dir: &Path,
+    ) -> Result<Vec<PathBuf>, anyhow::Error> {
+        let blob = self
+            .blobs
+            .get(blob_id)
+            .ok_or_else(|| anyhow!("Blob not found in manifest: {}", blob_id))?;
+
+        // Refuse to extract a blob whose payload doesn't match its recorded hash
+        let raw = blob.decode().context("Failed to decode blob payload")?;
+        let actual_sha256 = format!("{:x}", Sha256::digest(&raw));
+        if actual_sha256 != blob.get_sha256() {
+            return Err(anyhow!(
+                "Blob {} failed sha256 validation: expected {}, got {}",
+                blob_id,
+                blob.get_sha256(),
+                actual_sha256
+            ));
+        }
+
+        let tar_bytes = self.decode_blob_tar(blob_id)?;
+        fs::create_dir_all(dest_dir)?;
+
+        let mut extracted = Vec::new();
+        let mut ar = tar::Archive::new(&tar_bytes[..]);
+        for f in ar.entries()? {
+            let mut f = f?;
+            let member_path = f.path()?.into_owned();
+            // unpack_in refuses members that would escape dest_dir
+            if f.unpack_in(dest_dir)? && f.header().entry_type().is_file() {
+                extracted.push(dest_dir.join(member_path));
+            }
+        }
+
+        println!("Extracted {} files from blob {}", extracted.len(), blob_id);
+        Ok(extracted)
+    }
+
+    /// Content of the file `entry` stands for, decompressed in memory
+    pub fn read_entry(&self, entry: &Entry) -> Result<Vec<u8>, anyhow::Error> {
+        let member_name = entry.tar_member.as_ref().ok_or_else(|| {
+            anyhow!("'{}' is a directory, not a single file", entry.logical_path)
+        })?;
+        let tar_bytes = self.entry_tar(entry)?;
+        let mut ar = tar::Archive::new(&tar_bytes[..]);
+        for f in ar.entries()? {
+            let mut f = f?;
+            if f.path()?.as_os_str().to_string_lossy() == *member_name {
+                let mut data = Vec::new();
+                f.read_to_end(&mut data)?;
+                return Ok(data);
+            }
+        }
+        Err(anyhow!(messages::member_not_in_blob(member_name, &entry.blob_id)))
+    }
+
+    pub fn restore_blob_to(&self, entry: &Entry, dest: &Path) -> Result<(), anyhow::Error> {
+        let start_time = Instant::now();
+        let tar_bytes = self.entry_tar(entry)?;
+
         let decompression_time = start_time.elapsed();
         println!("Decompression completed in {:?}", decompression_time);
 
         fs::create_dir_all(
             dest.parent()
-                .ok_or_else(|| anyhow!("dest sem parent: {}", dest.display()))?,
+                .ok_or_else(|| anyhow!(messages::no_parent_dir(dest)))?,
         )?;
 
+        // Directory blobs carry no member name: restore all of their files
+        let Some(member_name) = entry.tar_member.as_ref() else {
+            return Self::restore_directory_tar_to(&tar_bytes, dest);
+        };
         let mut ar = tar::Archive::new(&tar_bytes[..]);
 
-        let member_name = entry
-            .tar_member
-            .as_ref()
-            .ok_or_else(|| anyhow!("extract_mode=file requer tar_member"))?;
-
         // Optimized member search with early exit
         println!("Searching for member: {}", member_name);
         let mut found = false;
@@ -887,27 +1829,7 @@ impl Manifest {
             let mut f = f?;
             let path = f.path()?;
             if path.as_os_str().to_string_lossy() == *member_name {
-                // Use parallel I/O for large files during extraction
-                let tmp = dest.with_extension("tmp.part");
-
-                let file_size = f.header().size().unwrap_or(0);
-                if file_size > 10_000_000 {
-                    // 10MB threshold
-                    println!("Large file extraction detected, using optimized I/O");
-
-                    // Use buffered writing for better performance
-                    let mut out = std::io::BufWriter::with_capacity(
-                        1024 * 1024, // 1MB buffer
-                        fs::File::create(&tmp)?,
-                    );
-                    std::io::copy(&mut f, &mut out)?;
-                    out.flush()?;
-                } else {
-                    let mut out = fs::File::create(&tmp)?;
-                    std::io::copy(&mut f, &mut out)?;
-                }
-
-                fs::rename(tmp, &dest)?;
+                Self::write_tar_member_to(&mut f, dest)?;
                 found = true;
                 break;
             }
@@ -940,11 +1862,7 @@ impl Manifest {
         }
 
         if !found {
-            return Err(anyhow!(
-                "membro '{}' não encontrado no TAR do blob {}",
-                member_name,
-                entry.blob_id
-            ));
+            return Err(anyhow!(messages::member_not_in_blob(member_name, &entry.blob_id)));
         }
 
         let total_time = start_time.elapsed();
@@ -952,6 +1870,74 @@ impl Manifest {
         Ok(())
     }
 
+    /// Write one TAR member over `dest` through a temp file, so a crash or a
+    /// truncated blob never leaves a half-written config behind
+    fn write_tar_member_to<R: Read>(
+        member: &mut tar::Entry<'_, R>,
+        dest: &Path,
+    ) -> Result<(), anyhow::Error> {
+        let file_size = member.header().size().unwrap_or(0);
+        let mode = member.header().mode();
+        write_atomic_with(dest, |file| {
+            let copied = if file_size > 10_000_000 {
+                // 10MB threshold
+                println!("Large file extraction detected, using optimized I/O");
+
+                // Use buffered writing for better performance
+                let mut out = std::io::BufWriter::with_capacity(
+                    1024 * 1024, // 1MB buffer
+                    &mut *file,
+                );
+                let copied = std::io::copy(member, &mut out)?;
+                out.flush()?;
+                copied
+            } else {
+                std::io::copy(member, file)?
+            };
+            if copied != file_size {
+                return Err(anyhow!(
+                    "TAR member for {} is truncated: {} of {} bytes",
+                    dest.display(),
+                    copied,
+                    file_size
+                ));
+            }
+
+            // Carry the backed-up mode over, so 0600 secrets stay 0600, but
+            // only the permission bits: never setuid, setgid or sticky
+            #[cfg(unix)]
+            if let std::result::Result::Ok(mode) = mode {
+                use std::os::unix::fs::PermissionsExt;
+                file.set_permissions(fs::Permissions::from_mode(mode & 0o777))?;
+            }
+            #[cfg(not(unix))]
+            let _ = mode;
+            Ok(())
+        })
+    }
+
+    /// Restore every file of a directory blob under `dest`, each through
+    /// its own temp file
+    fn restore_directory_tar_to(tar_bytes: &[u8], dest: &Path) -> Result<(), anyhow::Error> {
+        fs::create_dir_all(dest)?;
+        let mut ar = tar::Archive::new(tar_bytes);
+        for f in ar.entries()? {
+            let mut f = f?;
+            let member = f.path()?.to_string_lossy().into_owned();
+            let path = join_relative(dest, &member)
+                .ok_or_else(|| anyhow!("Invalid path in directory blob: {}", member))?;
+            if f.header().entry_type().is_dir() {
+                fs::create_dir_all(&path)?;
+            } else if f.header().entry_type().is_file() {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                Self::write_tar_member_to(&mut f, &path)?;
+            }
+        }
+        Ok(())
+    }
+
     /// Get current performance statistics
     pub fn get_performance_stats(&self) -> crate::storage::performance::PerformanceStats {
         PERFORMANCE_METRICS.get_stats()
@@ -1032,6 +2018,354 @@ impl Manifest {
         }
     }
 
+    /// Deterministic hash of this backup's content: identity, entries and the
+    /// blobs they reference. Chain link fields are not included.
+    pub fn calculate_backup_hash(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.name.as_bytes());
+        hasher.update(self.created_at.as_bytes());
+        hasher.update(self.os_source.as_bytes());
+
+        for entry in &self.entries {
+            hasher.update(entry.target_hint.as_bytes());
+            hasher.update(entry.logical_path.as_bytes());
+            hasher.update(entry.blob_id.as_bytes());
+            hasher.update(entry.tar_member.as_deref().unwrap_or_default().as_bytes());
+            if let Some(inline) = &entry.inline_data {
+                hasher.update(inline.as_bytes());
+            }
+        }
+
+        let mut blob_ids: Vec<&String> = self.blobs.keys().collect();
+        blob_ids.sort();
+        for blob_id in blob_ids {
+            hasher.update(blob_id.as_bytes());
+            hasher.update(self.blobs[blob_id].get_sha256().as_bytes());
+        }
+
+        hex::encode(hasher.finalize())
+    }
+
+    /// `backup_chain_hash` as it should be for the current link and content
+    pub fn calculate_backup_chain_hash(&self) -> String {
+        let mut hasher = Sha256::new();
+        if let Some(previous_hash) = &self.previous_backup_hash {
+            hasher.update(previous_hash.as_bytes());
+        }
+        hasher.update(self.calculate_backup_hash().as_bytes());
+        hex::encode(hasher.finalize())
+    }
+
+    /// Recompute `backup_chain_hash` and compare it with the stored one, the
+    /// backup-level counterpart of `BlobChainMetadata::verify_integrity`.
+    /// Editing the manifest's entries, blobs or chain link by hand makes
+    /// this return false; a backup saved before the field existed is an
+    /// error rather than a pass.
+    pub fn verify_backup_chain_hash(&self) -> Result<bool, anyhow::Error> {
+        let stored = self
+            .backup_chain_hash
+            .as_deref()
+            .ok_or_else(|| anyhow!("Backup '{}' has no backup chain hash", self.name))?;
+        std::result::Result::Ok(stored == self.calculate_backup_chain_hash())
+    }
+
+    pub(crate) fn load_in(storage_dir: &Path, name: &str) -> Result<Self, anyhow::Error> {
+        let manifest_path = storage_dir.join(name).join("manifest.json");
+        let content = fs::read_to_string(manifest_path)?;
+        let mut manifest: Self = serde_json::from_str(&content)?;
+        manifest.storage_dir = Some(storage_dir.to_path_buf());
+        Ok(manifest)
+    }
+
+    /// Every backup in the storage directory, in directory order
+    pub fn list_all() -> Result<Vec<Self>, anyhow::Error> {
+        Self::list_all_with_dir(None)
+    }
+
+    pub fn list_all_with_dir(
+        storage_dir_override: Option<PathBuf>,
+    ) -> Result<Vec<Self>, anyhow::Error> {
+        let storage_dir = match storage_dir_override {
+            Some(dir) => dir,
+            None => Self::base_storage_dir()?,
+        };
+        let mut manifests = Vec::new();
+        if !storage_dir.exists() {
+            return Ok(manifests);
+        }
+
+        for entry in fs::read_dir(&storage_dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() && entry.path().join("manifest.json").exists() {
+                let backup_name = entry.file_name().to_string_lossy().into_owned();
+                manifests.push(Self::load_in(&storage_dir, &backup_name)?);
+            }
+        }
+        Ok(manifests)
+    }
+
+    /// Backups tagged with `machine_profile`
+    pub fn list_for_profile(profile: &str) -> Result<Vec<Self>, anyhow::Error> {
+        Self::list_for_profile_with_dir(profile, None)
+    }
+
+    pub fn list_for_profile_with_dir(
+        profile: &str,
+        storage_dir_override: Option<PathBuf>,
+    ) -> Result<Vec<Self>, anyhow::Error> {
+        Ok(Self::list_all_with_dir(storage_dir_override)?
+            .into_iter()
+            .filter(|m| m.machine_profile.as_deref() == Some(profile))
+            .collect())
+    }
+
+    /// Name of the most recently created backup other than `exclude` with
+    /// the same `machine_profile`, so each machine's backups chain on their
+    /// own; untagged backups only follow untagged ones
+    pub fn find_latest_backup(
+        exclude: &str,
+        profile: Option<&str>,
+    ) -> Result<Option<String>, anyhow::Error> {
+        Self::find_latest_backup_with_dir(exclude, profile, None)
+    }
+
+    pub fn find_latest_backup_with_dir(
+        exclude: &str,
+        profile: Option<&str>,
+        storage_dir_override: Option<PathBuf>,
+    ) -> Result<Option<String>, anyhow::Error> {
+        let storage_dir = match storage_dir_override {
+            Some(dir) => dir,
+            None => Self::base_storage_dir()?,
+        };
+        if !storage_dir.exists() {
+            return Ok(None);
+        }
+
+        let mut latest: Option<(String, String)> = None;
+        for entry in fs::read_dir(&storage_dir)? {
+            let entry = entry?;
+            let backup_name = entry.file_name().to_string_lossy().into_owned();
+            if backup_name == exclude || !entry.path().join("manifest.json").exists() {
+                continue;
+            }
+            let Some(manifest) = Self::load_in(&storage_dir, &backup_name).ok() else {
+                continue;
+            };
+            if manifest.machine_profile.as_deref() != profile {
+                continue;
+            }
+            if latest.as_ref().is_none_or(|(_, created_at)| manifest.created_at > *created_at) {
+                latest = Some((backup_name, manifest.created_at));
+            }
+        }
+
+        Ok(latest.map(|(name, _)| name))
+    }
+
+    /// Link this backup after `previous` in the backup-level chain.
+    ///
+    /// Fails if `previous` doesn't exist, or if it already (transitively)
+    /// follows this backup, which would create a cycle.
+    pub fn set_previous_backup(&mut self, previous: &str) -> Result<(), anyhow::Error> {
+        self.set_previous_backup_with_dir(previous, None)
+    }
+
+    pub fn set_previous_backup_with_dir(
+        &mut self,
+        previous: &str,
+        storage_dir_override: Option<PathBuf>,
+    ) -> Result<(), anyhow::Error> {
+        let storage_dir = match storage_dir_override {
+            Some(dir) => dir,
+            None => self.storage_root()?,
+        };
+
+        if previous == self.name {
+            return Err(anyhow!("Backup '{}' cannot follow itself", self.name));
+        }
+
+        let previous_manifest = Self::load_in(&storage_dir, previous).map_err(|_| {
+            anyhow!(
+                "Cannot link '{}' to previous backup '{}': it does not exist",
+                self.name,
+                previous
+            )
+        })?;
+
+        // Walk the target's ancestors; meeting ourselves means a cycle
+        let mut visited = std::collections::HashSet::new();
+        let mut ancestor = previous_manifest.previous_backup.clone();
+        while let Some(name) = ancestor {
+            if name == self.name {
+                return Err(anyhow!(
+                    "Linking '{}' to '{}' would create a cycle in the backup chain",
+                    self.name,
+                    previous
+                ));
+            }
+            if !visited.insert(name.clone()) {
+                break;
+            }
+            ancestor = Self::load_in(&storage_dir, &name)
+                .ok()
+                .and_then(|m| m.previous_backup);
+        }
+
+        self.previous_backup = Some(previous.to_string());
+        self.previous_backup_hash = Some(previous_manifest.calculate_backup_hash());
+        Ok(())
+    }
+
+    /// Re-point backup `name` at `new_parent` in the backup-level chain, or
+    /// make it the start of a chain with `None`, and save it with a fresh
+    /// `backup_chain_hash`. Repairs links left dangling by deleting a
+    /// backup by hand; the same existence and cycle checks as
+    /// `set_previous_backup` apply.
+    pub fn rebase_backup_in(
+        storage_dir: &Path,
+        name: &str,
+        new_parent: Option<&str>,
+    ) -> Result<(), anyhow::Error> {
+        let mut manifest = Self::load_in(storage_dir, name)
+            .map_err(|_| anyhow!("Backup '{}' does not exist", name))?;
+        match new_parent {
+            Some(parent) => {
+                manifest.set_previous_backup_with_dir(parent, Some(storage_dir.to_path_buf()))?
+            }
+            None => {
+                manifest.previous_backup = None;
+                manifest.previous_backup_hash = None;
+            }
+        }
+        manifest.save_in(storage_dir)
+    }
+
+    /// Combine backups `a` and `b` into a new backup `into`.
+    ///
+    /// Entries are unioned by `logical_path`; when both backups have a path
+    /// the entry from the more recently created backup wins and the conflict
+    /// is reported. Blob files are hard-linked from the source backups where
+    /// possible, and `into` gets a fresh blob chain of its own.
+    pub fn merge_backups(a: &str, b: &str, into: &str) -> Result<MergeReport, anyhow::Error> {
+        Self::merge_backups_with_dir(a, b, into, None)
+    }
+
+    pub fn merge_backups_with_dir(
+        a: &str,
+        b: &str,
+        into: &str,
+        storage_dir_override: Option<PathBuf>,
+    ) -> Result<MergeReport, anyhow::Error> {
+        let storage_dir = match storage_dir_override {
+            Some(dir) => dir,
+            None => Self::base_storage_dir()?,
+        };
+
+        if into == a || into == b {
+            return Err(anyhow!("Cannot merge into one of the source backups"));
+        }
+        if storage_dir.join(into).join("manifest.json").exists() {
+            return Err(anyhow!("Backup '{}' already exists", into));
+        }
+
+        let load = |name: &str| {
+            Self::load_in(&storage_dir, name)
+                .map_err(|_| anyhow!("Backup '{}' does not exist", name))
+        };
+        let (first, second) = (load(a)?, load(b)?);
+        let (older, newer) = if second.created_at >= first.created_at {
+            (first, second)
+        } else {
+            (second, first)
+        };
+
+        // Later sources overwrite earlier ones, so walking older then newer
+        // leaves the newest entry for every path
+        let mut merged_entries: Vec<(Entry, &Manifest)> = Vec::new();
+        let mut by_path: HashMap<String, usize> = HashMap::new();
+        let mut conflicts = Vec::new();
+        for source in [&older, &newer] {
+            for entry in &source.entries {
+                match by_path.get(&entry.logical_path) {
+                    Some(&i) => {
+                        let (kept, kept_source) = &merged_entries[i];
+                        if kept_source.name != source.name
+                            && (kept.blob_id != entry.blob_id
+                                || kept.inline_data != entry.inline_data)
+                        {
+                            conflicts.push(MergeConflict {
+                                logical_path: entry.logical_path.clone(),
+                                kept_from: source.name.clone(),
+                                dropped_from: kept_source.name.clone(),
+                            });
+                        }
+                        merged_entries[i] = (entry.clone(), source);
+                    }
+                    None => {
+                        by_path.insert(entry.logical_path.clone(), merged_entries.len());
+                        merged_entries.push((entry.clone(), source));
+                    }
+                }
+            }
+        }
+
+        let mut merged = Self::new(
+            into.to_string(),
+            chrono::Utc::now().to_rfc3339(),
+            newer.os_source.clone(),
+        );
+        let blob_dir = pool::write_dir(&storage_dir, into);
+        fs::create_dir_all(&blob_dir)?;
+        let mut chain_manager = BlobChainManager::new_empty(storage_dir.clone(), into.to_string());
+
+        for (entry, source) in &merged_entries {
+            if entry.inline_data.is_some() || merged.blobs.contains_key(&entry.blob_id) {
+                continue;
+            }
+            let found = [*source, &older, &newer].into_iter().find_map(|m| {
+                m.blobs.get(&entry.blob_id).map(|blob| (m.name.clone(), Cow::Borrowed(blob)))
+            });
+            let (owner, payload) = match found {
+                Some(found) => found,
+                // Borrowed by a source through global dedup from a third backup
+                None => source.resolve_blob(&storage_dir, &entry.blob_id).with_context(|| {
+                    format!(
+                        "Blob '{}' for '{}' is missing from both backups",
+                        entry.blob_id, entry.logical_path
+                    )
+                })?,
+            };
+
+            let file_name = format!("{}.tar.zst", entry.blob_id);
+            let owner_blob_dir = storage_dir.join(&owner).join("blobs");
+            let dest = blob_dir.join(&file_name);
+            if !dest.exists() && fs::hard_link(owner_blob_dir.join(&file_name), &dest).is_err() {
+                // Packed, pooled or on another filesystem: copy the stored bytes
+                let stored = pack::read_blob_in_storage(&storage_dir, &owner, &entry.blob_id);
+                let bytes = match stored {
+                    std::result::Result::Ok(bytes) => bytes,
+                    Err(_) => payload.decode()?,
+                };
+                write_atomic(&dest, &bytes)?;
+            }
+
+            let mut blob = payload.into_owned();
+            chain_manager.add_blob_to_chain(&entry.blob_id, &mut blob)?;
+            merged.blobs.insert(entry.blob_id.clone(), blob);
+        }
+
+        merged.entries = merged_entries.into_iter().map(|(entry, _)| entry).collect();
+        merged.save_in(&storage_dir)?;
+
+        Ok(MergeReport {
+            name: merged.name.clone(),
+            entry_count: merged.entries.len(),
+            blob_count: merged.blobs.len(),
+            conflicts,
+        })
+    }
+
     pub fn add_blob_for_testing(&mut self, blob_id: String, blob: BlobPayload) {
         self.blobs.insert(blob_id, blob);
     }
@@ -1046,11 +2380,24 @@ impl Manifest {
         &self,
         storage_dir_override: Option<PathBuf>,
     ) -> Result<bool, anyhow::Error> {
-        let storage_dir = storage_dir_override.unwrap_or_else(|| Self::base_storage_dir().unwrap());
+        let storage_dir = storage_dir_override.unwrap_or_else(|| self.storage_root().unwrap());
         let chain_manager = BlobChainManager::new(storage_dir, self.name.clone())?;
         chain_manager.verify_blob_chain(&self.blobs)
     }
 
+    pub fn export_chain_links(&self) -> Result<Vec<ChainLink>, anyhow::Error> {
+        self.export_chain_links_with_dir(None)
+    }
+
+    pub fn export_chain_links_with_dir(
+        &self,
+        storage_dir_override: Option<PathBuf>,
+    ) -> Result<Vec<ChainLink>, anyhow::Error> {
+        let storage_dir = storage_dir_override.unwrap_or_else(|| self.storage_root().unwrap());
+        let chain_manager = BlobChainManager::new(storage_dir, self.name.clone())?;
+        Ok(chain_manager.export_links(&self.blobs))
+    }
+
     pub fn get_blob_chain_info(&self) -> Result<String, anyhow::Error> {
         self.get_blob_chain_info_with_dir(None)
     }
@@ -1059,7 +2406,7 @@ impl Manifest {
         &self,
         storage_dir_override: Option<PathBuf>,
     ) -> Result<String, anyhow::Error> {
-        let storage_dir = storage_dir_override.unwrap_or_else(|| Self::base_storage_dir().unwrap());
+        let storage_dir = storage_dir_override.unwrap_or_else(|| self.storage_root().unwrap());
         let chain_manager = BlobChainManager::new(storage_dir, self.name.clone())?;
         let metadata = chain_manager.get_chain_info();
         Ok(format!(
@@ -1068,4 +2415,232 @@ impl Manifest {
             metadata.chain_integrity_hash
         ))
     }
+
+    /// Check that every blob this backup references can actually be restored:
+    /// its blob file is on disk, hashes to the recorded `sha256`, decodes to a
+    /// readable TAR and holds the members the entries point at. Unlike the
+    /// chain checks this reads every blob. Returns false at the first bad
+    /// blob, which is logged.
+    pub fn verify_backup_integrity(&self) -> Result<bool, anyhow::Error> {
+        self.verify_backup_integrity_with_dir(None)
+    }
+
+    pub fn verify_backup_integrity_with_dir(
+        &self,
+        storage_dir_override: Option<PathBuf>,
+    ) -> Result<bool, anyhow::Error> {
+        let storage_dir = match storage_dir_override {
+            Some(dir) => dir,
+            None => self.storage_root()?,
+        };
+
+        let mut blob_ids: Vec<&str> =
+            self.entries.iter().filter_map(Entry::stored_blob_id).collect();
+        blob_ids.sort_unstable();
+        blob_ids.dedup();
+
+        for blob_id in blob_ids {
+            if let Err(e) = self.verify_stored_blob(&storage_dir, blob_id, false) {
+                println!("Backup '{}' failed integrity check: {}", self.name, e);
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// `verify_backup_integrity`, plus reading every TAR member to the end
+    /// and comparing it with the entry's `original_sha256` when recorded.
+    /// Catches blobs whose stored bytes are intact but whose TAR is not.
+    /// Blobs are checked in parallel; the error describes the first failure
+    /// found.
+    pub fn verify_backup_deep(&self) -> Result<(), anyhow::Error> {
+        self.verify_backup_deep_with_dir(None)
+    }
+
+    pub fn verify_backup_deep_with_dir(
+        &self,
+        storage_dir_override: Option<PathBuf>,
+    ) -> Result<(), anyhow::Error> {
+        let storage_dir = match storage_dir_override {
+            Some(dir) => dir,
+            None => self.storage_root()?,
+        };
+
+        let mut blob_ids: Vec<&str> =
+            self.entries.iter().filter_map(Entry::stored_blob_id).collect();
+        blob_ids.sort_unstable();
+        blob_ids.dedup();
+
+        blob_ids
+            .par_iter()
+            .try_for_each(|blob_id| self.verify_stored_blob(&storage_dir, blob_id, true))?;
+
+        for entry in self.entries.iter().filter(|e| e.inline_data.is_some()) {
+            let content = self
+                .read_entry(entry)
+                .with_context(|| format!("Inline data of {} is unreadable", entry.logical_path))?;
+            if let Some(expected) = &entry.original_sha256 {
+                if *expected != hex::encode(Sha256::digest(&content)) {
+                    return Err(anyhow!(
+                        "Inline data of {} does not match it as backed up",
+                        entry.logical_path
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Compare each blob's bytes in the manifest (`b64`) with its blob file,
+    /// returning one line per blob where the two copies disagree. Blobs
+    /// without data in the manifest only need their file to exist.
+    pub fn blob_copy_mismatches(&self) -> Result<Vec<String>, anyhow::Error> {
+        self.blob_copy_mismatches_with_dir(None)
+    }
+
+    pub fn blob_copy_mismatches_with_dir(
+        &self,
+        storage_dir_override: Option<PathBuf>,
+    ) -> Result<Vec<String>, anyhow::Error> {
+        let storage_dir = match storage_dir_override {
+            Some(dir) => dir,
+            None => self.storage_root()?,
+        };
+
+        let mut blob_ids: Vec<&String> = self.blobs.keys().collect();
+        blob_ids.sort_unstable();
+
+        let mut mismatches = Vec::new();
+        for blob_id in blob_ids {
+            let blob = &self.blobs[blob_id];
+            let on_disk = match pack::read_blob_in_storage(&storage_dir, &self.name, blob_id) {
+                std::result::Result::Ok(bytes) => bytes,
+                Err(e) => {
+                    mismatches.push(format!("Blob {} has no readable blob file: {}", blob_id, e));
+                    continue;
+                }
+            };
+            if !blob.has_data() {
+                continue;
+            }
+            match blob.decode() {
+                std::result::Result::Ok(in_manifest) if in_manifest == on_disk => {}
+                std::result::Result::Ok(in_manifest) => mismatches.push(format!(
+                    "Blob {} differs: {} bytes in the manifest, {} bytes in its blob file",
+                    blob_id,
+                    in_manifest.len(),
+                    on_disk.len()
+                )),
+                Err(_) => mismatches.push(format!(
+                    "Blob {}: {}",
+                    blob_id,
+                    messages::BLOB_BASE64_INVALID
+                )),
+            }
+        }
+        Ok(mismatches)
+    }
+
+    /// Payload metadata of `blob_id` and the backup holding it: this backup's
+    /// own, or for a blob borrowed through global dedup (whose ID is its
+    /// content hash) the one recorded by the backup that stores it
+    fn resolve_blob(
+        &self,
+        storage_dir: &Path,
+        blob_id: &str,
+    ) -> Result<(String, Cow<'_, BlobPayload>), anyhow::Error> {
+        if let Some(blob) = self.blobs.get(blob_id) {
+            return Ok((self.name.clone(), Cow::Borrowed(blob)));
+        }
+        if let Some((owner, owner_blob_id)) = dedup_index::find_blob(storage_dir, blob_id)? {
+            if owner_blob_id == blob_id {
+                let mut manifest = Self::load_in(storage_dir, &owner)?;
+                if let Some(blob) = manifest.blobs.remove(blob_id) {
+                    return Ok((owner, Cow::Owned(blob)));
+                }
+            }
+        }
+        Err(anyhow!("Blob {} is referenced but not in the manifest", blob_id))
+    }
+
+    /// Checks behind `verify_backup_integrity`; `deep` also reads and hashes
+    /// each member's content
+    fn verify_stored_blob(
+        &self,
+        storage_dir: &Path,
+        blob_id: &str,
+        deep: bool,
+    ) -> Result<(), anyhow::Error> {
+        let (owner, blob) = self.resolve_blob(storage_dir, blob_id)?;
+
+        let bytes = pack::read_blob_in_storage(storage_dir, &owner, blob_id)?;
+
+        let actual = hex::encode(Sha256::digest(&bytes));
+        if actual != blob.get_sha256() {
+            return Err(anyhow!(
+                "Blob {} file hash mismatch: expected {}, got {}",
+                blob_id,
+                blob.get_sha256(),
+                actual
+            ));
+        }
+
+        let dictionary = match blob.get_dictionary() {
+            Some(id) => Some(
+                dictionary::load(storage_dir, &self.name, id)
+                    .with_context(|| format!("Blob {} cannot be decoded", blob_id))?,
+            ),
+            None => None,
+        };
+        let tar_bytes = Self::stored_bytes_to_tar(blob.get_format(), bytes, dictionary.as_deref())
+            .with_context(|| format!("Blob {} cannot be decoded", blob_id))?;
+        // Member path -> content hash, only computed when deep
+        let mut members: HashMap<String, Option<String>> = HashMap::new();
+        let mut archive = tar::Archive::new(&tar_bytes[..]);
+        for member in archive
+            .entries()
+            .with_context(|| format!("Blob {} is not a readable TAR", blob_id))?
+        {
+            let mut member =
+                member.with_context(|| format!("Blob {} has a broken TAR member", blob_id))?;
+            let path = member.path()?.to_string_lossy().into_owned();
+            let content_hash = if deep {
+                let mut content = Vec::new();
+                member.read_to_end(&mut content).with_context(|| {
+                    format!("Blob {} member '{}' is unreadable", blob_id, path)
+                })?;
+                Some(hex::encode(Sha256::digest(&content)))
+            } else {
+                None
+            };
+            members.insert(path, content_hash);
+        }
+        if members.is_empty() {
+            return Err(anyhow!("Blob {} contains no files", blob_id));
+        }
+
+        for entry in self.entries.iter().filter(|e| e.blob_id == blob_id) {
+            if let Some(member) = &entry.tar_member {
+                let Some(content_hash) = members.get(member) else {
+                    return Err(anyhow!(
+                        "Blob {} has no member '{}' for {}",
+                        blob_id,
+                        member,
+                        entry.logical_path
+                    ));
+                };
+                if let (Some(expected), Some(actual)) = (&entry.original_sha256, content_hash) {
+                    if expected != actual {
+                        return Err(anyhow!(
+                            "Blob {} member '{}' does not match {} as backed up",
+                            blob_id,
+                            member,
+                            entry.logical_path
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
 }
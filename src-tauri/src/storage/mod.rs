@@ -1,8 +1,24 @@
+pub mod atomic;
 pub mod blob_chain;
 pub mod blobs;
+pub mod bundle;
+pub mod codec;
+pub mod crypto;
+pub mod dedup_index;
+pub mod dictionary;
+pub mod disk_space;
 pub mod entry;
+pub mod hashing;
+pub mod heal;
+pub mod health;
 pub mod manifest;
+pub mod pack;
+pub mod paths;
 pub mod performance;
+pub mod pool;
+pub mod prune;
+pub mod refcount;
+pub mod scrub;
 
 #[cfg(test)]
 mod tests;
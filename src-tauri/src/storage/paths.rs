@@ -0,0 +1,61 @@
+use std::path::{Component, Path, PathBuf};
+
+/// Portable form of `path` relative to `root`, joined with `/` regardless of
+/// the host OS. Returns `None` when `path` is not under `root`.
+pub fn relative_to(path: &Path, root: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let parts: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    if parts.is_empty() {
+        return None;
+    }
+    Some(parts.join("/"))
+}
+
+/// Re-root a portable relative path under `root` using native separators.
+/// Rejects absolute paths and `..` components so a manifest can never point
+/// a restore outside the app's directory.
+pub fn join_relative(root: &Path, relative: &str) -> Option<PathBuf> {
+    let mut path = root.to_path_buf();
+    for part in relative.split(['/', '\\']).filter(|p| !p.is_empty()) {
+        match Path::new(part).components().next() {
+            Some(Component::Normal(_)) => path.push(part),
+            Some(Component::CurDir) => {}
+            _ => return None,
+        }
+    }
+    if path == root {
+        return None;
+    }
+    Some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_round_trip() {
+        let root = Path::new("/home/user/.config/zed");
+        let file = root.join("themes").join("dark.json");
+
+        let relative = relative_to(&file, root).unwrap();
+        assert_eq!(relative, "themes/dark.json");
+
+        let windows_root = Path::new("C:/Users/user/AppData/Roaming/Zed");
+        assert_eq!(
+            join_relative(windows_root, &relative).unwrap(),
+            windows_root.join("themes").join("dark.json")
+        );
+    }
+
+    #[test]
+    fn test_join_relative_rejects_escapes() {
+        let root = Path::new("/home/user/.config/zed");
+        assert!(join_relative(root, "../../.ssh/id_rsa").is_none());
+        assert!(join_relative(root, "").is_none());
+        assert!(relative_to(Path::new("/etc/hosts"), root).is_none());
+    }
+}
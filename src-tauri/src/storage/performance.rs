@@ -1,6 +1,8 @@
 use once_cell::sync::Lazy;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
+use crate::settings::PerformanceProfile;
+
 /// Performance configuration for optimized backup operations
 #[derive(Debug, Clone)]
 pub struct PerformanceConfig {
@@ -20,12 +22,25 @@ pub struct PerformanceConfig {
     pub parallel_dedup: bool,
     /// Maximum files to process in a single batch
     pub max_batch_size: usize,
+    /// Files smaller than this many bytes are kept compressed in their
+    /// manifest entry instead of in a blob file; 0 disables inlining
+    pub inline_threshold: usize,
 }
 
 /// Global performance configuration instance
 pub static PERFORMANCE_CONFIG: Lazy<PerformanceConfig> =
     Lazy::new(|| PerformanceConfig::auto_detect());
 
+/// Default `inline_threshold`: a few KB covers most single-setting files
+pub const DEFAULT_INLINE_THRESHOLD: usize = 4 * 1024;
+
+/// Backups estimated above this size are saved with the `Fast` profile
+pub const FAST_PROFILE_MIN_MB: f64 = 2048.0;
+/// Backups estimated below this size are saved with `MaxCompression`. Kept
+/// low: level 22 without adaptive lowering runs at a few MB a second, which
+/// only a backup of a handful of config files doesn't notice.
+pub const MAX_COMPRESSION_PROFILE_MAX_MB: f64 = 2.0;
+
 /// Performance metrics tracking
 pub struct PerformanceMetrics {
     pub total_files_processed: AtomicUsize,
@@ -173,6 +188,7 @@ impl PerformanceConfig {
             adaptive_compression: true,
             parallel_dedup: cpu_count > 2,
             max_batch_size: (cpu_count * 10).min(200).max(20),
+            inline_threshold: DEFAULT_INLINE_THRESHOLD,
         }
     }
 
@@ -222,6 +238,49 @@ impl PerformanceConfig {
         }
     }
 
+    /// The preset `profile` names; `Auto` falls back to `balanced()`
+    pub fn for_profile(profile: PerformanceProfile) -> Self {
+        match profile {
+            PerformanceProfile::Fast => Self::fast(),
+            PerformanceProfile::MaxCompression => Self::max_compression(),
+            PerformanceProfile::Balanced | PerformanceProfile::Auto => Self::balanced(),
+        }
+    }
+
+    /// Resolve `Auto` for a backup of about `total_size_mb`: big backups
+    /// favour speed, small ones are cheap to squeeze as hard as possible.
+    /// Returns the profile to use and why it was picked.
+    pub fn resolve_profile(
+        requested: PerformanceProfile,
+        total_size_mb: f64,
+    ) -> (PerformanceProfile, String) {
+        if requested != PerformanceProfile::Auto {
+            return (requested, "chosen explicitly".to_string());
+        }
+        if total_size_mb > FAST_PROFILE_MIN_MB {
+            (
+                PerformanceProfile::Fast,
+                format!("{:.0}MB is over {:.0}MB", total_size_mb, FAST_PROFILE_MIN_MB),
+            )
+        } else if total_size_mb < MAX_COMPRESSION_PROFILE_MAX_MB {
+            (
+                PerformanceProfile::MaxCompression,
+                format!(
+                    "{:.1}MB is under {:.0}MB",
+                    total_size_mb, MAX_COMPRESSION_PROFILE_MAX_MB
+                ),
+            )
+        } else {
+            (
+                PerformanceProfile::Balanced,
+                format!(
+                    "{:.0}MB is between {:.0}MB and {:.0}MB",
+                    total_size_mb, MAX_COMPRESSION_PROFILE_MAX_MB, FAST_PROFILE_MIN_MB
+                ),
+            )
+        }
+    }
+
     /// Get adaptive compression level based on file size
     pub fn get_adaptive_compression_level(&self, file_size: usize) -> i32 {
         if !self.adaptive_compression {
@@ -251,6 +310,11 @@ impl PerformanceConfig {
             .min(base_chunk_size * 4)
     }
 
+    /// Whether a file of `file_size` bytes is small enough to be inlined
+    pub fn should_inline(&self, file_size: u64) -> bool {
+        file_size < self.inline_threshold as u64
+    }
+
     /// Check if parallel processing should be used for given data size
     pub fn should_use_parallel(&self, data_size: usize) -> bool {
         data_size > self.chunk_size && self.thread_count > 1
@@ -268,6 +332,17 @@ impl PerformanceConfig {
         }
     }
 
+    /// Copy of this configuration with a different compression level, for
+    /// overriding the level of a single operation
+    pub fn with_compression_level(&self, level: i32) -> Result<Self, String> {
+        let config = Self {
+            compression_level: level,
+            ..self.clone()
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
     /// Validate configuration settings
     pub fn validate(&self) -> Result<(), String> {
         if self.thread_count == 0 {
@@ -393,6 +468,45 @@ mod tests {
         assert!(large_file_level <= small_file_level);
     }
 
+    #[test]
+    fn test_auto_profile_follows_backup_size() {
+        use PerformanceProfile::*;
+        let resolve = |size_mb| PerformanceConfig::resolve_profile(Auto, size_mb).0;
+        assert_eq!(resolve(0.5), MaxCompression);
+        assert_eq!(resolve(5.0), Balanced);
+        assert_eq!(resolve(500.0), Balanced);
+        assert_eq!(resolve(10_000.0), Fast);
+        assert_eq!(PerformanceConfig::resolve_profile(Fast, 5.0).0, Fast);
+
+        assert_eq!(PerformanceConfig::for_profile(MaxCompression).compression_level, 22);
+        assert_eq!(PerformanceConfig::for_profile(Fast).compression_level, 6);
+    }
+
+    #[test]
+    fn test_with_compression_level() {
+        let config = PerformanceConfig::balanced();
+
+        let archival = config.with_compression_level(22).unwrap();
+        assert_eq!(archival.compression_level, 22);
+        assert_eq!(archival.thread_count, config.thread_count);
+
+        assert!(config.with_compression_level(0).is_err());
+        assert!(config.with_compression_level(23).is_err());
+    }
+
+    #[test]
+    fn test_inline_threshold() {
+        let config = PerformanceConfig::balanced();
+        assert!(config.should_inline(100));
+        assert!(!config.should_inline(DEFAULT_INLINE_THRESHOLD as u64));
+
+        let disabled = PerformanceConfig {
+            inline_threshold: 0,
+            ..config
+        };
+        assert!(!disabled.should_inline(0));
+    }
+
     #[test]
     fn test_performance_metrics() {
         let metrics = PerformanceMetrics::new();
@@ -0,0 +1,82 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::storage::{atomic::write_atomic, pack};
+
+/// Directory of the storage-wide blob pool, next to the backup directories
+/// (so `blobs` can't be used as a backup name)
+pub const POOL_DIR: &str = "blobs";
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct PoolMigration {
+    pub backups_migrated: usize,
+    /// Blobs copied into the pool
+    pub blobs_pooled: usize,
+    /// Per-backup copies dropped because the pool already held the blob
+    pub duplicates_removed: usize,
+    /// Size of the dropped duplicates
+    pub bytes_freed: u64,
+}
+
+/// The pool of `storage_dir`. Blob IDs are the SHA256 of the stored bytes,
+/// so one file per ID serves every backup referencing it.
+pub fn pool_dir(storage_dir: &Path) -> PathBuf {
+    storage_dir.join(POOL_DIR)
+}
+
+/// Directory new blobs of backup `backup` are written to: the pool with
+/// `pooled_blobs` on, else the backup's own `blobs` directory
+pub fn write_dir(storage_dir: &Path, backup: &str) -> PathBuf {
+    if crate::settings::current().pooled_blobs {
+        pool_dir(storage_dir)
+    } else {
+        storage_dir.join(backup).join("blobs")
+    }
+}
+
+/// Move every backup's blobs into the pool, one backup at a time. A
+/// backup's `blobs` directory is removed only once all of its blobs are in
+/// the pool, and readers look in the pool after the backup's own directory,
+/// so an interrupted migration leaves every blob readable and can simply be
+/// run again.
+pub fn migrate_in(storage_dir: &Path) -> Result<PoolMigration> {
+    let pool = pool_dir(storage_dir);
+    let mut migration = PoolMigration::default();
+    if !storage_dir.is_dir() {
+        return Ok(migration);
+    }
+
+    let mut backup_dirs: Vec<PathBuf> = fs::read_dir(storage_dir)?
+        .filter_map(|dir| dir.ok())
+        .map(|dir| dir.path())
+        .filter(|dir| dir.join("manifest.json").is_file())
+        .collect();
+    backup_dirs.sort();
+
+    for backup_dir in backup_dirs {
+        let blob_dir = backup_dir.join("blobs");
+        if !blob_dir.is_dir() {
+            continue;
+        }
+        fs::create_dir_all(&pool)?;
+        for blob_id in pack::blob_ids(&blob_dir)? {
+            let bytes = pack::read_blob(&blob_dir, &blob_id)?;
+            let pooled = pool.join(format!("{}.tar.zst", blob_id));
+            if pooled.is_file() {
+                migration.duplicates_removed += 1;
+                migration.bytes_freed += bytes.len() as u64;
+            } else {
+                write_atomic(&pooled, &bytes)?;
+                migration.blobs_pooled += 1;
+            }
+        }
+        fs::remove_dir_all(&blob_dir)?;
+        migration.backups_migrated += 1;
+    }
+    Ok(migration)
+}
@@ -0,0 +1,307 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+use crate::storage::{
+    atomic::write_atomic, blobs::BlobPayload, dedup_index, dictionary, entry::Entry,
+    manifest::Manifest, pack, pool, refcount,
+};
+
+/// Delete every backup created more than `max_age_days` ago, except `keep`
+/// (the backup just saved). Returns the names of the deleted backups.
+pub fn prune_backups(max_age_days: u32, keep: Option<&str>) -> Result<Vec<String>> {
+    let cutoff = Utc::now() - Duration::days(i64::from(max_age_days));
+    prune_backups_in(&Manifest::base_storage_dir()?, cutoff, keep)
+}
+
+/// Delete the backups created before `cutoff`, except `keep`, without
+/// breaking the ones that stay:
+///
+/// - blobs a surviving backup borrows from a pruned one (global dedup) are
+///   copied into the survivor's own blob directory first, along with the
+///   compression dictionaries those blobs were compressed against;
+/// - a survivor whose `previous_backup` is pruned is relinked to its
+///   nearest surviving ancestor, or becomes the start of the chain.
+///
+/// Backups with an unparseable `created_at` are never pruned.
+pub fn prune_backups_in(
+    storage_dir: &Path,
+    cutoff: DateTime<Utc>,
+    keep: Option<&str>,
+) -> Result<Vec<String>> {
+    let manifests = Manifest::list_all_with_dir(Some(storage_dir.to_path_buf()))?;
+    let doomed: HashSet<String> = manifests
+        .iter()
+        .filter(|m| keep != Some(m.name.as_str()))
+        .filter(|m| m.created_at_datetime().is_some_and(|t| t < cutoff))
+        .map(|m| m.name.clone())
+        .collect();
+    remove_backups(storage_dir, manifests, doomed)
+}
+
+/// What deleting a backup would do, computed without touching anything
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeletePreview {
+    /// Blobs no other backup references, whose files would be removed
+    pub blobs_freed: usize,
+    /// Stored size of those blobs
+    pub bytes_reclaimed: u64,
+    /// Backups chained onto this one (`previous_backup`) or borrowing its
+    /// blobs through global dedup, sorted
+    pub dependent_backups: Vec<String>,
+}
+
+/// Delete backup `name` and free the blobs nobody else references
+pub fn delete_backup(name: &str, force: bool) -> Result<()> {
+    delete_backup_in(&Manifest::base_storage_dir()?, name, force)
+}
+
+pub fn preview_delete(name: &str) -> Result<DeletePreview> {
+    preview_delete_in(&Manifest::base_storage_dir()?, name)
+}
+
+/// Work out the consequences of deleting backup `name` from the blob
+/// reference counts and the other manifests
+pub fn preview_delete_in(storage_dir: &Path, name: &str) -> Result<DeletePreview> {
+    let manifests = Manifest::list_all_with_dir(Some(storage_dir.to_path_buf()))?;
+    let target = manifests
+        .iter()
+        .find(|m| m.name == name)
+        .ok_or_else(|| anyhow!("Backup '{}' does not exist", name))?;
+    let counts = refcount::load_refcounts(storage_dir)?;
+
+    let mut own_references: HashMap<&str, u64> = HashMap::new();
+    for blob_id in target.entries.iter().filter_map(Entry::stored_blob_id) {
+        *own_references.entry(blob_id).or_default() += 1;
+    }
+    let mut preview = DeletePreview::default();
+    for (blob_id, references) in own_references {
+        if counts.by_blob.get(blob_id).is_none_or(|&count| count <= references) {
+            preview.blobs_freed += 1;
+            preview.bytes_reclaimed += target.blobs.get(blob_id).map_or(0, |b| b.get_size());
+        }
+    }
+
+    let target_dir = storage_dir.join(name).join("blobs");
+    let target_index = pack::PackIndex::load(&target_dir)?;
+    for other in manifests.iter().filter(|m| m.name != name) {
+        let chained = other.previous_backup.as_deref() == Some(name);
+        let own_dir = storage_dir.join(&other.name).join("blobs");
+        let own_index = pack::PackIndex::load(&own_dir)?;
+        let borrows = other.entries.iter().filter_map(Entry::stored_blob_id).any(|blob_id| {
+            !own_index.has_blob(&own_dir, blob_id) && target_index.has_blob(&target_dir, blob_id)
+        });
+        if chained || borrows {
+            preview.dependent_backups.push(other.name.clone());
+        }
+    }
+    preview.dependent_backups.sort();
+    Ok(preview)
+}
+
+/// Delete backup `name` the same way pruning does: borrowed blobs are handed
+/// to the backups that use them and the chain is relinked around it. Blob
+/// files whose reference count drops to zero are removed right away.
+///
+/// Refuses while other backups depend on it (see `preview_delete_in`)
+/// unless `force` is set.
+pub fn delete_backup_in(storage_dir: &Path, name: &str, force: bool) -> Result<()> {
+    let preview = preview_delete_in(storage_dir, name)?;
+    if !force && !preview.dependent_backups.is_empty() {
+        return Err(anyhow!(
+            "Backup '{}' is used by {}; delete it with force to relink them",
+            name,
+            preview.dependent_backups.join(", ")
+        ));
+    }
+    let manifests = Manifest::list_all_with_dir(Some(storage_dir.to_path_buf()))?;
+    remove_backups(storage_dir, manifests, HashSet::from([name.to_string()]))?;
+    Ok(())
+}
+
+/// Blob files of backup `name` none of its entries reference
+pub fn find_orphan_blobs(name: &str) -> Result<Vec<String>> {
+    find_orphan_blobs_in(&Manifest::base_storage_dir()?, name)
+}
+
+pub fn find_orphan_blobs_in(storage_dir: &Path, name: &str) -> Result<Vec<String>> {
+    let manifest = Manifest::list_all_with_dir(Some(storage_dir.to_path_buf()))?
+        .into_iter()
+        .find(|m| m.name == name)
+        .ok_or_else(|| anyhow!("Backup '{}' does not exist", name))?;
+    Ok(manifest.orphan_blobs_in(storage_dir))
+}
+
+pub fn gc_orphaned_blobs(name: &str) -> Result<Vec<String>> {
+    gc_orphaned_blobs_in(&Manifest::base_storage_dir()?, name)
+}
+
+/// Remove the loose orphan files of backup `name`, sparing any another
+/// backup still references; returns the removed blob IDs. Orphans inside
+/// the pack stay until it is rewritten.
+pub fn gc_orphaned_blobs_in(storage_dir: &Path, name: &str) -> Result<Vec<String>> {
+    let counts = refcount::load_refcounts(storage_dir)?;
+    let blob_dir = storage_dir.join(name).join("blobs");
+    let mut removed = Vec::new();
+    for blob_id in find_orphan_blobs_in(storage_dir, name)? {
+        if counts.by_blob.get(&blob_id).is_some_and(|&count| count > 0) {
+            continue;
+        }
+        if pack::remove_loose_blob(&blob_dir, &blob_id)? {
+            removed.push(blob_id);
+        }
+    }
+    Ok(removed)
+}
+
+/// Remove the `doomed` backups out of `manifests` (every backup in
+/// `storage_dir`); returns their names, sorted
+fn remove_backups(
+    storage_dir: &Path,
+    manifests: Vec<Manifest>,
+    doomed: HashSet<String>,
+) -> Result<Vec<String>> {
+    if doomed.is_empty() {
+        return Ok(Vec::new());
+    }
+    // Settle the counts while every doomed manifest is still on disk
+    refcount::load_refcounts(storage_dir)?;
+
+    let previous_of: HashMap<String, Option<String>> = manifests
+        .iter()
+        .map(|m| (m.name.clone(), m.previous_backup.clone()))
+        .collect();
+
+    let (doomed_manifests, survivors): (Vec<Manifest>, Vec<Manifest>) =
+        manifests.into_iter().partition(|m| doomed.contains(&m.name));
+    let survivor_names: Vec<String> = survivors.iter().map(|m| m.name.clone()).collect();
+
+    for mut survivor in survivors {
+        rescue_borrowed_blobs(storage_dir, &survivor, &doomed)?;
+        rescue_borrowed_dictionaries(storage_dir, &survivor, &doomed_manifests)?;
+
+        let Some(previous) = survivor.previous_backup.clone() else {
+            continue;
+        };
+        if !doomed.contains(&previous) {
+            continue;
+        }
+        // Walk up past pruned ancestors (guarding against broken cycles)
+        let mut ancestor = previous_of.get(&previous).cloned().flatten();
+        let mut seen = HashSet::new();
+        while let Some(name) = ancestor.clone() {
+            if !doomed.contains(&name) || !seen.insert(name.clone()) {
+                break;
+            }
+            ancestor = previous_of.get(&name).cloned().flatten();
+        }
+        match ancestor.filter(|name| !doomed.contains(name)) {
+            Some(ancestor) => {
+                survivor.set_previous_backup_with_dir(&ancestor, Some(storage_dir.to_path_buf()))?
+            }
+            None => {
+                survivor.previous_backup = None;
+                survivor.previous_backup_hash = None;
+            }
+        }
+        survivor.save_in(storage_dir)?;
+    }
+
+    let mut pruned: Vec<String> = doomed.into_iter().collect();
+    pruned.sort();
+    for name in &pruned {
+        fs::remove_dir_all(storage_dir.join(name))?;
+        dedup_index::forget_backup(storage_dir, name);
+        let chain_file = storage_dir.join(format!("{}_blob_chain.encrypted", name));
+        if chain_file.exists() {
+            fs::remove_file(chain_file)?;
+        }
+    }
+
+    // References go away only now that the manifests holding them are gone
+    let released = doomed_manifests
+        .iter()
+        .flat_map(|m| m.entries.iter().filter_map(Entry::stored_blob_id));
+    for blob_id in refcount::release_references(storage_dir, released)? {
+        for name in &survivor_names {
+            pack::remove_loose_blob(&storage_dir.join(name).join("blobs"), &blob_id)?;
+        }
+        pack::remove_loose_blob(&pool::pool_dir(storage_dir), &blob_id)?;
+    }
+    Ok(pruned)
+}
+
+/// Copy into `survivor`'s blob directory every blob it references that only
+/// a to-be-pruned backup holds
+fn rescue_borrowed_blobs(
+    storage_dir: &Path,
+    survivor: &Manifest,
+    doomed: &HashSet<String>,
+) -> Result<()> {
+    let own_dir = storage_dir.join(&survivor.name).join("blobs");
+    let own_index = pack::PackIndex::load(&own_dir)?;
+    let pool_dir = pool::pool_dir(storage_dir);
+    let pool_index = pack::PackIndex::load(&pool_dir)?;
+
+    let mut seen = HashSet::new();
+    for blob_id in survivor.entries.iter().filter_map(Entry::stored_blob_id) {
+        if !seen.insert(blob_id)
+            || own_index.has_blob(&own_dir, blob_id)
+            || pool_index.has_blob(&pool_dir, blob_id)
+        {
+            continue;
+        }
+        let held_by_doomed = doomed
+            .iter()
+            .find_map(|name| pack::read_blob(&storage_dir.join(name).join("blobs"), blob_id).ok());
+        if let Some(bytes) = held_by_doomed {
+            fs::create_dir_all(&own_dir)?;
+            write_atomic(&own_dir.join(format!("{}.tar.zst", blob_id)), &bytes)?;
+        }
+    }
+    Ok(())
+}
+
+/// Copy into `survivor`'s backup directory every compression dictionary
+/// its blobs (own or pooled) need that only a to-be-pruned backup holds
+fn rescue_borrowed_dictionaries(
+    storage_dir: &Path,
+    survivor: &Manifest,
+    doomed: &[Manifest],
+) -> Result<()> {
+    let blob_of = |blob_id: &str| {
+        survivor
+            .blobs
+            .get(blob_id)
+            .or_else(|| doomed.iter().find_map(|m| m.blobs.get(blob_id)))
+    };
+    let needed: HashSet<&str> = survivor
+        .entries
+        .iter()
+        .filter_map(Entry::stored_blob_id)
+        .filter_map(blob_of)
+        .filter_map(BlobPayload::get_dictionary)
+        .chain(survivor.compression_dictionary.as_deref())
+        .collect();
+
+    let own_dir = storage_dir.join(&survivor.name);
+    for id in needed {
+        if dictionary::dictionary_path(&own_dir, id).is_file() {
+            continue;
+        }
+        let held_by_doomed = doomed
+            .iter()
+            .map(|m| dictionary::dictionary_path(&storage_dir.join(&m.name), id))
+            .find(|path| path.is_file());
+        if let Some(path) = held_by_doomed {
+            dictionary::write(&own_dir, &fs::read(path)?)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,136 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::Path,
+    sync::Mutex,
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::storage::{atomic::write_atomic, entry::Entry, manifest::Manifest};
+
+const REFCOUNT_FILE: &str = "refcount.json";
+
+/// Serializes read-modify-write cycles of `refcount.json` within the process
+static REFCOUNT_LOCK: Mutex<()> = Mutex::new(());
+
+/// Number of manifest entries, across all backups, referencing each blob.
+///
+/// Kept in `refcount.json` next to the backups so deleting a backup knows
+/// which blobs nobody references anymore without scanning every manifest.
+/// Counts are raised before a manifest referencing them is written and
+/// lowered only after the references are gone, so a crash in between leaves
+/// a count too high (a blob kept too long), never too low.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct RefCounts {
+    /// Blob ID -> number of referencing entries; unreferenced blobs are absent
+    pub by_blob: BTreeMap<String, u64>,
+}
+
+impl RefCounts {
+    fn add<'a>(&mut self, blob_ids: impl IntoIterator<Item = &'a str>) {
+        for blob_id in blob_ids {
+            *self.by_blob.entry(blob_id.to_string()).or_default() += 1;
+        }
+    }
+
+    /// Returns the blob IDs whose count dropped to zero
+    fn release<'a>(&mut self, blob_ids: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+        let mut freed = Vec::new();
+        for blob_id in blob_ids {
+            if let Some(count) = self.by_blob.get_mut(blob_id) {
+                *count = count.saturating_sub(1);
+                if *count == 0 {
+                    self.by_blob.remove(blob_id);
+                    freed.push(blob_id.to_string());
+                }
+            }
+        }
+        freed
+    }
+
+    /// Load the counts; a storage directory without `refcount.json` (older
+    /// storage, or a deleted file) gets them rebuilt from its manifests
+    fn load_or_rebuild(storage_dir: &Path) -> Result<Self> {
+        let path = storage_dir.join(REFCOUNT_FILE);
+        if !path.exists() {
+            return count_references(storage_dir);
+        }
+        serde_json::from_str(&fs::read_to_string(&path)?)
+            .with_context(|| format!("Invalid blob reference counts: {}", path.display()))
+    }
+
+    fn save(&self, storage_dir: &Path) -> Result<()> {
+        fs::create_dir_all(storage_dir)?;
+        write_atomic(
+            &storage_dir.join(REFCOUNT_FILE),
+            serde_json::to_string_pretty(self)?.as_bytes(),
+        )
+    }
+}
+
+fn update<T>(storage_dir: &Path, change: impl FnOnce(&mut RefCounts) -> T) -> Result<T> {
+    let _guard = REFCOUNT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut counts = RefCounts::load_or_rebuild(storage_dir)?;
+    let result = change(&mut counts);
+    counts.save(storage_dir)?;
+    Ok(result)
+}
+
+/// Current reference counts of `storage_dir`. Counts that had to be rebuilt
+/// are written out, so they reflect the manifests as they are now rather
+/// than after a later removal.
+pub fn load_refcounts(storage_dir: &Path) -> Result<RefCounts> {
+    update(storage_dir, |counts| counts.clone())
+}
+
+/// Count one reference per listed blob ID (repeat an ID for several entries)
+pub fn add_references<'a>(
+    storage_dir: &Path,
+    blob_ids: impl IntoIterator<Item = &'a str>,
+) -> Result<()> {
+    update(storage_dir, |counts| counts.add(blob_ids))
+}
+
+/// Drop one reference per listed blob ID; returns the blob IDs now
+/// unreferenced, whose files can be deleted
+pub fn release_references<'a>(
+    storage_dir: &Path,
+    blob_ids: impl IntoIterator<Item = &'a str>,
+) -> Result<Vec<String>> {
+    update(storage_dir, |counts| counts.release(blob_ids))
+}
+
+/// Recount every reference from the manifests on disk and overwrite
+/// `refcount.json`, recovering from drift (e.g. a backup directory removed
+/// by hand)
+pub fn rebuild_refcounts() -> Result<RefCounts> {
+    rebuild_refcounts_in(&Manifest::base_storage_dir()?)
+}
+
+pub fn rebuild_refcounts_in(storage_dir: &Path) -> Result<RefCounts> {
+    let _guard = REFCOUNT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let counts = count_references(storage_dir)?;
+    counts.save(storage_dir)?;
+    Ok(counts)
+}
+
+fn count_references(storage_dir: &Path) -> Result<RefCounts> {
+    let mut counts = RefCounts::default();
+    if !storage_dir.is_dir() {
+        return Ok(counts);
+    }
+    for dir in fs::read_dir(storage_dir)? {
+        let dir = dir?;
+        let name = dir.file_name().to_string_lossy().into_owned();
+        // Dot directories are imports still being staged
+        if name.starts_with('.') || !dir.path().join("manifest.json").is_file() {
+            continue;
+        }
+        let manifest = Manifest::load_in(storage_dir, &name)?;
+        counts.add(manifest.entries.iter().filter_map(Entry::stored_blob_id));
+    }
+    Ok(counts)
+}
@@ -0,0 +1,86 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::storage::atomic::write_atomic;
+
+const SCRUB_FILE: &str = "scrub.json";
+
+/// Outcome of the last background verification ("scrub") of one backup
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScrubResult {
+    /// RFC 3339 time the verification finished
+    pub verified_at: String,
+    pub ok: bool,
+    /// Why verification failed; None when it passed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ScrubResult {
+    pub fn new(outcome: Result<()>) -> Self {
+        Self {
+            verified_at: Utc::now().to_rfc3339(),
+            ok: outcome.is_ok(),
+            error: outcome.err().map(|e| e.to_string()),
+        }
+    }
+
+    /// Whether the backup should be verified again at `now`: never
+    /// verified, verified more than `interval` ago, or at an unreadable time
+    pub fn is_due(last: Option<&Self>, interval: Duration, now: DateTime<Utc>) -> bool {
+        match last.and_then(|result| DateTime::parse_from_rfc3339(&result.verified_at).ok()) {
+            Some(verified_at) => now - verified_at.with_timezone(&Utc) >= interval,
+            None => true,
+        }
+    }
+}
+
+/// Last scrub result per backup name, kept as `scrub.json` in `storage_dir`.
+/// A missing or unreadable file means nothing has been verified yet.
+pub fn load_in(storage_dir: &Path) -> BTreeMap<String, ScrubResult> {
+    fs::read_to_string(storage_dir.join(SCRUB_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_in(storage_dir: &Path, results: &BTreeMap<String, ScrubResult>) -> Result<()> {
+    write_atomic(
+        &storage_dir.join(SCRUB_FILE),
+        &serde_json::to_vec_pretty(results)?,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_scrub_results_round_trip_and_fall_due() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        assert!(load_in(temp_dir.path()).is_empty());
+
+        let mut results = BTreeMap::new();
+        results.insert("daily".to_string(), ScrubResult::new(Ok(())));
+        results.insert(
+            "weekly".to_string(),
+            ScrubResult::new(Err(anyhow!("missing blob"))),
+        );
+        save_in(temp_dir.path(), &results)?;
+        let loaded = load_in(temp_dir.path());
+        assert_eq!(loaded, results);
+        assert_eq!(loaded["weekly"].error.as_deref(), Some("missing blob"));
+
+        let day = Duration::days(1);
+        let now = Utc::now();
+        assert!(ScrubResult::is_due(None, day, now));
+        assert!(!ScrubResult::is_due(loaded.get("daily"), day, now));
+        assert!(ScrubResult::is_due(loaded.get("daily"), day, now + Duration::hours(25)));
+        Ok(())
+    }
+}
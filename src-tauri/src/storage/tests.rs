@@ -2,6 +2,7 @@
 mod tests {
     use crate::storage::{manifest::Manifest, entry::Entry, blobs::BlobPayload};
     use tempfile::TempDir;
+    use sha2::{Digest, Sha256};
     
     #[test]
     fn test_blob_integrity_calculation() {
@@ -73,6 +74,7 @@ mod tests {
             target_hint: "app:test1".to_string(),
             logical_path: "/test/path1".to_string(),
             tar_member: Some("test1.txt".to_string()),
+            ..Default::default()
         });
 
         manifest.entries.push(Entry {
@@ -80,6 +82,7 @@ mod tests {
             target_hint: "app:test2".to_string(),
             logical_path: "/test/path2".to_string(),
             tar_member: Some("test2.txt".to_string()),
+            ..Default::default()
         });
 
         // Test that individual blobs are valid
@@ -112,4 +115,1221 @@ mod tests {
         assert_eq!(decompressed_19, test_data);
         assert_eq!(decompressed_3, decompressed_19);
     }
-}
\ No newline at end of file
+
+    fn tar_zst_of(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut tar_data = Vec::new();
+        {
+            let mut builder = tar::Builder::new(&mut tar_data);
+            for (name, data) in files {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(data.len() as u64);
+                header.set_mode(0o644);
+                header.set_cksum();
+                builder.append_data(&mut header, name, *data).unwrap();
+            }
+            builder.finish().unwrap();
+        }
+        zstd::encode_all(&tar_data[..], 3).unwrap()
+    }
+
+    #[test]
+    fn test_extract_blob_to_dir() -> Result<(), anyhow::Error> {
+        let temp_dir = TempDir::new()?;
+        let mut manifest = Manifest::empty("extract-test".to_string());
+
+        let compressed = tar_zst_of(&[("settings.json", b"{}"), ("themes/dark.json", b"[]")]);
+        manifest.add_blob_for_testing("blob1".to_string(), BlobPayload::new("tar.zst".to_string(), &compressed));
+
+        let mut extracted = manifest.extract_blob_to_dir("blob1", temp_dir.path())?;
+        extracted.sort();
+
+        assert_eq!(extracted, vec![temp_dir.path().join("settings.json"), temp_dir.path().join("themes/dark.json")]);
+        assert_eq!(std::fs::read(temp_dir.path().join("themes/dark.json"))?, b"[]");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encrypted_blob_restores() -> Result<(), anyhow::Error> {
+        let temp_dir = TempDir::new()?;
+        let mut manifest = Manifest::empty("encrypted-test".to_string());
+
+        use crate::storage::crypto;
+
+        crypto::set_passphrase(Some(crypto::TEST_PASSPHRASE.to_string()));
+        let sealed = crypto::seal(&tar_zst_of(&[("prefs.json", b"{\"theme\":1}")]))?;
+        manifest.add_blob_for_testing(
+            "blob1".to_string(),
+            BlobPayload::new("tar.zst.enc".to_string(), &sealed),
+        );
+
+        manifest.extract_blob_to_dir("blob1", temp_dir.path())?;
+        assert_eq!(std::fs::read(temp_dir.path().join("prefs.json"))?, b"{\"theme\":1}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_auto_codec_blobs_restore() -> Result<(), anyhow::Error> {
+        use crate::storage::codec::Codec;
+
+        let temp_dir = TempDir::new()?;
+        let mut manifest = Manifest::empty("codec-test".to_string());
+
+        // Blobs picked by auto selection carry their codec in the format
+        let tar_data = zstd::decode_all(&tar_zst_of(&[("init.lua", b"vim.o.number = true")])[..])?;
+        for (blob_id, codec) in [("xz", Codec::Xz), ("lz4", Codec::Lz4)] {
+            let compressed = codec.compress(&tar_data, 3)?;
+            manifest.add_blob_for_testing(
+                blob_id.to_string(),
+                BlobPayload::new(codec.format().to_string(), &compressed),
+            );
+
+            let dest = temp_dir.path().join(blob_id);
+            manifest.extract_blob_to_dir(blob_id, &dest)?;
+            assert_eq!(std::fs::read(dest.join("init.lua"))?, b"vim.o.number = true");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_blob_rejects_sha256_mismatch() -> Result<(), anyhow::Error> {
+        let temp_dir = TempDir::new()?;
+        let mut manifest = Manifest::empty("extract-test".to_string());
+
+        // Serialize a valid payload then swap its data for a different blob's
+        let good = BlobPayload::new("tar.zst".to_string(), &tar_zst_of(&[("a.conf", b"a")]));
+        let other = BlobPayload::new("tar.zst".to_string(), &tar_zst_of(&[("b.conf", b"b")]));
+        let mut json = serde_json::to_value(&good)?;
+        json["b64"] = serde_json::to_value(&other)?["b64"].clone();
+        manifest.add_blob_for_testing("blob1".to_string(), serde_json::from_value(json)?);
+
+        assert!(manifest.extract_blob_to_dir("blob1", temp_dir.path()).is_err());
+        assert!(!temp_dir.path().join("b.conf").exists());
+
+        Ok(())
+    }
+
+    fn write_manifest_in(storage_dir: &std::path::Path, manifest: &Manifest) -> Result<(), anyhow::Error> {
+        let backup_dir = storage_dir.join(&manifest.name);
+        std::fs::create_dir_all(&backup_dir)?;
+        std::fs::write(backup_dir.join("manifest.json"), serde_json::to_string(manifest)?)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_previous_backup_captures_hash() -> Result<(), anyhow::Error> {
+        let temp_dir = TempDir::new()?;
+        let first = Manifest::empty("first".to_string());
+        write_manifest_in(temp_dir.path(), &first)?;
+
+        let mut second = Manifest::empty("second".to_string());
+        second.set_previous_backup_with_dir("first", Some(temp_dir.path().to_path_buf()))?;
+
+        assert_eq!(second.previous_backup.as_deref(), Some("first"));
+        assert_eq!(second.previous_backup_hash, Some(first.calculate_backup_hash()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_previous_backup_missing_target() -> Result<(), anyhow::Error> {
+        let temp_dir = TempDir::new()?;
+        let mut manifest = Manifest::empty("orphan".to_string());
+
+        let err = manifest
+            .set_previous_backup_with_dir("deleted", Some(temp_dir.path().to_path_buf()))
+            .unwrap_err();
+        assert!(err.to_string().contains("does not exist"), "{}", err);
+        assert!(manifest.previous_backup.is_none());
+        assert!(manifest.previous_backup_hash.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_previous_backup_rejects_cycle() -> Result<(), anyhow::Error> {
+        let temp_dir = TempDir::new()?;
+        let storage_dir = Some(temp_dir.path().to_path_buf());
+
+        // a <- b <- c, then try to make a follow c
+        let a = Manifest::empty("a".to_string());
+        write_manifest_in(temp_dir.path(), &a)?;
+        let mut b = Manifest::empty("b".to_string());
+        b.set_previous_backup_with_dir("a", storage_dir.clone())?;
+        write_manifest_in(temp_dir.path(), &b)?;
+        let mut c = Manifest::empty("c".to_string());
+        c.set_previous_backup_with_dir("b", storage_dir.clone())?;
+        write_manifest_in(temp_dir.path(), &c)?;
+
+        let mut a = a;
+        let err = a.set_previous_backup_with_dir("c", storage_dir.clone()).unwrap_err();
+        assert!(err.to_string().contains("cycle"), "{}", err);
+        assert!(a.set_previous_backup_with_dir("a", storage_dir).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_backup_chain_hash_detects_tampering() -> Result<(), anyhow::Error> {
+        let temp_dir = TempDir::new()?;
+        let storage_dir = temp_dir.path();
+        let mut parent = source_backup("parent", "2026-01-01T00:00:00Z", &[("/cfg/a.conf", b"a")]);
+        parent.save_in(storage_dir)?;
+        let mut child = source_backup("child", "2026-01-02T00:00:00Z", &[("/cfg/b.conf", b"b")]);
+        child.set_previous_backup_with_dir("parent", Some(storage_dir.to_path_buf()))?;
+        child.save_in(storage_dir)?;
+
+        let child = Manifest::load_in(storage_dir, "child")?;
+        assert!(child.verify_backup_chain_hash()?);
+
+        let mut edited = child.clone();
+        edited.entries[0].logical_path = "/etc/passwd".to_string();
+        assert!(!edited.verify_backup_chain_hash()?);
+
+        let mut relinked = child.clone();
+        relinked.previous_backup_hash = Some("0".repeat(64));
+        assert!(!relinked.verify_backup_chain_hash()?);
+
+        let mut forged = child.clone();
+        forged.backup_chain_hash = Some("0".repeat(64));
+        assert!(!forged.verify_backup_chain_hash()?);
+
+        assert!(Manifest::empty("legacy".to_string()).verify_backup_chain_hash().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rebase_backup_repairs_dangling_link() -> Result<(), anyhow::Error> {
+        let temp_dir = TempDir::new()?;
+        let storage_dir = temp_dir.path();
+
+        // a <- b <- c, then b is deleted by hand
+        let a = Manifest::empty("a".to_string());
+        write_manifest_in(storage_dir, &a)?;
+        let mut b = Manifest::empty("b".to_string());
+        b.set_previous_backup_with_dir("a", Some(storage_dir.to_path_buf()))?;
+        write_manifest_in(storage_dir, &b)?;
+        let mut c = Manifest::empty("c".to_string());
+        c.set_previous_backup_with_dir("b", Some(storage_dir.to_path_buf()))?;
+        write_manifest_in(storage_dir, &c)?;
+        std::fs::remove_dir_all(storage_dir.join("b"))?;
+
+        assert!(Manifest::rebase_backup_in(storage_dir, "c", Some("b")).is_err());
+        Manifest::rebase_backup_in(storage_dir, "c", Some("a"))?;
+        let c = Manifest::load_in(storage_dir, "c")?;
+        assert_eq!(c.previous_backup.as_deref(), Some("a"));
+        assert_eq!(c.previous_backup_hash, Some(a.calculate_backup_hash()));
+        assert_eq!(c.backup_chain_hash, Some(c.calculate_backup_chain_hash()));
+
+        let err = Manifest::rebase_backup_in(storage_dir, "a", Some("c")).unwrap_err();
+        assert!(err.to_string().contains("cycle"), "{}", err);
+
+        Manifest::rebase_backup_in(storage_dir, "c", None)?;
+        let genesis = Manifest::load_in(storage_dir, "c")?;
+        assert!(genesis.previous_backup.is_none());
+        assert_ne!(genesis.backup_chain_hash, c.backup_chain_hash);
+        Ok(())
+    }
+
+    fn source_backup(name: &str, created_at: &str, files: &[(&str, &[u8])]) -> Manifest {
+        let mut manifest = Manifest::empty(name.to_string());
+        manifest.created_at = created_at.to_string();
+        for (path, data) in files {
+            let blob_id = format!("{}-{}", name, path.replace('/', "_"));
+            let member = path.rsplit('/').next().unwrap();
+            manifest.add_blob_for_testing(
+                blob_id.clone(),
+                BlobPayload::new("tar.zst".to_string(), &tar_zst_of(&[(member, *data)])),
+            );
+            manifest.entries.push(Entry {
+                blob_id,
+                target_hint: "app:test".to_string(),
+                logical_path: path.to_string(),
+                tar_member: Some(member.to_string()),
+                ..Default::default()
+            });
+        }
+        manifest
+    }
+
+    #[test]
+    fn test_merge_backups_prefers_newer_entry() -> Result<(), anyhow::Error> {
+        let temp_dir = TempDir::new()?;
+        let storage_dir = Some(temp_dir.path().to_path_buf());
+
+        let editors = source_backup(
+            "editors",
+            "2026-01-01T00:00:00Z",
+            &[("/cfg/zed/settings.json", b"old"), ("/cfg/Code/settings.json", b"code")],
+        );
+        let terminals = source_backup(
+            "terminals",
+            "2026-02-01T00:00:00Z",
+            &[("/cfg/zed/settings.json", b"new"), ("/cfg/wt/settings.json", b"wt")],
+        );
+        write_manifest_in(temp_dir.path(), &editors)?;
+        write_manifest_in(temp_dir.path(), &terminals)?;
+
+        let report = Manifest::merge_backups_with_dir("editors", "terminals", "all", storage_dir.clone())?;
+        assert_eq!(report.entry_count, 3);
+        assert_eq!(report.blob_count, 3);
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].logical_path, "/cfg/zed/settings.json");
+        assert_eq!(report.conflicts[0].kept_from, "terminals");
+
+        let merged: Manifest = serde_json::from_str(&std::fs::read_to_string(
+            temp_dir.path().join("all").join("manifest.json"),
+        )?)?;
+        let zed = merged.entries.iter().find(|e| e.logical_path == "/cfg/zed/settings.json").unwrap();
+        assert_eq!(zed.blob_id, "terminals-_cfg_zed_settings.json");
+        assert!(merged.verify_blob_chain_integrity_with_dir(storage_dir.clone())?);
+
+        // The target name is taken now
+        assert!(Manifest::merge_backups_with_dir("editors", "terminals", "all", storage_dir).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_backups_copies_blobs_borrowed_from_a_third_backup() -> Result<(), anyhow::Error> {
+        let temp_dir = TempDir::new()?;
+        let shared = temp_dir.path().join("shared.conf");
+        let own = temp_dir.path().join("own.conf");
+        std::fs::write(&shared, b"held by the base backup")?;
+        std::fs::write(&own, b"only in the other source")?;
+        let storage_dir = temp_dir.path().join("storage");
+        for (name, src) in [("base", &shared), ("borrower", &shared), ("other", &own)] {
+            let mut manifest = Manifest::new_in(name.to_string(), &storage_dir);
+            manifest.set_inline_threshold(0);
+            manifest.create_blob_from_file(src, "app:test", None)?;
+            manifest.save()?;
+        }
+        assert!(Manifest::load_in(&storage_dir, "borrower")?.blobs.is_empty());
+
+        let report = Manifest::merge_backups_with_dir(
+            "borrower",
+            "other",
+            "all",
+            Some(storage_dir.clone()),
+        )?;
+        assert_eq!(report.blob_count, 2);
+        let merged = Manifest::load_in(&storage_dir, "all")?;
+        assert!(merged.verify_backup_integrity_with_dir(Some(storage_dir))?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_mark_restored_keeps_integrity() -> Result<(), anyhow::Error> {
+        let temp_dir = TempDir::new()?;
+        let storage_dir = Some(temp_dir.path().to_path_buf());
+
+        // Merging is the quickest way to get a backup with a real chain on disk
+        let source = source_backup("source", "2026-01-01T00:00:00Z", &[("/cfg/a.conf", b"a")]);
+        write_manifest_in(temp_dir.path(), &source)?;
+        let empty = Manifest::empty("empty".to_string());
+        write_manifest_in(temp_dir.path(), &empty)?;
+        Manifest::merge_backups_with_dir("source", "empty", "daily", storage_dir.clone())?;
+
+        let manifest_path = temp_dir.path().join("daily").join("manifest.json");
+        let mut manifest: Manifest = serde_json::from_str(&std::fs::read_to_string(&manifest_path)?)?;
+        assert!(manifest.last_restored_at.is_none());
+        let hash_before = manifest.calculate_backup_hash();
+
+        manifest.mark_restored_with_dir(storage_dir.clone())?;
+
+        let reloaded: Manifest = serde_json::from_str(&std::fs::read_to_string(&manifest_path)?)?;
+        assert!(reloaded.last_restored_at.is_some());
+        assert_eq!(reloaded.calculate_backup_hash(), hash_before);
+        assert!(reloaded.verify_blob_chain_integrity_with_dir(storage_dir)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_dedup_scope_none_never_dedups() -> Result<(), anyhow::Error> {
+        use crate::settings::DedupScope;
+
+        let mut manifest = Manifest::empty("dedup-test".to_string());
+        let payload = BlobPayload::new("tar.zst".to_string(), &tar_zst_of(&[("a.conf", b"a")]));
+        let content_hash = payload.get_sha256().to_string();
+        manifest.add_blob_for_testing(content_hash.clone(), payload);
+
+        assert_eq!(
+            manifest.find_duplicate_blob(&content_hash, DedupScope::WithinBackup)?,
+            Some(("dedup-test".to_string(), content_hash.clone()))
+        );
+        assert_eq!(manifest.find_duplicate_blob(&content_hash, DedupScope::None)?, None);
+        // IDs stay content hashes: a repeat within the backup shares its blob
+        assert_eq!(
+            manifest.reusable_blob(&content_hash, DedupScope::None)?,
+            Some(("dedup-test".to_string(), content_hash.clone()))
+        );
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_restore_preserves_file_mode() -> Result<(), anyhow::Error> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new()?;
+        let mut manifest = Manifest::empty("mode-test".to_string());
+        let mut restore_with_mode = |member: &str, mode: u32| -> Result<u32, anyhow::Error> {
+            let mut tar_data = Vec::new();
+            {
+                let mut builder = tar::Builder::new(&mut tar_data);
+                let mut header = tar::Header::new_gnu();
+                header.set_size(4);
+                header.set_mode(mode);
+                header.set_cksum();
+                builder.append_data(&mut header, member, &b"psk="[..])?;
+                builder.finish()?;
+            }
+            manifest.add_blob_for_testing(
+                member.to_string(),
+                BlobPayload::new("tar.zst".to_string(), &zstd::encode_all(&tar_data[..], 3)?),
+            );
+            let entry = Entry {
+                blob_id: member.to_string(),
+                target_hint: "sys:networkmanager".to_string(),
+                logical_path: format!("/etc/NetworkManager/system-connections/{}", member),
+                tar_member: Some(member.to_string()),
+                ..Default::default()
+            };
+            let dest = temp_dir.path().join(member);
+            manifest.restore_blob_to(&entry, &dest)?;
+            Ok(std::fs::metadata(&dest)?.permissions().mode() & 0o7777)
+        };
+
+        assert_eq!(restore_with_mode("home.nmconnection", 0o600)?, 0o600);
+        // setuid, setgid and sticky bits from a backup are never applied
+        assert_eq!(restore_with_mode("helper", 0o7755)?, 0o755);
+        Ok(())
+    }
+
+    #[test]
+    fn test_compression_override_is_validated_and_recorded() {
+        let mut manifest = Manifest::empty("archival".to_string());
+
+        assert!(manifest.set_compression_override(Some(0)).is_err());
+        assert!(manifest.set_compression_override(Some(23)).is_err());
+        assert_eq!(manifest.compression_profile, None);
+
+        manifest.set_compression_override(Some(22)).unwrap();
+        assert_eq!(manifest.compression_profile, Some(22));
+
+        // A resave without an override keeps the recorded level
+        manifest.set_compression_override(None).unwrap();
+        assert_eq!(manifest.compression_profile, Some(22));
+    }
+
+    #[test]
+    fn test_list_for_profile() -> Result<(), anyhow::Error> {
+        let temp_dir = TempDir::new()?;
+        let storage_dir = Some(temp_dir.path().to_path_buf());
+
+        for (name, profile) in [("desk-1", Some("desktop")), ("lap-1", Some("laptop")), ("untagged", None)] {
+            let mut manifest = Manifest::empty(name.to_string());
+            manifest.machine_profile = profile.map(str::to_string);
+            write_manifest_in(temp_dir.path(), &manifest)?;
+        }
+
+        let laptop = Manifest::list_for_profile_with_dir("laptop", storage_dir.clone())?;
+        assert_eq!(laptop.len(), 1);
+        assert_eq!(laptop[0].name, "lap-1");
+        assert_eq!(Manifest::list_all_with_dir(storage_dir)?.len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_latest_backup_is_of_the_same_profile() -> Result<(), anyhow::Error> {
+        let temp_dir = TempDir::new()?;
+        let storage_dir = Some(temp_dir.path().to_path_buf());
+        for (name, created_at, profile) in [
+            ("lap-1", "2026-01-01T00:00:00Z", Some("laptop")),
+            ("untagged", "2026-02-01T00:00:00Z", None),
+            ("desk-1", "2026-03-01T00:00:00Z", Some("desktop")),
+        ] {
+            let mut manifest = Manifest::empty(name.to_string());
+            manifest.created_at = created_at.to_string();
+            manifest.machine_profile = profile.map(str::to_string);
+            write_manifest_in(temp_dir.path(), &manifest)?;
+        }
+
+        let latest = |exclude, profile| {
+            Manifest::find_latest_backup_with_dir(exclude, profile, storage_dir.clone())
+        };
+        assert_eq!(latest("lap-2", Some("laptop"))?.as_deref(), Some("lap-1"));
+        assert_eq!(latest("new", None)?.as_deref(), Some("untagged"));
+        assert_eq!(latest("desk-1", Some("desktop"))?, None);
+        assert_eq!(latest("new", Some("server"))?, None);
+        Ok(())
+    }
+
+    /// Write `manifest` with a loose blob file for each of its payloads
+    fn write_backup_with_blob_files(
+        storage_dir: &std::path::Path,
+        manifest: &Manifest,
+    ) -> Result<std::path::PathBuf, anyhow::Error> {
+        write_manifest_in(storage_dir, manifest)?;
+        let blob_dir = storage_dir.join(&manifest.name).join("blobs");
+        std::fs::create_dir_all(&blob_dir)?;
+        for (blob_id, blob) in &manifest.blobs {
+            std::fs::write(blob_dir.join(format!("{}.tar.zst", blob_id)), blob.decode()?)?;
+        }
+        Ok(blob_dir)
+    }
+
+    #[test]
+    fn test_verify_backup_integrity_detects_corrupted_blob_file() -> Result<(), anyhow::Error> {
+        let temp_dir = TempDir::new()?;
+        let storage_dir = Some(temp_dir.path().to_path_buf());
+        let manifest = source_backup(
+            "verify",
+            "2026-01-01T00:00:00Z",
+            &[("/cfg/a.conf", b"alpha"), ("/cfg/b.conf", b"beta")],
+        );
+        let blob_dir = write_backup_with_blob_files(temp_dir.path(), &manifest)?;
+        assert!(manifest.verify_backup_integrity_with_dir(storage_dir.clone())?);
+
+        let blob_path = blob_dir.join("verify-_cfg_b.conf.tar.zst");
+        let mut bytes = std::fs::read(&blob_path)?;
+        let middle = bytes.len() / 2;
+        bytes[middle] ^= 0xff;
+        std::fs::write(&blob_path, &bytes)?;
+        assert!(!manifest.verify_backup_integrity_with_dir(storage_dir)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_backup_integrity_detects_truncated_blob_file() -> Result<(), anyhow::Error> {
+        let temp_dir = TempDir::new()?;
+        let storage_dir = Some(temp_dir.path().to_path_buf());
+        let manifest = source_backup("verify", "2026-01-01T00:00:00Z", &[("/cfg/a.conf", b"alpha")]);
+        let blob_dir = write_backup_with_blob_files(temp_dir.path(), &manifest)?;
+
+        let blob_path = blob_dir.join("verify-_cfg_a.conf.tar.zst");
+        let bytes = std::fs::read(&blob_path)?;
+        std::fs::write(&blob_path, &bytes[..bytes.len() / 2])?;
+        assert!(!manifest.verify_backup_integrity_with_dir(storage_dir.clone())?);
+
+        // A missing blob file is just as unrestorable
+        std::fs::remove_file(&blob_path)?;
+        assert!(!manifest.verify_backup_integrity_with_dir(storage_dir)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_blob_copy_mismatches_detects_changed_blob_file() -> Result<(), anyhow::Error> {
+        let temp_dir = TempDir::new()?;
+        let storage_dir = Some(temp_dir.path().to_path_buf());
+        let manifest = source_backup(
+            "copies",
+            "2026-01-01T00:00:00Z",
+            &[("/cfg/a.conf", b"alpha"), ("/cfg/b.conf", b"beta")],
+        );
+        let blob_dir = write_backup_with_blob_files(temp_dir.path(), &manifest)?;
+        assert!(manifest.blob_copy_mismatches_with_dir(storage_dir.clone())?.is_empty());
+
+        let blob_path = blob_dir.join("copies-_cfg_b.conf.tar.zst");
+        let mut bytes = std::fs::read(&blob_path)?;
+        bytes.push(0);
+        std::fs::write(&blob_path, &bytes)?;
+        let mismatches = manifest.blob_copy_mismatches_with_dir(storage_dir.clone())?;
+        assert_eq!(mismatches.len(), 1);
+        assert!(mismatches[0].contains("copies-_cfg_b.conf"));
+
+        // Without data in the manifest only a missing file counts
+        let mut migrated = manifest.clone();
+        for blob in migrated.blobs.values_mut() {
+            *blob = blob.without_data();
+        }
+        assert!(migrated.blob_copy_mismatches_with_dir(storage_dir.clone())?.is_empty());
+        std::fs::remove_file(&blob_path)?;
+        assert_eq!(migrated.blob_copy_mismatches_with_dir(storage_dir)?.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_backup_deep_reads_tar_contents() -> Result<(), anyhow::Error> {
+        let temp_dir = TempDir::new()?;
+        let storage_dir = Some(temp_dir.path().to_path_buf());
+        let mut manifest =
+            source_backup("deep", "2026-01-01T00:00:00Z", &[("/cfg/a.conf", b"alpha")]);
+        manifest.entries[0].original_sha256 = Some(hex::encode(Sha256::digest(b"alpha")));
+        write_backup_with_blob_files(temp_dir.path(), &manifest)?;
+        manifest.verify_backup_deep_with_dir(storage_dir.clone())?;
+
+        // Stored bytes hash fine, but the content isn't what was backed up
+        manifest.entries[0].original_sha256 = Some(hex::encode(Sha256::digest(b"other")));
+        assert!(manifest.verify_backup_integrity_with_dir(storage_dir.clone())?);
+        let err = manifest.verify_backup_deep_with_dir(storage_dir.clone()).unwrap_err();
+        assert!(err.to_string().contains("does not match"));
+
+        // A member whose header promises more data than the TAR holds
+        let mut tar_data = Vec::new();
+        {
+            let mut builder = tar::Builder::new(&mut tar_data);
+            let mut header = tar::Header::new_gnu();
+            header.set_size(4096);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, "b.conf", &[b'x'; 4096][..])?;
+        }
+        tar_data.truncate(512 + 100);
+        let mut broken = Manifest::empty("deep-broken".to_string());
+        broken.add_blob_for_testing(
+            "broken".to_string(),
+            BlobPayload::new("tar.zst".to_string(), &zstd::encode_all(&tar_data[..], 3)?),
+        );
+        broken.entries.push(Entry {
+            blob_id: "broken".to_string(),
+            target_hint: "app:test".to_string(),
+            logical_path: "/cfg/b.conf".to_string(),
+            tar_member: Some("b.conf".to_string()),
+            ..Default::default()
+        });
+        write_backup_with_blob_files(temp_dir.path(), &broken)?;
+        assert!(broken.verify_backup_deep_with_dir(storage_dir).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_prune_keeps_surviving_backups_intact() -> Result<(), anyhow::Error> {
+        let temp_dir = TempDir::new()?;
+        let storage_dir = Some(temp_dir.path().to_path_buf());
+
+        let oldest = source_backup("oldest", "2026-01-01T00:00:00Z", &[("/cfg/a.conf", b"alpha")]);
+        write_backup_with_blob_files(temp_dir.path(), &oldest)?;
+        let mut old = source_backup("old", "2026-01-02T00:00:00Z", &[("/cfg/b.conf", b"beta")]);
+        old.set_previous_backup_with_dir("oldest", storage_dir.clone())?;
+        write_backup_with_blob_files(temp_dir.path(), &old)?;
+
+        // Globally deduplicated: points at a blob stored only by "oldest"
+        let mut recent = source_backup("recent", "2026-03-01T00:00:00Z", &[]);
+        recent.entries.push(oldest.entries[0].clone());
+        let borrowed_id = oldest.entries[0].blob_id.clone();
+        recent.add_blob_for_testing(borrowed_id.clone(), oldest.blobs[&borrowed_id].clone());
+        recent.set_previous_backup_with_dir("old", storage_dir.clone())?;
+        write_manifest_in(temp_dir.path(), &recent)?;
+        let undated = source_backup("undated", "", &[]);
+        write_manifest_in(temp_dir.path(), &undated)?;
+
+        let cutoff = chrono::DateTime::parse_from_rfc3339("2026-02-01T00:00:00Z")?.to_utc();
+        let pruned =
+            crate::storage::prune::prune_backups_in(temp_dir.path(), cutoff, Some("undated"))?;
+        assert_eq!(pruned, vec!["old".to_string(), "oldest".to_string()]);
+        assert!(!temp_dir.path().join("oldest").exists());
+        assert!(temp_dir.path().join("undated").exists());
+
+        let recent: Manifest = serde_json::from_str(&std::fs::read_to_string(
+            temp_dir.path().join("recent").join("manifest.json"),
+        )?)?;
+        assert_eq!(recent.previous_backup, None);
+        assert!(recent.verify_backup_integrity_with_dir(storage_dir)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_prune_keeps_dictionaries_survivors_borrow() -> Result<(), anyhow::Error> {
+        let temp_dir = TempDir::new()?;
+        let storage_dir = temp_dir.path().join("storage");
+        let sources: Vec<std::path::PathBuf> = (0..20)
+            .map(|i| temp_dir.path().join(format!("settings-{}.json", i)))
+            .collect();
+        for (i, path) in sources.iter().enumerate() {
+            let content = format!(
+                "{{\n  \"editor.fontSize\": {},\n  \"workbench.colorTheme\": \"Theme {}\",\n  \
+                 \"files.autoSave\": \"afterDelay\",\n  \"editor.tabSize\": 4\n}}\n",
+                10 + i % 7,
+                i
+            );
+            std::fs::write(path, content.repeat(40))?;
+        }
+
+        let mut old = Manifest::new_in("old".to_string(), &storage_dir);
+        old.created_at = "2026-01-01T00:00:00Z".to_string();
+        assert!(old.use_compression_dictionary(&sources)?);
+        old.create_blob_from_file(&sources[0], "app:test", None)?;
+        old.save()?;
+        let old = Manifest::load_in(&storage_dir, "old")?;
+        let blob_id = old.entries[0].blob_id.clone();
+        assert!(old.blobs[&blob_id].get_dictionary().is_some());
+
+        // Globally deduplicated: borrows a blob compressed against the
+        // dictionary only "old" holds
+        let mut recent = source_backup("recent", "2026-03-01T00:00:00Z", &[]);
+        recent.entries.push(old.entries[0].clone());
+        recent.add_blob_for_testing(blob_id.clone(), old.blobs[&blob_id].clone());
+        write_manifest_in(&storage_dir, &recent)?;
+
+        let cutoff = chrono::DateTime::parse_from_rfc3339("2026-02-01T00:00:00Z")?.to_utc();
+        let pruned = crate::storage::prune::prune_backups_in(&storage_dir, cutoff, None)?;
+        assert_eq!(pruned, vec!["old".to_string()]);
+
+        let recent = Manifest::load_in(&storage_dir, "recent")?;
+        let dest = temp_dir.path().join("restored.json");
+        recent.restore_blob_to(&recent.entries[0], &dest)?;
+        assert_eq!(std::fs::read(&dest)?, std::fs::read(&sources[0])?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_orphan_blobs_of_an_interrupted_save() -> Result<(), anyhow::Error> {
+        use crate::storage::prune::{find_orphan_blobs_in, gc_orphaned_blobs_in};
+
+        let temp_dir = TempDir::new()?;
+        let storage_dir = temp_dir.path();
+        let crashed =
+            source_backup("crashed", "2026-01-01T00:00:00Z", &[("/cfg/a.conf", b"alpha")]);
+        let blob_dir = write_backup_with_blob_files(storage_dir, &crashed)?;
+        assert!(crashed.orphan_blobs_in(storage_dir).is_empty());
+
+        // Blobs written before the save died, one of which "later" uses since
+        std::fs::write(blob_dir.join("stray.tar.zst"), b"half a save")?;
+        std::fs::write(blob_dir.join("reused.tar.zst"), b"still needed")?;
+        let mut later = source_backup("later", "2026-01-02T00:00:00Z", &[]);
+        later.entries.push(Entry {
+            blob_id: "reused".to_string(),
+            target_hint: "app:test".to_string(),
+            logical_path: "/cfg/b.conf".to_string(),
+            ..Default::default()
+        });
+        write_manifest_in(storage_dir, &later)?;
+
+        assert_eq!(crashed.orphan_blobs_in(storage_dir), ["reused", "stray"]);
+        assert_eq!(find_orphan_blobs_in(storage_dir, "crashed")?, ["reused", "stray"]);
+        assert_eq!(gc_orphaned_blobs_in(storage_dir, "crashed")?, ["stray"]);
+        assert!(!blob_dir.join("stray.tar.zst").exists());
+        assert!(blob_dir.join("reused.tar.zst").exists());
+        assert!(blob_dir.join(format!("{}.tar.zst", crashed.entries[0].blob_id)).exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_refcounts_free_blobs_on_delete() -> Result<(), anyhow::Error> {
+        use crate::storage::{
+            prune::{delete_backup_in, preview_delete_in},
+            refcount,
+        };
+
+        let temp_dir = TempDir::new()?;
+        let storage_dir = temp_dir.path();
+
+        let shared = source_backup("shared", "2026-01-01T00:00:00Z", &[("/cfg/a.conf", b"alpha")]);
+        write_backup_with_blob_files(storage_dir, &shared)?;
+        let shared_id = shared.entries[0].blob_id.clone();
+
+        // "borrower" reuses shared's blob and stores one of its own
+        let mut borrower =
+            source_backup("borrower", "2026-01-02T00:00:00Z", &[("/cfg/b.conf", b"beta")]);
+        borrower.entries.push(shared.entries[0].clone());
+        write_backup_with_blob_files(storage_dir, &borrower)?;
+        borrower.add_blob_for_testing(shared_id.clone(), shared.blobs[&shared_id].clone());
+        let own_id = borrower.entries[0].blob_id.clone();
+
+        // No refcount.json yet: it's rebuilt from the manifests on first use
+        let counts = refcount::load_refcounts(storage_dir)?;
+        assert_eq!(counts.by_blob.get(&shared_id), Some(&2));
+        assert_eq!(counts.by_blob.get(&own_id), Some(&1));
+
+        // Saving an extra reference goes through the counts too
+        borrower.entries.push(borrower.entries[0].clone());
+        borrower.save_in(storage_dir)?;
+        assert_eq!(refcount::load_refcounts(storage_dir)?.by_blob.get(&own_id), Some(&2));
+        borrower.entries.pop();
+        borrower.save_in(storage_dir)?;
+
+        // "borrower" depends on "shared", so deleting it needs force
+        let preview = preview_delete_in(storage_dir, "shared")?;
+        assert_eq!(preview.dependent_backups, ["borrower"]);
+        assert_eq!(preview.blobs_freed, 0);
+        assert!(delete_backup_in(storage_dir, "shared", false).is_err());
+        delete_backup_in(storage_dir, "shared", true)?;
+        let counts = refcount::load_refcounts(storage_dir)?;
+        assert_eq!(counts.by_blob.get(&shared_id), Some(&1));
+        assert!(borrower.verify_backup_integrity_with_dir(Some(storage_dir.to_path_buf()))?);
+
+        // Drift (a stale count) is repaired by a rebuild
+        std::fs::write(storage_dir.join("refcount.json"), b"{}")?;
+        assert_eq!(refcount::load_refcounts(storage_dir)?.by_blob.get(&own_id), None);
+        assert_eq!(refcount::rebuild_refcounts_in(storage_dir)?, counts);
+
+        let preview = preview_delete_in(storage_dir, "borrower")?;
+        assert!(preview.dependent_backups.is_empty());
+        assert_eq!(preview.blobs_freed, 2);
+        let own_size = borrower.blobs[&own_id].get_size();
+        assert_eq!(preview.bytes_reclaimed, own_size + shared.blobs[&shared_id].get_size());
+        delete_backup_in(storage_dir, "borrower", false)?;
+        assert_eq!(refcount::load_refcounts(storage_dir)?, Default::default());
+        assert!(delete_backup_in(storage_dir, "borrower", false).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_legacy_storage_dir_is_migrated() -> Result<(), anyhow::Error> {
+        use crate::storage::manifest::migrate_legacy_storage;
+
+        let temp_dir = TempDir::new()?;
+        let legacy = temp_dir.path().join("you").join("saveconfig");
+        let current = temp_dir.path().join("saveme").join("saveme-config");
+        std::fs::create_dir_all(legacy.join("daily"))?;
+        std::fs::write(legacy.join("daily").join("manifest.json"), b"{}")?;
+
+        assert_eq!(migrate_legacy_storage(&legacy, &current), current);
+        assert!(current.join("daily").join("manifest.json").exists());
+        assert!(!legacy.exists());
+
+        // Once the new location exists, a stray legacy dir is left alone
+        std::fs::create_dir_all(&legacy)?;
+        assert_eq!(migrate_legacy_storage(&legacy, &current), current);
+        assert!(legacy.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_heal_blob_from_duplicate_copy() -> Result<(), anyhow::Error> {
+        use crate::storage::heal::heal_blob_in;
+
+        let temp_dir = TempDir::new()?;
+        let storage_dir = temp_dir.path();
+        let original = source_backup("original", "2026-01-01T00:00:00Z", &[("/cfg/a.conf", b"a")]);
+        let blob_id = original.entries[0].blob_id.clone();
+        let blob_file = format!("{}.tar.zst", blob_id);
+
+        // A second backup stored its own copy of the same blob
+        let mut copy = Manifest::empty("copy".to_string());
+        copy.entries.push(original.entries[0].clone());
+        copy.add_blob_for_testing(blob_id.clone(), original.blobs[&blob_id].clone());
+        let original_dir = write_backup_with_blob_files(storage_dir, &original)?;
+        let copy_dir = write_backup_with_blob_files(storage_dir, &copy)?;
+
+        std::fs::write(original_dir.join(&blob_file), b"bit rot")?;
+        assert!(!original.verify_backup_integrity_with_dir(Some(storage_dir.to_path_buf()))?);
+
+        assert!(heal_blob_in(storage_dir, &original, &blob_id)?);
+        assert!(original.verify_backup_integrity_with_dir(Some(storage_dir.to_path_buf()))?);
+
+        // With every copy damaged there is nothing to heal from
+        std::fs::write(original_dir.join(&blob_file), b"bit rot")?;
+        std::fs::write(copy_dir.join(&blob_file), b"more rot")?;
+        assert!(!heal_blob_in(storage_dir, &original, &blob_id)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_refreshes_size_totals() -> Result<(), anyhow::Error> {
+        let temp_dir = TempDir::new()?;
+        let storage_dir = temp_dir.path();
+        let mut manifest = source_backup(
+            "sized",
+            "2026-01-01T00:00:00Z",
+            &[("/cfg/a.conf", b"alpha"), ("/cfg/b.conf", b"beta")],
+        );
+        manifest.entries[0].original_size = Some(5);
+        manifest.entries[1].original_size = Some(4);
+        // A borrowed blob adds logical but not physical bytes
+        manifest.entries.push(Entry {
+            blob_id: "elsewhere".to_string(),
+            original_size: Some(100),
+            ..Default::default()
+        });
+        let stored: u64 = manifest.blobs.values().map(|blob| blob.get_size()).sum();
+
+        // Older manifests have no totals until their next save
+        write_manifest_in(storage_dir, &manifest)?;
+        let mut loaded = Manifest::load_in(storage_dir, "sized")?;
+        assert_eq!((loaded.total_blob_bytes, loaded.total_logical_bytes), (0, 0));
+
+        loaded.save_in(storage_dir)?;
+        let loaded = Manifest::load_in(storage_dir, "sized")?;
+        assert_eq!(loaded.total_blob_bytes, stored);
+        assert_eq!(loaded.total_logical_bytes, 109);
+        Ok(())
+    }
+
+    #[test]
+    fn test_duplicate_file_counts_dedup_save() -> Result<(), anyhow::Error> {
+        use crate::storage::performance::PERFORMANCE_METRICS;
+
+        let temp_dir = TempDir::new()?;
+        let src = temp_dir.path().join("dup.conf");
+        std::fs::write(&src, b"same content")?;
+        let mut manifest = Manifest::new(
+            "dedup-metrics-test".to_string(),
+            "2026-01-01T00:00:00Z".to_string(),
+            "linux".to_string(),
+        );
+        manifest.set_inline_threshold(0);
+
+        manifest.create_blob_from_file(&src, "app:test", None)?;
+        let before = PERFORMANCE_METRICS.get_stats();
+        manifest.create_blob_from_file(&src, "app:test", None)?;
+        let after = PERFORMANCE_METRICS.get_stats();
+
+        assert_eq!(manifest.entries.len(), 2);
+        assert_eq!(manifest.entries[0].blob_id, manifest.entries[1].blob_id);
+        assert!(after.dedup_saves > before.dedup_saves);
+        assert!(after.files_processed > before.files_processed);
+        Ok(())
+    }
+
+    #[test]
+    fn test_deduped_backup_passes_integrity_checks() -> Result<(), anyhow::Error> {
+        let temp_dir = TempDir::new()?;
+        let src = temp_dir.path().join("shared.conf");
+        std::fs::write(&src, b"content both backups hold")?;
+        let storage_dir = temp_dir.path().join("storage");
+        let mut first = Manifest::new_in("first".to_string(), &storage_dir);
+        first.set_inline_threshold(0);
+        first.create_blob_from_file(&src, "app:test", None)?;
+        first.save()?;
+
+        let mut second = Manifest::new_in("second".to_string(), &storage_dir);
+        second.set_inline_threshold(0);
+        second.create_blob_from_file(&src, "app:test", None)?;
+        second.save()?;
+
+        let second = Manifest::load_in(&storage_dir, "second")?;
+        assert_eq!(second.entries[0].blob_id, first.entries[0].blob_id);
+        assert!(second.blobs.is_empty());
+        assert!(second.verify_backup_integrity_with_dir(Some(storage_dir.clone()))?);
+        second.verify_backup_deep_with_dir(Some(storage_dir))?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_batch_entries_record_their_relative_path() -> Result<(), anyhow::Error> {
+        let temp_dir = TempDir::new()?;
+        let app_root = temp_dir.path().join("app");
+        std::fs::create_dir_all(app_root.join("themes"))?;
+        std::fs::write(app_root.join("themes").join("dark.toml"), b"bg = \"#000\"")?;
+        std::fs::write(temp_dir.path().join("outside.conf"), b"x = 1")?;
+        let mut manifest =
+            Manifest::new_in("batch-relative-test".to_string(), &temp_dir.path().join("storage"));
+
+        manifest.create_blobs_from_files_batch(vec![
+            (app_root.join("themes").join("dark.toml"), "app:test".to_string(), Some(app_root.clone())),
+            (temp_dir.path().join("outside.conf"), "app:test".to_string(), Some(app_root)),
+        ])?;
+        assert_eq!(manifest.entries[0].relative_path.as_deref(), Some("themes/dark.toml"));
+        assert_eq!(manifest.entries[1].relative_path, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_small_files_are_inlined_in_their_entry() -> Result<(), anyhow::Error> {
+        let temp_dir = TempDir::new()?;
+        let small = temp_dir.path().join("small.conf");
+        std::fs::write(&small, b"theme = dark")?;
+        let large = temp_dir.path().join("large.conf");
+        std::fs::write(&large, "x = 1\n".repeat(2048))?;
+
+        let storage_dir = temp_dir.path().join("storage");
+        let mut manifest = Manifest::new_in("inline-test".to_string(), &storage_dir);
+        manifest.create_blob_from_file(&small, "app:test", None)?;
+        manifest.create_blob_from_file(&large, "app:test", None)?;
+        manifest.save()?;
+
+        let manifest = Manifest::load_in(&storage_dir, "inline-test")?;
+        assert!(manifest.entries[0].inline_data.is_some());
+        assert_eq!(manifest.entries[0].stored_blob_id(), None);
+        assert!(manifest.entries[1].inline_data.is_none());
+        assert_eq!(manifest.blobs.len(), 1);
+        assert!(manifest.verify_backup_integrity_with_dir(Some(storage_dir.clone()))?);
+        manifest.verify_backup_deep_with_dir(Some(storage_dir.clone()))?;
+
+        let dest = temp_dir.path().join("restored.conf");
+        manifest.restore_blob_to(&manifest.entries[0], &dest)?;
+        assert_eq!(std::fs::read(&dest)?, b"theme = dark");
+        assert_eq!(manifest.read_entry(&manifest.entries[0])?, b"theme = dark");
+        Ok(())
+    }
+
+    #[test]
+    fn test_cancelled_save_leaves_the_chain_matching_the_manifest() -> Result<(), anyhow::Error> {
+        let temp_dir = TempDir::new()?;
+        let first = temp_dir.path().join("first.conf");
+        std::fs::write(&first, "first = 1\n".repeat(1024))?;
+        let second = temp_dir.path().join("second.conf");
+        std::fs::write(&second, "second = 2\n".repeat(1024))?;
+
+        let storage_dir = temp_dir.path().join("storage");
+        let mut manifest = Manifest::new_in("cancel-test".to_string(), &storage_dir);
+        manifest.create_blob_from_file(&first, "app:test", None)?;
+        manifest.save()?;
+
+        // What a save cancelled after its first file does
+        let mut manifest = Manifest::load_in(&storage_dir, "cancel-test")?;
+        let checkpoint = manifest.save_checkpoint()?;
+        manifest.create_blob_from_file(&second, "app:test", None)?;
+        let on_disk = Manifest::load_in(&storage_dir, "cancel-test")?;
+        assert!(!matches!(on_disk.verify_blob_chain_integrity(), Ok(true)));
+        manifest.roll_back_to(&checkpoint)?;
+
+        let manifest = Manifest::load_in(&storage_dir, "cancel-test")?;
+        assert!(manifest.verify_blob_chain_integrity()?);
+        assert!(manifest.verify_backup_integrity()?);
+        assert!(manifest.orphan_blobs().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_directory_files_dedup_between_snapshots() -> Result<(), anyhow::Error> {
+        let temp_dir = TempDir::new()?;
+        let themes = temp_dir.path().join("themes");
+        std::fs::create_dir_all(themes.join("dark"))?;
+        let mut seed = 7u64;
+        for i in 0..30 {
+            let colors: Vec<String> = (0..32)
+                .map(|_| {
+                    seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                    format!("#{:06x}", seed >> 40)
+                })
+                .collect();
+            let dir = if i % 3 == 0 { themes.join("dark") } else { themes.clone() };
+            std::fs::write(dir.join(format!("scheme{}.toml", i)), colors.join("\n"))?;
+        }
+
+        // Two consecutive snapshots with one scheme edited in between
+        let new_bytes = |manifest: &mut Manifest, per_file: bool| -> Result<u64, anyhow::Error> {
+            manifest.set_inline_threshold(0);
+            let snapshot = |manifest: &mut Manifest| match per_file {
+                true => manifest.create_blob_from_directory(&themes, "app:test", Some(&themes)),
+                false => manifest.create_single_blob_from_directory(&themes, "app:test", None),
+            };
+            std::fs::write(themes.join("scheme1.toml"), "#000000")?;
+            snapshot(manifest)?;
+            let before = manifest.blobs.values().map(|b| b.get_size()).sum::<u64>();
+            std::fs::write(themes.join("scheme1.toml"), "#ffffff")?;
+            snapshot(manifest)?;
+            Ok(manifest.blobs.values().map(|b| b.get_size()).sum::<u64>() - before)
+        };
+        let storage_dir = temp_dir.path().join("storage");
+        let mut per_file = Manifest::new_in("directory-dedup-test".to_string(), &storage_dir);
+        let per_file_bytes = new_bytes(&mut per_file, true)?;
+        let mut single = Manifest::new_in("directory-single-blob-test".to_string(), &storage_dir);
+        let single_bytes = new_bytes(&mut single, false)?;
+        println!(
+            "Second snapshot stored {} bytes per file vs {} bytes as one blob",
+            per_file_bytes, single_bytes
+        );
+
+        assert_eq!(per_file.entries.len(), 60);
+        assert_eq!(per_file.blobs.len(), 31);
+        let relative = per_file.entries[0].relative_path.as_deref();
+        assert_eq!(relative, Some("dark/scheme0.toml"));
+        assert!(per_file_bytes * 10 < single_bytes);
+        Ok(())
+    }
+
+    #[test]
+    fn test_blobs_compressed_with_trained_dictionary() -> Result<(), anyhow::Error> {
+        let temp_dir = TempDir::new()?;
+        let paths: Vec<_> = (0..40)
+            .map(|i| {
+                let path = temp_dir.path().join(format!("profile{}.json", i));
+                let content = format!(
+                    "{{\n  \"name\": \"profile {}\",\n  \"font\": \"JetBrains Mono\",\n  \
+                     \"fontSize\": {},\n  \"cursorStyle\": \"bar\",\n  \"theme\": \"dark\"\n}}\n",
+                    i,
+                    10 + i % 5
+                );
+                std::fs::write(&path, content).map(|_| path)
+            })
+            .collect::<Result<_, _>>()?;
+        let mut manifest = Manifest::new(
+            "dictionary-test".to_string(),
+            "2026-01-01T00:00:00Z".to_string(),
+            "linux".to_string(),
+        );
+        manifest.set_inline_threshold(0);
+
+        assert!(manifest.use_compression_dictionary(&paths)?);
+        let id = manifest.compression_dictionary.clone().unwrap();
+        assert!(manifest
+            .backup_dir()?
+            .join("dictionaries")
+            .join(format!("{}.zdict", id))
+            .is_file());
+        for path in &paths[..3] {
+            manifest.create_blob_from_file(path, "app:test", None)?;
+        }
+        let blob_id = manifest.entries[1].blob_id.clone();
+        assert_eq!(manifest.blobs[&blob_id].get_dictionary(), Some(id.as_str()));
+
+        // A reloaded manifest reads the dictionary back from disk
+        let reloaded: Manifest = serde_json::from_str(&serde_json::to_string(&manifest)?)?;
+        let extracted = reloaded.extract_blob_to_dir(&blob_id, &temp_dir.path().join("out"))?;
+        assert_eq!(std::fs::read(&extracted[0])?, std::fs::read(&paths[1])?);
+
+        // Too few files to train on leaves compression unchanged
+        let mut plain = Manifest::new(
+            "dictionary-test-plain".to_string(),
+            "2026-01-01T00:00:00Z".to_string(),
+            "linux".to_string(),
+        );
+        assert!(!plain.use_compression_dictionary(&paths[..2])?);
+        assert!(plain.compression_dictionary.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_dedup_index_answers_after_first_scan() -> Result<(), anyhow::Error> {
+        use crate::storage::{dedup_index, performance::PERFORMANCE_METRICS};
+
+        let temp_dir = TempDir::new()?;
+        let storage_dir = temp_dir.path();
+        let manifest = source_backup("indexed", "2026-01-01T00:00:00Z", &[("/cfg/a.conf", b"a")]);
+        write_manifest_in(storage_dir, &manifest)?;
+        let blob_id = manifest.entries[0].blob_id.clone();
+        let sha256 = manifest.blobs[&blob_id].get_sha256().to_string();
+
+        // The first lookup has to scan the manifests
+        let lookup = dedup_index::lookup(storage_dir, &sha256)?;
+        assert!(!lookup.from_index);
+        assert_eq!(lookup.found, Some(("indexed".to_string(), blob_id)));
+
+        // Then the index answers, for stored and unknown content alike
+        assert!(dedup_index::lookup(storage_dir, &sha256)?.from_index);
+        let unknown = dedup_index::lookup(storage_dir, "not-stored")?;
+        assert!(unknown.from_index && unknown.found.is_none());
+
+        // A backup deleted behind the index's back forces a rescan
+        std::fs::remove_dir_all(storage_dir.join("indexed"))?;
+        let lookup = dedup_index::lookup(storage_dir, &sha256)?;
+        assert!(!lookup.from_index && lookup.found.is_none());
+
+        let before = PERFORMANCE_METRICS.get_stats();
+        dedup_index::find_blob(storage_dir, &sha256)?;
+        assert!(PERFORMANCE_METRICS.get_stats().cache_hits > before.cache_hits);
+        dedup_index::invalidate(storage_dir);
+        dedup_index::find_blob(storage_dir, &sha256)?;
+        assert!(PERFORMANCE_METRICS.get_stats().cache_misses > before.cache_misses);
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_to_blob_pool_drops_duplicate_blob_files() -> Result<(), anyhow::Error> {
+        use crate::storage::{health, pack, pool};
+
+        let temp_dir = TempDir::new()?;
+        let storage_dir = temp_dir.path();
+        let shared = BlobPayload::new("tar.zst".to_string(), &tar_zst_of(&[("shared", b"same")]));
+        let mut names = Vec::new();
+        for name in ["first", "second"] {
+            let mut manifest = source_backup(name, "2026-01-01T00:00:00Z", &[("/cfg/own", b"own")]);
+            manifest.add_blob_for_testing("shared".to_string(), shared.clone());
+            manifest.entries.push(Entry {
+                blob_id: "shared".to_string(),
+                target_hint: "app:test".to_string(),
+                logical_path: "/cfg/shared".to_string(),
+                tar_member: Some("shared".to_string()),
+                ..Default::default()
+            });
+            write_backup_with_blob_files(storage_dir, &manifest)?;
+            names.push((name, manifest.entries[0].blob_id.clone()));
+        }
+        // Packed blobs move too
+        pack::pack_loose_blobs(&storage_dir.join("second").join("blobs"))?;
+
+        let migration = pool::migrate_in(storage_dir)?;
+        assert_eq!(migration.backups_migrated, 2);
+        assert_eq!(migration.blobs_pooled, 3);
+        assert_eq!(migration.duplicates_removed, 1);
+        assert_eq!(migration.bytes_freed, shared.decode()?.len() as u64);
+        // Running it again finds nothing left to move
+        assert_eq!(pool::migrate_in(storage_dir)?, Default::default());
+
+        for (name, own_id) in &names {
+            assert!(!storage_dir.join(name).join("blobs").exists());
+            assert_eq!(
+                pack::read_blob_in_storage(storage_dir, name, "shared")?,
+                shared.decode()?
+            );
+            pack::read_blob_in_storage(storage_dir, name, own_id)?;
+        }
+        let report = health::health_check_in(storage_dir)?;
+        assert!(report.backups.iter().all(|backup| backup.missing_blobs.is_empty()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_created_by_version_defaults_and_newer_check() -> Result<(), anyhow::Error> {
+        use crate::storage::manifest::{get_crate_version, is_much_newer_version};
+
+        let legacy: Manifest = serde_json::from_str(
+            r#"{"name":"old","created_at":"","os_source":"linux","entries":[],"blobs":{}}"#,
+        )?;
+        assert_eq!(legacy.created_by_version, "unknown");
+        let fresh = Manifest::new("new".to_string(), String::new(), "linux".to_string());
+        assert_eq!(fresh.created_by_version, get_crate_version());
+
+        assert!(!is_much_newer_version("unknown"));
+        assert!(!is_much_newer_version(get_crate_version()));
+        assert!(is_much_newer_version("999.0.0"));
+        assert!(!is_much_newer_version("0.0.1-beta"));
+        Ok(())
+    }
+
+    /// Restoring two large blobs queued behind 400 small ones, in their
+    /// stored order vs `restore_blobs_batch`'s largest-first order. Run with
+    /// `cargo test --release -- --ignored bench_restore_largest_first`.
+    #[test]
+    #[ignore]
+    fn bench_restore_largest_first() -> Result<(), anyhow::Error> {
+        use rayon::prelude::*;
+
+        // Pseudo-random bytes so decompression and writes have real work
+        const LCG_MUL: u64 = 6364136223846793005;
+        const LCG_ADD: u64 = 1442695040888963407;
+        let noise = |len: usize, seed: u64| -> Vec<u8> {
+            let mut state = seed.wrapping_mul(LCG_MUL).wrapping_add(1);
+            (0..len)
+                .map(|_| {
+                    state = state.wrapping_mul(LCG_MUL).wrapping_add(LCG_ADD);
+                    (state >> 59) as u8
+                })
+                .collect()
+        };
+        let mut manifest = Manifest::empty("bench-restore".to_string());
+        let sizes = std::iter::repeat_n(64 * 1024, 400).chain([48 << 20, 48 << 20]);
+        for (i, size) in sizes.enumerate() {
+            let member = format!("file{:03}", i);
+            let blob_id = format!("blob{:03}", i);
+            let data = noise(size, i as u64);
+            manifest.add_blob_for_testing(
+                blob_id.clone(),
+                BlobPayload::new("tar.zst".to_string(), &tar_zst_of(&[(&member, &data)])),
+            );
+            manifest.entries.push(Entry {
+                blob_id,
+                target_hint: "app:bench".to_string(),
+                logical_path: member.clone(),
+                tar_member: Some(member),
+                ..Default::default()
+            });
+        }
+
+        let targets_in = |dir: &std::path::Path| -> Vec<(&Entry, std::path::PathBuf)> {
+            manifest
+                .entries
+                .iter()
+                .map(|entry| (entry, dir.join(&entry.logical_path)))
+                .collect()
+        };
+
+        let stored_order = TempDir::new()?;
+        let start = std::time::Instant::now();
+        targets_in(stored_order.path())
+            .into_par_iter()
+            .try_for_each(|(entry, dest)| manifest.restore_blob_to(entry, &dest))?;
+        let stored_time = start.elapsed();
+
+        let largest_first = TempDir::new()?;
+        let start = std::time::Instant::now();
+        manifest.restore_blobs_batch(targets_in(largest_first.path()), |_| Ok(()))?;
+        let sorted_time = start.elapsed();
+
+        println!("402 blobs: stored order {:?}, largest first {:?}", stored_time, sorted_time);
+        assert!(sorted_time < stored_time);
+        Ok(())
+    }
+}